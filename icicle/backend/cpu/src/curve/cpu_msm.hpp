@@ -271,8 +271,9 @@ private:
       if (negate_p_and_s) { scalar = scalar_t::neg(scalar); } // TBD: inplace
 
       for (int j = 0; j < m_precompute_factor; j++) {
-        // Handle required preprocess of base P. Note: no need to convert to montgomery. Projective point handles it)
-        const A& base = bases[m_precompute_factor * i + j];
+        // Handle required preprocess of base P
+        const A base = m_config.are_points_montgomery_form ? A::from_montgomery(bases[m_precompute_factor * i + j])
+                                                           : bases[m_precompute_factor * i + j]; // TBD: avoid copy
         if (base == A::zero()) { continue; } // TBD: why is that? can be done more efficiently?
         const A base_neg = A::neg(base);
 
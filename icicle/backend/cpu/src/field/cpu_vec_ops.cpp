@@ -451,3 +451,41 @@ eIcicleError cpu_scalar_mul(
 }
 
 REGISTER_SCALAR_MUL_VEC_BACKEND("CPU", cpu_scalar_mul<scalar_t>);
+
+
+/*********************************** SPARSE MATVEC ***********************************/
+template <typename T>
+eIcicleError cpu_sparse_matvec(
+  const Device& device,
+  const uint32_t* row_ptr,
+  const uint32_t* col_idx,
+  const T* values,
+  uint64_t nof_rows,
+  const T* vec_x,
+  const VecOpsConfig& config,
+  T* output)
+{
+  if (config.batch_size != 1) { return eIcicleError::INVALID_ARGUMENT; }
+
+  // rows are split evenly between the workers, each worker accumulates its rows independently
+  const uint64_t nof_workers = std::max(get_nof_workers(config), 1);
+  const uint64_t rows_per_worker = (nof_rows + nof_workers - 1) / nof_workers;
+  tf::Taskflow taskflow;
+  tf::Executor executor(nof_workers);
+  for (uint64_t row_start = 0; row_start < nof_rows; row_start += rows_per_worker) {
+    const uint64_t row_end = std::min(row_start + rows_per_worker, nof_rows);
+    taskflow.emplace([=]() {
+      for (uint64_t row = row_start; row < row_end; row++) {
+        T sum = T::zero();
+        for (uint32_t k = row_ptr[row]; k < row_ptr[row + 1]; k++) {
+          sum = sum + values[k] * vec_x[col_idx[k]];
+        }
+        output[row] = sum;
+      }
+    });
+  }
+  executor.run(taskflow).wait();
+  return eIcicleError::SUCCESS;
+}
+
+REGISTER_SPARSE_MATVEC_BACKEND("CPU", cpu_sparse_matvec<scalar_t>);
@@ -230,6 +230,71 @@ div_cuda(const Device& device, const E* vec_a, const E* vec_b, uint64_t size, co
   return translateCudaError(err);
 }
 
+/*============================== sparse matvec ==============================*/
+template <typename E>
+__global__ void sparse_matvec_kernel(
+  const uint32_t* row_ptr, const uint32_t* col_idx, const E* values, uint64_t nof_rows, const E* vec_x, E* result)
+{
+  uint64_t row = blockIdx.x * blockDim.x + threadIdx.x;
+  if (row < nof_rows) {
+    E sum = E::zero();
+    for (uint32_t k = row_ptr[row]; k < row_ptr[row + 1]; k++) {
+      sum = sum + values[k] * vec_x[col_idx[k]];
+    }
+    result[row] = sum;
+  }
+}
+
+template <typename E>
+cudaError_t sparse_matvec_op(
+  const uint32_t* row_ptr,
+  const uint32_t* col_idx,
+  const E* values,
+  uint64_t nof_rows,
+  const E* vec_x,
+  const VecOpsConfig& config,
+  E* result)
+{
+  CHK_INIT_IF_RETURN();
+
+  cudaStream_t cuda_stream = reinterpret_cast<cudaStream_t>(config.stream);
+  E* d_result = config.is_result_on_device ? result : allocate_on_device<E>(nof_rows * sizeof(E), cuda_stream);
+
+  uint64_t num_threads = MAX_THREADS_PER_BLOCK;
+  uint64_t num_blocks = (nof_rows + num_threads - 1) / num_threads;
+  sparse_matvec_kernel<<<num_blocks, num_threads, 0, cuda_stream>>>(row_ptr, col_idx, values, nof_rows, vec_x, d_result);
+
+  // copy back result to host if need to
+  if (!config.is_result_on_device) {
+    CHK_IF_RETURN(cudaMemcpyAsync(result, d_result, nof_rows * sizeof(E), cudaMemcpyDeviceToHost, cuda_stream));
+    CHK_IF_RETURN(cudaFreeAsync(d_result, cuda_stream));
+  }
+
+  // wait for stream to empty is not async
+  if (!config.is_async) return CHK_STICKY(cudaStreamSynchronize(cuda_stream));
+
+  return CHK_LAST();
+}
+
+template <typename E>
+eIcicleError sparse_matvec_cuda(
+  const Device& device,
+  const uint32_t* row_ptr,
+  const uint32_t* col_idx,
+  const E* values,
+  uint64_t nof_rows,
+  const E* vec_x,
+  const VecOpsConfig& config,
+  E* result)
+{
+  // the number of nonzeros and the length of vec_x are not known here, so the inputs must already be on device
+  if (!config.is_a_on_device || !config.is_b_on_device || config.batch_size != 1) {
+    return eIcicleError::INVALID_ARGUMENT;
+  }
+  cudaError_t err = sparse_matvec_op<E>(row_ptr, col_idx, values, nof_rows, vec_x, config, result);
+  return translateCudaError(err);
+}
+
 /************************************ REGISTRATION ************************************/
 
 REGISTER_VECTOR_ADD_BACKEND("CUDA", add_cuda<scalar_t>);
@@ -239,4 +304,5 @@ REGISTER_VECTOR_MUL_BACKEND("CUDA", (mul_cuda<scalar_t, scalar_t>));
 REGISTER_VECTOR_DIV_BACKEND("CUDA", div_cuda<scalar_t>);
 REGISTER_SCALAR_MUL_VEC_BACKEND("CUDA", (mul_scalar_cuda<scalar_t>));
 REGISTER_SCALAR_ADD_VEC_BACKEND("CUDA", (add_scalar_cuda<scalar_t>));
-REGISTER_SCALAR_SUB_VEC_BACKEND("CUDA", (sub_scalar_cuda<scalar_t>));
\ No newline at end of file
+REGISTER_SCALAR_SUB_VEC_BACKEND("CUDA", (sub_scalar_cuda<scalar_t>));
+REGISTER_SPARSE_MATVEC_BACKEND("CUDA", sparse_matvec_cuda<scalar_t>);
\ No newline at end of file
@@ -29,6 +29,16 @@ namespace icicle {
     const VecOpsConfig& config,
     scalar_t* output)>;
 
+  using sparseMatvecImpl = std::function<eIcicleError(
+    const Device& device,
+    const uint32_t* row_ptr,
+    const uint32_t* col_idx,
+    const scalar_t* values,
+    uint64_t nof_rows,
+    const scalar_t* vec_x,
+    const VecOpsConfig& config,
+    scalar_t* output)>;
+
   using scalarBitReverseOpImpl = std::function<eIcicleError(
     const Device& device, const scalar_t* input, uint64_t size, const VecOpsConfig& config, scalar_t* output)>;
 
@@ -174,4 +184,14 @@ namespace icicle {
       return true;                                                                                                     \
     }();                                                                                                               \
   }
+
+  void register_sparse_matvec(const std::string& deviceType, sparseMatvecImpl impl);
+
+#define REGISTER_SPARSE_MATVEC_BACKEND(DEVICE_TYPE, FUNC)                                                              \
+  namespace {                                                                                                          \
+    static bool UNIQUE(_reg_sparse_matvec) = []() -> bool {                                                            \
+      register_sparse_matvec(DEVICE_TYPE, FUNC);                                                                       \
+      return true;                                                                                                     \
+    }();                                                                                                               \
+  }
 } // namespace icicle
\ No newline at end of file
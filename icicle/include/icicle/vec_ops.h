@@ -279,4 +279,34 @@ namespace icicle {
   template <typename T>
   eIcicleError scalar_mul_vec(const T* scalar_a, const T* vec_b, uint64_t size, const VecOpsConfig& config, T* output);
 
+  // Sparse operations
+
+  /**
+   * @brief Multiplies a sparse matrix in CSR form by a dense vector.
+   *
+   * Row `i` of the matrix holds the entries `row_ptr[i]..row_ptr[i+1]` of `col_idx` and `values`, so that
+   * `output[i] = sum(values[k] * vec_x[col_idx[k]])` over that range. Empty rows produce zero.
+   *
+   * @tparam T Type of the matrix values and vector elements.
+   * @param row_ptr Pointer to the row offsets, `nof_rows + 1` entries.
+   * @param col_idx Pointer to the column of each nonzero entry.
+   * @param values Pointer to the value of each nonzero entry.
+   * @param nof_rows Number of rows in the matrix.
+   * @param vec_x Pointer to the dense input vector.
+   * @param config Configuration for the operation. `is_a_on_device` refers to the matrix arrays and
+   *               `is_b_on_device` to `vec_x`. Batching is not supported.
+   * @param output Pointer to the output vector, `nof_rows` elements.
+   * @return eIcicleError Error code indicating success or failure.
+   * @note The CUDA backend requires the matrix arrays and `vec_x` to be on device.
+   */
+  template <typename T>
+  eIcicleError sparse_matvec(
+    const uint32_t* row_ptr,
+    const uint32_t* col_idx,
+    const T* values,
+    uint64_t nof_rows,
+    const T* vec_x,
+    const VecOpsConfig& config,
+    T* output);
+
 } // namespace icicle
\ No newline at end of file
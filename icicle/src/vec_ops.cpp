@@ -176,4 +176,33 @@ namespace icicle {
   {
     return CONCAT_EXPAND(ICICLE_FFI_PREFIX, scalar_convert_montgomery)(input, size, is_to_montgomery, &config, output);
   }
+
+  /*********************************** SPARSE MATVEC ***********************************/
+
+  ICICLE_DISPATCHER_INST(SparseMatvecDispatcher, sparse_matvec, sparseMatvecImpl)
+
+  extern "C" eIcicleError CONCAT_EXPAND(ICICLE_FFI_PREFIX, sparse_matvec)(
+    const uint32_t* row_ptr,
+    const uint32_t* col_idx,
+    const scalar_t* values,
+    uint64_t nof_rows,
+    const scalar_t* vec_x,
+    const VecOpsConfig* config,
+    scalar_t* output)
+  {
+    return SparseMatvecDispatcher::execute(row_ptr, col_idx, values, nof_rows, vec_x, *config, output);
+  }
+
+  template <>
+  eIcicleError sparse_matvec(
+    const uint32_t* row_ptr,
+    const uint32_t* col_idx,
+    const scalar_t* values,
+    uint64_t nof_rows,
+    const scalar_t* vec_x,
+    const VecOpsConfig& config,
+    scalar_t* output)
+  {
+    return CONCAT_EXPAND(ICICLE_FFI_PREFIX, sparse_matvec)(row_ptr, col_idx, values, nof_rows, vec_x, &config, output);
+  }
 } // namespace icicle
\ No newline at end of file
@@ -0,0 +1,568 @@
+//! Proof-of-concept SnarkPack aggregation of Groth16 proofs of one circuit.
+//!
+//! The `n` proofs are committed to with pairing commitments, a verifier challenge `r` batches
+//! their verification equations into one, and a combined TIPP/MIPP argument (GIPA with
+//! inner-pairing products) shows in `log2(n)` rounds that the batched `prod e(A_i, B_i)^(r^i)`
+//! and `sum r^i * C_i` match the commitments. The keys the argument folds are checked with
+//! KZG openings, so the verifier's work is logarithmic in `n` apart from the public inputs.
+//!
+//! The commitment keys are powers of a single secret `tau`, taken from a `.ptau`, whereas
+//! SnarkPack pairs two independent secrets. With one secret the commitment to `(A, B)` is not
+//! binding, so aggregates over a ptau-derived SRS are only fit for testing.
+
+use icicle_core::{
+    curve::{Affine, Curve},
+    pairing::pairing,
+    traits::{Arithmetic, FieldImpl},
+};
+use num_bigint::BigUint;
+use rayon::prelude::*;
+use sha3::{Digest, Keccak256};
+use std::io;
+
+use crate::{
+    cache::VerificationKey,
+    conversions::{deserialize_g1_affine, deserialize_g2_affine},
+    icicle_helper::msm_host_helper,
+    proof_helper::{parse_public_signals, Proof},
+    ptau::PowersOfTau,
+    C1, F, G1, G2, GT,
+};
+
+/// Powers of a secret `tau` in both groups: `2n` in G1 and `n` in G2 aggregate up to `n`
+/// proofs.
+#[derive(Clone, Debug)]
+pub struct AggregationSrs {
+    pub g1: Vec<G1>,
+    pub g2: Vec<G2>,
+}
+
+/// The part of an `AggregationSrs` the verifier needs.
+#[derive(Clone, Copy, Debug)]
+pub struct AggregationVerifierKey {
+    pub g1: G1,
+    pub tau_g1: G1,
+    pub g2: G2,
+    pub tau_g2: G2,
+}
+
+impl AggregationSrs {
+    /// Takes the SRS for up to `max_proofs` proofs, a power of two, from a `.ptau` file of
+    /// power at least `log2(max_proofs) + 1`.
+    pub fn from_ptau(path: &str, max_proofs: usize) -> io::Result<Self> {
+        if max_proofs < 2 || !max_proofs.is_power_of_two() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "The number of proofs must be a power of two of at least 2",
+            ));
+        }
+
+        let ptau = PowersOfTau::read(path, 2 * max_proofs, max_proofs)?;
+
+        Ok(Self {
+            g1: ptau.tau_g1,
+            g2: ptau.tau_g2,
+        })
+    }
+
+    pub fn max_proofs(&self) -> usize {
+        self.g2.len()
+    }
+
+    pub fn verifier_key(&self) -> AggregationVerifierKey {
+        AggregationVerifierKey {
+            g1: self.g1[0],
+            tau_g1: self.g1[1],
+            g2: self.g2[0],
+            tau_g2: self.g2[1],
+        }
+    }
+}
+
+/// Messages of one GIPA round, each a pair of cross terms: the left half of one vector with
+/// the right half of the other, then the other way round.
+#[derive(Clone, Debug)]
+pub struct GipaRound {
+    /// Commitments to `(A, B)`.
+    pub com_ab: (GT, GT),
+    /// Commitments to `C`.
+    pub com_c: (GT, GT),
+    /// Pairing products of `A` and `B`.
+    pub ip_ab: (GT, GT),
+    /// Multi-exponentiations of `C` by the powers of `r`.
+    pub agg_c: (G1, G1),
+}
+
+/// Aggregate of `n` Groth16 proofs, with `log2(n)` GIPA rounds.
+#[derive(Clone, Debug)]
+pub struct AggregateProof {
+    /// `prod e(A_i, v_i) * e(w_i, B_i)`
+    pub com_ab: GT,
+    /// `prod e(C_i, v_i)`
+    pub com_c: GT,
+    /// `prod e(A_i, B_i)^(r^i)`
+    pub ip_ab: GT,
+    /// `sum r^i * C_i`
+    pub agg_c: G1,
+    pub rounds: Vec<GipaRound>,
+    pub final_a: G1,
+    pub final_b: G2,
+    pub final_c: G1,
+    /// The folded commitment keys and their KZG openings.
+    pub final_v: G2,
+    pub final_w: G1,
+    pub opening_v: G2,
+    pub opening_w: G1,
+}
+
+/// Keccak-256 Fiat-Shamir transcript; each challenge hashes the previous one together with
+/// everything appended since.
+struct Transcript {
+    hasher: Keccak256,
+}
+
+impl Transcript {
+    fn new() -> Self {
+        let mut hasher = Keccak256::new();
+        hasher.update(b"icicle-snark snarkpack");
+        Self { hasher }
+    }
+
+    fn append_scalar(&mut self, scalar: &F) {
+        self.hasher.update(scalar.to_bytes_le());
+    }
+
+    fn append_g1(&mut self, point: &G1) {
+        self.hasher.update(point.x.to_bytes_le());
+        self.hasher.update(point.y.to_bytes_le());
+    }
+
+    fn append_g2(&mut self, point: &G2) {
+        self.hasher.update(point.x.to_bytes_le());
+        self.hasher.update(point.y.to_bytes_le());
+    }
+
+    fn append_gt(&mut self, value: &GT) {
+        self.hasher.update(value.to_bytes_le());
+    }
+
+    fn append_round(&mut self, round: &GipaRound) {
+        for value in [&round.com_ab, &round.com_c, &round.ip_ab] {
+            self.append_gt(&value.0);
+            self.append_gt(&value.1);
+        }
+        self.append_g1(&round.agg_c.0);
+        self.append_g1(&round.agg_c.1);
+    }
+
+    /// A non-zero challenge, so that it can be inverted.
+    fn challenge(&mut self) -> F {
+        let modulus = BigUint::from_bytes_le(&(F::zero() - F::one()).to_bytes_le()) + 1u32;
+        loop {
+            let digest = self.hasher.finalize_reset();
+            self.hasher.update(digest);
+            let challenge = BigUint::from_bytes_le(&digest) % &modulus;
+            if challenge != BigUint::ZERO {
+                return F::from_bytes_le(&challenge.to_bytes_le());
+            }
+        }
+    }
+}
+
+fn pairing_product(a: &[G1], b: &[G2]) -> GT {
+    a.par_iter()
+        .zip(b.par_iter())
+        .map(|(a, b)| pairing(a, b).unwrap())
+        .reduce(GT::one, |x, y| x * y)
+}
+
+fn gt_pow(base: GT, exp: &F) -> GT {
+    let mut result = GT::one();
+    for byte in exp.to_bytes_le().iter().rev() {
+        for bit in (0..8).rev() {
+            result = result.sqr();
+            if (byte >> bit) & 1 == 1 {
+                result = result * base;
+            }
+        }
+    }
+    result
+}
+
+fn powers(base: F, n: usize) -> Vec<F> {
+    let mut powers = Vec::with_capacity(n);
+    let mut power = F::one();
+    for _ in 0..n {
+        powers.push(power);
+        power = power * base;
+    }
+    powers
+}
+
+/// `left + x * right` of the two halves of `points`.
+fn fold<C: Curve<ScalarField = F>>(points: &[Affine<C>], x: &F) -> Vec<Affine<C>> {
+    let (left, right) = points.split_at(points.len() / 2);
+    left.par_iter()
+        .zip(right.par_iter())
+        .map(|(l, r)| (l.to_projective() + r.to_projective() * *x).into())
+        .collect()
+}
+
+fn fold_scalars(scalars: &[F], x: &F) -> Vec<F> {
+    let (left, right) = scalars.split_at(scalars.len() / 2);
+    left.iter().zip(right).map(|(l, r)| *l + *r * *x).collect()
+}
+
+/// Coefficients of `prod_j (1 + factors[j] * X^(n / 2^(j + 1)))`, the polynomial in `tau`
+/// that the powers `tau^i` fold into when round `j` folds with `factors[j]`.
+fn folding_polynomial(factors: &[F]) -> Vec<F> {
+    let mut coeffs = vec![F::one()];
+    for factor in factors.iter().rev() {
+        let high: Vec<F> = coeffs.iter().map(|c| *c * *factor).collect();
+        coeffs.extend(high);
+    }
+    coeffs
+}
+
+fn eval_folding_polynomial(factors: &[F], z: &F) -> F {
+    let mut result = F::one();
+    let mut power = *z;
+    for factor in factors.iter().rev() {
+        result = result * (F::one() + *factor * power);
+        power = power * power;
+    }
+    result
+}
+
+/// Commits to `(f(X) - f(z)) / (X - z)`, the KZG proof that `f` opens to `f(z)` at `z`.
+fn open<C: Curve<ScalarField = F> + icicle_core::msm::MSM<C>>(
+    coeffs: &[F],
+    z: &F,
+    powers: &[Affine<C>],
+) -> Affine<C> {
+    let mut quotient = vec![F::zero(); coeffs.len() - 1];
+    let mut carry = F::zero();
+    for i in (1..coeffs.len()).rev() {
+        carry = coeffs[i] + carry * *z;
+        quotient[i - 1] = carry;
+    }
+    msm_host_helper(&quotient, &powers[..quotient.len()]).into()
+}
+
+fn invalid_input(message: &str) -> Box<dyn std::error::Error> {
+    Box::new(io::Error::new(io::ErrorKind::InvalidInput, message.to_string()))
+}
+
+fn check_count(n: usize, public: &[Vec<String>]) -> Result<(), Box<dyn std::error::Error>> {
+    if n < 2 || !n.is_power_of_two() {
+        return Err(invalid_input("The number of proofs must be a power of two of at least 2"));
+    }
+    if public.len() != n {
+        return Err(invalid_input("Expected one set of public signals per proof"));
+    }
+    Ok(())
+}
+
+fn append_statement(transcript: &mut Transcript, com_ab: &GT, com_c: &GT, public: &[Vec<String>]) {
+    transcript.append_gt(com_ab);
+    transcript.append_gt(com_c);
+    for signals in public {
+        for signal in parse_public_signals(signals) {
+            transcript.append_scalar(&signal);
+        }
+    }
+}
+
+fn append_finals(transcript: &mut Transcript, aggregate: &AggregateProof) {
+    transcript.append_g1(&aggregate.final_a);
+    transcript.append_g2(&aggregate.final_b);
+    transcript.append_g1(&aggregate.final_c);
+    transcript.append_g2(&aggregate.final_v);
+    transcript.append_g1(&aggregate.final_w);
+}
+
+pub fn aggregate_helper(
+    proofs: &[Proof],
+    public: &[Vec<String>],
+    srs: &AggregationSrs,
+) -> Result<AggregateProof, Box<dyn std::error::Error>> {
+    let n = proofs.len();
+    check_count(n, public)?;
+    if n > srs.max_proofs() {
+        return Err(invalid_input("The SRS is too small for this many proofs"));
+    }
+
+    let mut a: Vec<G1> = proofs.iter().map(|p| deserialize_g1_affine(&p.pi_a)).collect();
+    let b: Vec<G2> = proofs.iter().map(|p| deserialize_g2_affine(&p.pi_b)).collect();
+    let mut c: Vec<G1> = proofs.iter().map(|p| deserialize_g1_affine(&p.pi_c)).collect();
+    let mut v = srs.g2[..n].to_vec();
+    let w = &srs.g1[n..2 * n];
+
+    let com_ab = pairing_product(&a, &v) * pairing_product(w, &b);
+    let com_c = pairing_product(&c, &v);
+
+    let mut transcript = Transcript::new();
+    append_statement(&mut transcript, &com_ab, &com_c, public);
+    let r = transcript.challenge();
+
+    // B_i * r^i against w_i / r^i keeps the commitment to (A, B) unchanged
+    let mut r_powers = powers(r, n);
+    let r_inv_powers = powers(r.inv(), n);
+    let mut b: Vec<G2> = b
+        .par_iter()
+        .zip(r_powers.par_iter())
+        .map(|(b, r)| (b.to_projective() * *r).into())
+        .collect();
+    let mut w: Vec<G1> = w
+        .par_iter()
+        .zip(r_inv_powers.par_iter())
+        .map(|(w, r)| (w.to_projective() * *r).into())
+        .collect();
+
+    let ip_ab = pairing_product(&a, &b);
+    let agg_c: G1 = msm_host_helper(&r_powers, &c).into();
+    transcript.append_gt(&ip_ab);
+    transcript.append_g1(&agg_c);
+
+    let mut rounds = Vec::new();
+    let mut challenges = Vec::new();
+    while a.len() > 1 {
+        let half = a.len() / 2;
+        let (a_l, a_r) = a.split_at(half);
+        let (b_l, b_r) = b.split_at(half);
+        let (c_l, c_r) = c.split_at(half);
+        let (r_l, r_r) = r_powers.split_at(half);
+        let (v_l, v_r) = v.split_at(half);
+        let (w_l, w_r) = w.split_at(half);
+
+        let round = GipaRound {
+            com_ab: (
+                pairing_product(a_r, v_l) * pairing_product(w_r, b_l),
+                pairing_product(a_l, v_r) * pairing_product(w_l, b_r),
+            ),
+            com_c: (pairing_product(c_r, v_l), pairing_product(c_l, v_r)),
+            ip_ab: (pairing_product(a_r, b_l), pairing_product(a_l, b_r)),
+            agg_c: (
+                msm_host_helper(r_l, c_r).into(),
+                msm_host_helper(r_r, c_l).into(),
+            ),
+        };
+        transcript.append_round(&round);
+        let x = transcript.challenge();
+        let x_inv = x.inv();
+
+        a = fold(&a, &x);
+        b = fold(&b, &x_inv);
+        c = fold(&c, &x);
+        r_powers = fold_scalars(&r_powers, &x_inv);
+        v = fold(&v, &x_inv);
+        w = fold(&w, &x);
+
+        rounds.push(round);
+        challenges.push(x);
+    }
+
+    let mut aggregate = AggregateProof {
+        com_ab,
+        com_c,
+        ip_ab,
+        agg_c,
+        rounds,
+        final_a: a[0],
+        final_b: b[0],
+        final_c: c[0],
+        final_v: v[0],
+        final_w: w[0],
+        opening_v: G2::zero(),
+        opening_w: G1::zero(),
+    };
+    append_finals(&mut transcript, &aggregate);
+    let z = transcript.challenge();
+
+    // v folds into f_v(tau) * g2 and w into tau^n * f_x(tau / r) * g1
+    let inverses: Vec<F> = challenges.iter().map(|x| x.inv()).collect();
+    aggregate.opening_v = open(&folding_polynomial(&inverses), &z, &srs.g2);
+
+    let mut coeffs_w = vec![F::zero(); n];
+    coeffs_w.extend(
+        folding_polynomial(&challenges)
+            .iter()
+            .zip(&r_inv_powers)
+            .map(|(c, r)| *c * *r),
+    );
+    aggregate.opening_w = open(&coeffs_w, &z, &srs.g1);
+
+    Ok(aggregate)
+}
+
+pub fn aggregate_verify_helper(
+    aggregate: &AggregateProof,
+    public: &[Vec<String>],
+    vk: &VerificationKey,
+    srs_vk: &AggregationVerifierKey,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let n = public.len();
+    check_count(n, public)?;
+    if let Some(signals) = public.iter().find(|signals| signals.len() != vk.n_public) {
+        return Err(invalid_input(&format!(
+            "Invalid number of public signals. Circuit: {}, given: {}",
+            vk.n_public,
+            signals.len()
+        )));
+    }
+    if aggregate.rounds.len() != n.trailing_zeros() as usize {
+        return Ok(false);
+    }
+
+    let mut transcript = Transcript::new();
+    append_statement(&mut transcript, &aggregate.com_ab, &aggregate.com_c, public);
+    let r = transcript.challenge();
+    transcript.append_gt(&aggregate.ip_ab);
+    transcript.append_g1(&aggregate.agg_c);
+
+    let mut com_ab = aggregate.com_ab;
+    let mut com_c = aggregate.com_c;
+    let mut ip_ab = aggregate.ip_ab;
+    let mut agg_c = aggregate.agg_c.to_projective();
+    let mut challenges = Vec::new();
+    for round in &aggregate.rounds {
+        transcript.append_round(round);
+        let x = transcript.challenge();
+        let x_inv = x.inv();
+
+        com_ab = gt_pow(round.com_ab.0, &x) * com_ab * gt_pow(round.com_ab.1, &x_inv);
+        com_c = gt_pow(round.com_c.0, &x) * com_c * gt_pow(round.com_c.1, &x_inv);
+        ip_ab = gt_pow(round.ip_ab.0, &x) * ip_ab * gt_pow(round.ip_ab.1, &x_inv);
+        agg_c = round.agg_c.0.to_projective() * x + agg_c + round.agg_c.1.to_projective() * x_inv;
+
+        challenges.push(x);
+    }
+    append_finals(&mut transcript, aggregate);
+    let z = transcript.challenge();
+
+    // the folded vectors open the folded commitments
+    let inverses: Vec<F> = challenges.iter().map(|x| x.inv()).collect();
+    let final_r = eval_folding_polynomial(&inverses, &r);
+    let gipa = com_ab
+        == pairing(&aggregate.final_a, &aggregate.final_v).unwrap()
+            * pairing(&aggregate.final_w, &aggregate.final_b).unwrap()
+        && com_c == pairing(&aggregate.final_c, &aggregate.final_v).unwrap()
+        && ip_ab == pairing(&aggregate.final_a, &aggregate.final_b).unwrap()
+        && G1::from(agg_c) == G1::from(aggregate.final_c.to_projective() * final_r);
+
+    // e(g1, v - f_v(z) * g2) = e(tau_g1 - z * g1, opening_v)
+    let g1 = srs_vk.g1.to_projective();
+    let g2 = srs_vk.g2.to_projective();
+    let f_v = eval_folding_polynomial(&inverses, &z);
+    let kzg_v = pairing(&srs_vk.g1, &(aggregate.final_v.to_projective() - g2 * f_v).into()).unwrap()
+        == pairing(&(srs_vk.tau_g1.to_projective() - g1 * z).into(), &aggregate.opening_v).unwrap();
+
+    // e(w - f_w(z) * g1, g2) = e(opening_w, tau_g2 - z * g2)
+    let mut z_n = z;
+    for _ in 0..n.trailing_zeros() {
+        z_n = z_n * z_n;
+    }
+    let f_w = z_n * eval_folding_polynomial(&challenges, &(z * r.inv()));
+    let kzg_w = pairing(&(aggregate.final_w.to_projective() - g1 * f_w).into(), &srs_vk.g2).unwrap()
+        == pairing(&aggregate.opening_w, &(srs_vk.tau_g2.to_projective() - g2 * z).into()).unwrap();
+
+    // prod e(A_i, B_i)^(r^i) = e(alpha, beta)^(sum r^i) * e(sum r^i * IC_i, gamma) * e(agg_c, delta)
+    let r_powers = powers(r, n);
+    let mut ic_scalars = vec![F::zero(); vk.ic.len()];
+    for (signals, r) in public.iter().zip(&r_powers) {
+        ic_scalars[0] = ic_scalars[0] + *r;
+        let signals = parse_public_signals(signals);
+        for (scalar, signal) in ic_scalars[1..].iter_mut().zip(signals) {
+            *scalar = *scalar + *r * signal;
+        }
+    }
+    let ic: G1 = msm_host_helper::<C1>(&ic_scalars, &vk.ic).into();
+    let groth16 = aggregate.ip_ab
+        == gt_pow(pairing(&vk.vk_alpha_1, &vk.vk_beta_2).unwrap(), &ic_scalars[0])
+            * pairing(&ic, &vk.vk_gamma_2).unwrap()
+            * pairing(&aggregate.agg_c, &vk.vk_delta_2).unwrap();
+
+    Ok(gipa && kzg_v && kzg_w && groth16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proof_helper::tests::{signals, toy_proofs};
+    use crate::C2;
+
+    fn test_srs(max_proofs: usize) -> AggregationSrs {
+        let tau = F::from_u32(0x1234_5678);
+        let g1 = C1::get_generator();
+        let g2 = C2::get_generator();
+        let tau_powers = powers(tau, 2 * max_proofs);
+
+        AggregationSrs {
+            g1: tau_powers.iter().map(|&t| (g1 * t).into()).collect(),
+            g2: tau_powers[..max_proofs].iter().map(|&t| (g2 * t).into()).collect(),
+        }
+    }
+
+    fn statements(n: u32) -> Vec<Vec<F>> {
+        (0..n)
+            .map(|i| vec![F::from_u32(i + 1), F::from_u32(100 + i)])
+            .collect()
+    }
+
+    #[test]
+    fn test_aggregate_verifies() {
+        let srs = test_srs(8);
+        let statements = statements(4);
+        let publics: Vec<&[F]> = statements.iter().map(|s| &s[..]).collect();
+        let (proofs, vk) = toy_proofs(&publics);
+        let public: Vec<Vec<String>> = statements.iter().map(|s| signals(s)).collect();
+
+        let aggregate = aggregate_helper(&proofs, &public, &srs).unwrap();
+
+        assert_eq!(aggregate.rounds.len(), 2);
+        assert!(aggregate_verify_helper(&aggregate, &public, &vk, &srs.verifier_key()).unwrap());
+    }
+
+    #[test]
+    fn test_aggregate_rejects_other_statement() {
+        let srs = test_srs(2);
+        let statements = statements(2);
+        let publics: Vec<&[F]> = statements.iter().map(|s| &s[..]).collect();
+        let (proofs, vk) = toy_proofs(&publics);
+        let mut public: Vec<Vec<String>> = statements.iter().map(|s| signals(s)).collect();
+
+        let aggregate = aggregate_helper(&proofs, &public, &srs).unwrap();
+        public[1][0] = "7".to_string();
+
+        assert!(!aggregate_verify_helper(&aggregate, &public, &vk, &srs.verifier_key()).unwrap());
+    }
+
+    #[test]
+    fn test_aggregate_rejects_invalid_proof() {
+        let srs = test_srs(2);
+        let statements = statements(2);
+        let publics: Vec<&[F]> = statements.iter().map(|s| &s[..]).collect();
+        let (mut proofs, vk) = toy_proofs(&publics);
+        let public: Vec<Vec<String>> = statements.iter().map(|s| signals(s)).collect();
+        proofs[0].pi_c = proofs[1].pi_c.clone();
+
+        let aggregate = aggregate_helper(&proofs, &public, &srs).unwrap();
+
+        assert!(!aggregate_verify_helper(&aggregate, &public, &vk, &srs.verifier_key()).unwrap());
+    }
+
+    #[test]
+    fn test_aggregate_rejects_wrong_signal_count() {
+        let srs = test_srs(2);
+        let statements = statements(2);
+        let publics: Vec<&[F]> = statements.iter().map(|s| &s[..]).collect();
+        let (proofs, vk) = toy_proofs(&publics);
+        let mut public: Vec<Vec<String>> = statements.iter().map(|s| signals(s)).collect();
+
+        let aggregate = aggregate_helper(&proofs, &public, &srs).unwrap();
+        public[1].push("0".to_string());
+
+        let err = aggregate_verify_helper(&aggregate, &public, &vk, &srs.verifier_key()).unwrap_err();
+        assert!(err.to_string().contains("Invalid number of public signals"));
+    }
+}
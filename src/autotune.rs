@@ -0,0 +1,255 @@
+use icicle_bn254::curve::ScalarCfg;
+use icicle_core::curve::{Affine, Curve};
+use icicle_core::msm::MSM;
+use icicle_core::traits::GenerateRandom;
+use icicle_runtime::memory::{DeviceVec, HostSlice};
+use icicle_runtime::stream::IcicleStream;
+use icicle_runtime::Device;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::cache::{default_window, upload_bases, BasesRef, MsmParams, MsmSettings};
+use crate::icicle_helper::{msm_bases_helper, precompute_bases_helper};
+use crate::{F, G1, G2};
+
+/// Timed runs per candidate, after one warm-up run.
+const RUNS: u32 = 3;
+
+const PRECOMPUTE_FACTORS: [i32; 2] = [2, 4];
+const LARGE_BUCKET_FACTORS: [i32; 3] = [2, 5, 10];
+
+/// Bases of the five commitment MSMs; only their sizes and the device key the saved results.
+pub struct MsmBases<'a> {
+    pub a: &'a [G1],
+    pub b1: &'a [G1],
+    pub b: &'a [G2],
+    pub c: &'a [G1],
+    pub h: &'a [G1],
+}
+
+/// Returns the settings tuned earlier for the bases of `zkey` on the active device, or tunes
+/// them and saves them next to the zkey for later caches.
+///
+/// Precomputation is only tried when `precompute` is set, i.e. when the bases are not
+/// streamed from the zkey.
+pub fn load_or_tune(
+    zkey: &str,
+    points: &MsmBases,
+    precompute: bool,
+) -> Result<MsmSettings, Box<dyn std::error::Error>> {
+    let device = icicle_runtime::get_active_device().unwrap();
+    let device_type = device.get_device_type();
+    let path = settings_path(Path::new(zkey), &device, points, precompute);
+
+    if let Ok(file) = fs::read_to_string(&path) {
+        if let Ok(settings) = serde_json::from_str(&file) {
+            return Ok(settings);
+        }
+    }
+
+    let on_cpu = device_type == "CPU";
+    let cuda = device_type == "CUDA";
+
+    let settings = MsmSettings {
+        a: tune_section(points.a, on_cpu, cuda, precompute),
+        b1: tune_section(points.b1, on_cpu, cuda, precompute),
+        b: tune_section(points.b, on_cpu, cuda, precompute),
+        c: tune_section(points.c, on_cpu, cuda, precompute),
+        h: tune_section(points.h, on_cpu, cuda, precompute),
+    };
+
+    // several workers may tune the same circuit at once, so publish the file atomically
+    let tmp_path = path.with_extension(format!("{:?}.tmp", std::thread::current().id()));
+    fs::write(&tmp_path, serde_json::to_string_pretty(&settings)?)?;
+    fs::rename(&tmp_path, &path)?;
+
+    Ok(settings)
+}
+
+/// `<zkey>.msm_tuning_<device type>_<device id>_<MSM sizes>.json`, in the directory of the zkey.
+/// Devices of the same type can be different models, so each one is tuned on its own.
+fn settings_path(zkey: &Path, device: &Device, points: &MsmBases, precompute: bool) -> PathBuf {
+    let mode = if precompute { "" } else { "_streamed" };
+    let zkey_name = zkey.file_name().map_or("zkey".into(), |name| name.to_string_lossy());
+    zkey.with_file_name(format!(
+        "{}.msm_tuning_{}_{}_{}_{}_{}_{}_{}{}.json",
+        zkey_name,
+        device.get_device_type(),
+        device.id,
+        points.a.len(),
+        points.b1.len(),
+        points.b.len(),
+        points.c.len(),
+        points.h.len(),
+        mode
+    ))
+}
+
+/// Coordinate search: window first, then precomputation around the best window, then the
+/// CUDA bucket knobs on top of the best of those.
+fn tune_section<C: Curve<ScalarField = F> + MSM<C>>(
+    points: &[Affine<C>],
+    on_cpu: bool,
+    cuda: bool,
+    precompute: bool,
+) -> MsmParams {
+    if points.is_empty() {
+        return MsmParams::default();
+    }
+
+    let mut stream = IcicleStream::create().unwrap();
+
+    let scalars = ScalarCfg::generate_random(points.len());
+    let mut d_scalars = DeviceVec::device_malloc_async(scalars.len(), &stream).unwrap();
+    d_scalars
+        .copy_from_host_async(HostSlice::from_slice(&scalars), &stream)
+        .unwrap();
+
+    let uploaded = (!on_cpu).then(|| upload_bases(points, &stream));
+
+    let time = |params: &MsmParams| -> Option<Duration> {
+        let precomputed = if params.precompute_factor > 1 {
+            Some(precompute_bases_helper(points, params, &stream).ok()?)
+        } else {
+            None
+        };
+        let bases = match (&precomputed, &uploaded) {
+            (Some(points), _) => BasesRef::Precomputed {
+                points,
+                factor: params.precompute_factor as usize,
+            },
+            (None, Some(points)) => BasesRef::Device(points),
+            (None, None) => BasesRef::Host(points),
+        };
+
+        let run = || {
+            msm_bases_helper(&d_scalars[..], bases, params, 1, &stream);
+            stream.synchronize().unwrap();
+        };
+        run();
+        let start = Instant::now();
+        for _ in 0..RUNS {
+            run();
+        }
+        Some(start.elapsed() / RUNS)
+    };
+
+    let consider = |best: &mut (MsmParams, Duration), candidate: MsmParams| {
+        if let Some(elapsed) = time(&candidate) {
+            if elapsed < best.1 {
+                *best = (candidate, elapsed);
+            }
+        }
+    };
+
+    let mut best = (MsmParams::default(), time(&MsmParams::default()).unwrap());
+
+    let window = default_window(points.len());
+    for c in (window - 2..=window + 2).filter(|c| (4..=20).contains(c)) {
+        consider(
+            &mut best,
+            MsmParams {
+                c,
+                ..Default::default()
+            },
+        );
+    }
+
+    if precompute {
+        let window = if best.0.c > 0 { best.0.c } else { window };
+        for precompute_factor in PRECOMPUTE_FACTORS {
+            for c in (window - 1..=window + 1).filter(|c| (4..=20).contains(c)) {
+                consider(&mut best, MsmParams::precomputed(precompute_factor, c));
+            }
+        }
+    }
+
+    if cuda {
+        let base = best.0;
+        for large_bucket_factor in LARGE_BUCKET_FACTORS {
+            consider(
+                &mut best,
+                MsmParams {
+                    large_bucket_factor,
+                    ..base
+                },
+            );
+        }
+        let base = best.0;
+        consider(
+            &mut best,
+            MsmParams {
+                is_big_triangle: true,
+                ..base
+            },
+        );
+    }
+
+    drop(uploaded);
+    drop(d_scalars);
+    stream.destroy().unwrap();
+
+    best.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{C1, C2};
+
+    #[test]
+    fn test_settings_path_is_per_zkey_and_device() {
+        let (g1, g2) = (vec![G1::zero(); 3], vec![G2::zero(); 2]);
+        let points = MsmBases {
+            a: &g1,
+            b1: &g1,
+            b: &g2,
+            c: &g1[..1],
+            h: &g1,
+        };
+        let zkey = Path::new("/keys/circuit.zkey");
+        let gpu = Device::new("CUDA", 1);
+
+        let path = settings_path(zkey, &gpu, &points, true);
+
+        assert_eq!(path, Path::new("/keys/circuit.zkey.msm_tuning_CUDA_1_3_3_2_1_3.json"));
+        assert_ne!(path, settings_path(zkey, &Device::new("CUDA", 0), &points, true));
+        assert_ne!(path, settings_path(zkey, &gpu, &points, false));
+        assert_ne!(path, settings_path(Path::new("/keys/other.zkey"), &gpu, &points, true));
+    }
+
+    #[test]
+    fn test_tuned_settings_round_trip() {
+        let cpu = Device::new("CPU", 0);
+        icicle_runtime::set_device(&cpu).unwrap();
+        let dir = std::env::temp_dir().join(format!("icicle_snark_autotune_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let zkey = dir.join("circuit.zkey");
+
+        let g1 = C1::generate_random_affine_points(16);
+        let g2 = C2::generate_random_affine_points(16);
+        let points = MsmBases {
+            a: &g1,
+            b1: &g1,
+            b: &g2,
+            c: &g1[..8],
+            h: &g1,
+        };
+
+        let tuned = load_or_tune(zkey.to_str().unwrap(), &points, true).unwrap();
+        let path = settings_path(&zkey, &cpu, &points, true);
+        let saved: MsmSettings = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved, tuned);
+
+        // once saved, the settings are read back instead of tuned again
+        let edited = MsmSettings {
+            a: MsmParams::precomputed(2, 7),
+            ..tuned
+        };
+        fs::write(&path, serde_json::to_string(&edited).unwrap()).unwrap();
+        assert_eq!(load_or_tune(zkey.to_str().unwrap(), &points, true).unwrap(), edited);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
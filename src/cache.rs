@@ -1,25 +1,30 @@
 use icicle_bn254::curve::ScalarField;
-use icicle_core::ntt::{get_root_of_unity, initialize_domain, release_domain, NTTInitDomainConfig};
-use icicle_core::traits::{FieldImpl, MontgomeryConvertible};
-use icicle_runtime::memory::{DeviceVec, HostOrDeviceSlice, HostSlice};
+use icicle_core::ntt::get_root_of_unity;
+use icicle_core::curve::{Affine, Curve};
+use icicle_core::msm::MSM;
+use icicle_core::traits::{Arithmetic, FieldImpl, MontgomeryConvertible};
+use icicle_runtime::memory::{DeviceSlice, DeviceVec, HostOrDeviceSlice, HostSlice};
 use icicle_runtime::stream::IcicleStream;
-use rayon::iter::{IndexedParallelIterator, IntoParallelRefMutIterator, ParallelIterator};
+use icicle_runtime::Device;
+use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, IntoParallelRefMutIterator, ParallelIterator};
+use num_bigint::BigUint;
 use std::collections::HashMap;
-use serde::Deserialize;
+use std::ops::Range;
+use serde::{Deserialize, Serialize};
 use serde::de::Deserializer;
 
-#[cfg(not(feature = "coset-gen"))]
 use std::fs::File;
-#[cfg(not(feature = "coset-gen"))]
 use std::io::{self, Read, Write};
-#[cfg(not(feature = "coset-gen"))]
 use std::path::Path;
-#[cfg(not(feature = "coset-gen"))]
 use std::{mem, slice};
 
-use crate::conversions::from_u8;
-use crate::file_wrapper::FileWrapper;
-use crate::zkey::ZKey;
+use crate::autotune::{self, MsmBases};
+use crate::config::{ProverConfig, QuotientStrategy};
+use crate::conversions::{deserialize_scalar, from_scalars_mont, from_u8};
+use crate::icicle_helper::{ntt_batch_helper, precompute_bases_helper};
+use crate::file_wrapper::{FileWrapper, Section};
+use crate::witness_calculator::{modulus, WitnessCalculator};
+use crate::zkey::{FflonkZKey, PlonkZKey, ZKey};
 use crate::{F, G1, G2};
 
 const W: [&str; 30] = [
@@ -55,20 +60,616 @@ const W: [&str; 30] = [
     "0x2260e724844bca5251829353968e4915305258418357473a5c1d597f613f6cbd",
 ];
 
+/// Shift of the coset the PLONK quotient is evaluated on. It generates the multiplicative group
+/// of Fr, so the coset misses every power-of-two domain.
+const PLONK_COSET_GEN: u32 = 5;
+
 pub struct ZKeyCache {
-    pub s_values: Vec<usize>,
-    pub c_values: Vec<usize>,
-    pub m_values: Vec<usize>,
-    pub first_slice: DeviceVec<F>,
-    pub points_a: DeviceVec<G1>,
-    pub points_b1: DeviceVec<G1>,
-    pub points_b: DeviceVec<G2>,
-    pub points_h: DeviceVec<G1>,
-    pub points_c: DeviceVec<G1>,
-    #[cfg(not(feature = "coset-gen"))]
-    pub keys: DeviceVec<F>,
+    pub matrix: SparseMatrix,
+    pub points_a: Bases<G1>,
+    pub points_b1: Bases<G1>,
+    pub points_b: Bases<G2>,
+    pub points_h: Bases<G1>,
+    pub points_c: Bases<G1>,
+    /// Parameters the commitments are computed with; a precomputed section's window is the
+    /// one its points were extended for.
+    pub msm: MsmSettings,
+    /// Powers of the coset generator, computed on the first proof that asks for
+    /// `QuotientStrategy::PrecomputedKeys`.
+    pub keys: Option<DeviceVec<F>>,
     pub inc: F,
     pub zkey: ZKey,
+    /// Per-device MSM bases; empty when every MSM runs on the device that owns this cache.
+    pub shards: Vec<DeviceShard>,
+    zkey_file: FileWrapper,
+    sections: Vec<Vec<Section>>,
+}
+
+impl ZKeyCache {
+    /// Computes whatever `config` needs that is not part of every cache.
+    pub(crate) fn prepare(&mut self, config: &ProverConfig) -> io::Result<()> {
+        if config.quotient == QuotientStrategy::PrecomputedKeys && self.keys.is_none() {
+            let keys = CacheManager::pre_compute_keys(F::one(), self.inc, self.zkey.domain_size)?;
+            let mut d_keys = DeviceVec::device_malloc(keys.len()).unwrap();
+            d_keys.copy_from_host(HostSlice::from_slice(&keys)).unwrap();
+            self.keys = Some(d_keys);
+        }
+        Ok(())
+    }
+
+    /// Resolves `bases`, which must belong to this cache, to where its points currently are.
+    pub fn bases<'a, P>(&'a self, bases: &'a Bases<P>) -> BasesRef<'a, P> {
+        match bases {
+            Bases::Device(points) => BasesRef::Device(points),
+            Bases::Mapped { section, chunk_size } => BasesRef::Mapped {
+                points: from_u8(self.zkey_file.read_section(&self.sections, *section).unwrap()),
+                chunk_size: *chunk_size,
+            },
+            Bases::Host { section } => {
+                BasesRef::Host(from_u8(self.zkey_file.read_section(&self.sections, *section).unwrap()))
+            }
+            Bases::Precomputed { points, factor } => BasesRef::Precomputed {
+                points,
+                factor: *factor,
+            },
+        }
+    }
+}
+
+/// Proving key of a PLONK zkey.
+pub struct PlonkCache {
+    pub zkey: PlonkZKey,
+    /// Internal signals, each `factor_a * signal_a + factor_b * signal_b`:
+    /// `(signal_a, signal_b, factor_a, factor_b)`.
+    pub additions: Vec<(u32, u32, F, F)>,
+    /// Signal on the A, B and C wire of every constraint.
+    pub wires: [Vec<u32>; 3],
+    /// Coefficients of qm, ql, qr, qo, qc, sigma1, sigma2 and sigma3, `domain_size` each.
+    pub coefficients: Vec<F>,
+    /// Evaluations of sigma1, sigma2 and sigma3 over the domain.
+    pub sigma: DeviceVec<F>,
+    /// Evaluations over the quotient coset, `4 * domain_size` each, of qm, ql, qr, qo, qc,
+    /// sigma1, sigma2, sigma3, X, L1 and 1 / Z_H. Computed on the first proof.
+    pub coset: Option<DeviceVec<F>>,
+    /// `domain_size + 6` powers of tau in G1.
+    pub ptau: DeviceVec<G1>,
+    /// Generator of the domain.
+    pub omega: F,
+    pub coset_gen: F,
+}
+
+impl PlonkCache {
+    /// Evaluates the circuit polynomials over the quotient coset.
+    pub(crate) fn prepare(&mut self) {
+        if self.coset.is_none() {
+            self.coset = Some(coset_evaluations(&self.coefficients, self.zkey.domain_size, &self.coset_gen));
+        }
+    }
+
+    pub fn verification_key(&self) -> PlonkVerificationKey {
+        let zkey = &self.zkey;
+        PlonkVerificationKey {
+            n_public: zkey.n_public,
+            power: zkey.power,
+            k1: zkey.k1,
+            k2: zkey.k2,
+            qm: zkey.qm,
+            ql: zkey.ql,
+            qr: zkey.qr,
+            qo: zkey.qo,
+            qc: zkey.qc,
+            s1: zkey.s1,
+            s2: zkey.s2,
+            s3: zkey.s3,
+            x_2: zkey.x_2,
+            w: self.omega,
+        }
+    }
+}
+
+/// Proving key of an FFLONK zkey.
+pub struct FflonkCache {
+    pub zkey: FflonkZKey,
+    /// Internal signals, each `factor_a * signal_a + factor_b * signal_b`:
+    /// `(signal_a, signal_b, factor_a, factor_b)`.
+    pub additions: Vec<(u32, u32, F, F)>,
+    /// Signal on the A, B and C wire of every constraint.
+    pub wires: [Vec<u32>; 3],
+    /// Coefficients of ql, qr, qm, qo, qc, sigma1, sigma2 and sigma3, `domain_size` each.
+    pub coefficients: Vec<F>,
+    /// Evaluations of sigma1, sigma2 and sigma3 over the domain.
+    pub sigma: DeviceVec<F>,
+    /// Evaluations over the quotient coset, `4 * domain_size` each, of the polynomials in
+    /// `coefficients`, X, L1 and 1 / Z_H. Computed on the first proof.
+    pub coset: Option<DeviceVec<F>>,
+    /// `9 * domain_size + 18` powers of tau in G1.
+    pub ptau: DeviceVec<G1>,
+    /// Generator of the domain.
+    pub omega: F,
+    pub coset_gen: F,
+}
+
+impl FflonkCache {
+    /// Evaluates the circuit polynomials over the quotient coset.
+    pub(crate) fn prepare(&mut self) {
+        if self.coset.is_none() {
+            self.coset = Some(coset_evaluations(&self.coefficients, self.zkey.domain_size, &self.coset_gen));
+        }
+    }
+
+    pub fn verification_key(&self) -> FflonkVerificationKey {
+        let zkey = &self.zkey;
+        FflonkVerificationKey {
+            n_public: zkey.n_public,
+            power: zkey.power,
+            k1: zkey.k1,
+            k2: zkey.k2,
+            w: self.omega,
+            w3: zkey.w3,
+            w4: zkey.w4,
+            w8: zkey.w8,
+            wr: zkey.wr,
+            x_2: zkey.x_2,
+            c0: zkey.c0,
+        }
+    }
+}
+
+/// Evaluations over the coset `coset_gen * <w4n>` of the 8 circuit polynomials in
+/// `coefficients`, followed by those of X, L1 and 1 / Z_H, `4n` each.
+fn coset_evaluations(coefficients: &[F], n: usize, coset_gen: &F) -> DeviceVec<F> {
+    let coset_size = 4 * n;
+    let mut evals = vec![F::zero(); 11 * coset_size];
+
+    for (i, coefficients) in coefficients.chunks(n).enumerate() {
+        evals[i * coset_size..i * coset_size + n].copy_from_slice(coefficients);
+    }
+    // X, and L1, whose coefficients are all 1 / n
+    evals[8 * coset_size + 1] = F::one();
+    let n_inv = F::from_u32(n as u32).inv();
+    evals[9 * coset_size..9 * coset_size + n].fill(n_inv);
+
+    // Z_H(g w^i) = g^n w^(n i) - 1 takes four values, w^n being a fourth root of unity
+    let root: F = get_root_of_unity(4);
+    let mut shift = coset_gen.pow(n);
+    let mut zh_inv = [F::zero(); 4];
+    for value in zh_inv.iter_mut() {
+        *value = (shift - F::one()).inv();
+        shift = shift * root;
+    }
+    for (i, value) in evals[10 * coset_size..].iter_mut().enumerate() {
+        *value = zh_inv[i % 4];
+    }
+
+    let stream = IcicleStream::default();
+    let mut d_evals = DeviceVec::device_malloc(evals.len()).unwrap();
+    d_evals.copy_from_host(HostSlice::from_slice(&evals)).unwrap();
+    ntt_batch_helper(&mut d_evals[..10 * coset_size], false, Some(coset_gen), 10, &stream);
+    stream.synchronize().unwrap();
+
+    d_evals
+}
+
+/// Additions and wire maps of a PLONK circuit, as `PlonkCache` keeps them.
+type PlonkCircuit = (Vec<(u32, u32, F, F)>, [Vec<u32>; 3]);
+
+/// Reads the additions and the A, B and C wire maps, sections 3 to 6 of PLONK and FFLONK
+/// zkeys alike.
+fn read_plonk_circuit(
+    zkey_file: &FileWrapper,
+    sections: &[Vec<Section>],
+    n_additions: usize,
+    n_constraints: usize,
+    n8r: usize,
+) -> Result<PlonkCircuit, Box<dyn std::error::Error>> {
+    let buff_additions = zkey_file.read_section(sections, 3)?;
+    let mut factors = Vec::with_capacity(2 * n_additions);
+    let mut signals = Vec::with_capacity(n_additions);
+    for addition in buff_additions.chunks_exact(8 + 2 * n8r).take(n_additions) {
+        signals.push((
+            u32::from_le_bytes(addition[0..4].try_into().unwrap()),
+            u32::from_le_bytes(addition[4..8].try_into().unwrap()),
+        ));
+        factors.push(F::from_bytes_le(&addition[8..8 + n8r]));
+        factors.push(F::from_bytes_le(&addition[8 + n8r..8 + 2 * n8r]));
+    }
+    if !factors.is_empty() {
+        from_scalars_mont(&mut factors);
+    }
+    let additions = signals
+        .into_iter()
+        .zip(factors.chunks(2))
+        .map(|((a, b), factors)| (a, b, factors[0], factors[1]))
+        .collect();
+
+    let wires = [4, 5, 6].map(|section| {
+        from_u8::<u32>(zkey_file.read_section(sections, section).unwrap())[..n_constraints].to_vec()
+    });
+
+    Ok((additions, wires))
+}
+
+/// Reads the coefficients of the five selectors in `selector_sections` and of the three sigmas
+/// in `sigma_sections`, along with the sigma evaluations over the domain, uploaded to the
+/// device. Every polynomial takes n coefficients followed by 4n evaluations.
+fn read_plonk_polynomials(
+    zkey_file: &FileWrapper,
+    sections: &[Vec<Section>],
+    selector_sections: &[usize],
+    sigma_sections: &[usize],
+    n: usize,
+) -> Result<(Vec<F>, DeviceVec<F>), Box<dyn std::error::Error>> {
+    let mut coefficients = Vec::with_capacity(8 * n);
+    for section in selector_sections {
+        coefficients.extend_from_slice(&from_u8::<F>(zkey_file.read_section(sections, *section)?)[..n]);
+    }
+    let mut sigma = Vec::with_capacity(3 * n);
+    for section in sigma_sections {
+        let buff_sigma = from_u8::<F>(zkey_file.read_section(sections, *section)?);
+        for polynomial in buff_sigma.chunks(5 * n).take(3) {
+            coefficients.extend_from_slice(&polynomial[..n]);
+            // the evaluations are over the 4n domain, of which every fourth point is in the n one
+            sigma.extend(polynomial[n..].iter().step_by(4).take(n));
+        }
+    }
+    from_scalars_mont(&mut coefficients);
+    from_scalars_mont(&mut sigma);
+
+    let mut d_sigma = DeviceVec::device_malloc(sigma.len()).unwrap();
+    d_sigma.copy_from_host(HostSlice::from_slice(&sigma)).unwrap();
+
+    Ok((coefficients, d_sigma))
+}
+
+/// Where the bases of one commitment are kept between proofs.
+///
+/// Bases read from the zkey (`Mapped` and `Host`) stay in the Montgomery form snarkjs stores
+/// them in, and their MSMs are configured with `are_bases_montgomery_form`, which converts them
+/// on every proof. Bases kept on the device (`Device` and `Precomputed`) are converted out of
+/// Montgomery form once when the cache is built instead, trading a pass over the bases at build
+/// time for MSMs that skip the conversion.
+pub enum Bases<P> {
+    /// Uploaded to the device once, out of Montgomery form.
+    Device(DeviceVec<P>),
+    /// Left in the mmapped zkey section and streamed to the device in `chunk_size` point
+    /// chunks on every proof.
+    Mapped { section: usize, chunk_size: usize },
+    /// Left in the mmapped zkey section and read in place by the MSM. Only valid when the
+    /// device shares host memory, i.e. the CPU backend.
+    Host { section: usize },
+    /// Extended on the device with `factor - 1` shifted copies of every point, stored
+    /// interleaved as `precompute_bases` lays them out, out of Montgomery form.
+    Precomputed { points: DeviceVec<P>, factor: usize },
+}
+
+/// Borrowed view of `Bases`.
+#[derive(Clone, Copy)]
+pub enum BasesRef<'a, P> {
+    Device(&'a DeviceSlice<P>),
+    Mapped { points: &'a [P], chunk_size: usize },
+    Host(&'a [P]),
+    Precomputed { points: &'a DeviceSlice<P>, factor: usize },
+}
+
+impl<'a, P> BasesRef<'a, P> {
+    pub fn len(&self) -> usize {
+        match self {
+            BasesRef::Device(points) => points.len(),
+            BasesRef::Mapped { points, .. } => points.len(),
+            BasesRef::Host(points) => points.len(),
+            BasesRef::Precomputed { points, factor } => points.len() / factor,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn slice(self, range: Range<usize>) -> Self {
+        match self {
+            BasesRef::Device(points) => BasesRef::Device(&points[range]),
+            BasesRef::Mapped { points, chunk_size } => BasesRef::Mapped {
+                points: &points[range],
+                chunk_size,
+            },
+            BasesRef::Host(points) => BasesRef::Host(&points[range]),
+            BasesRef::Precomputed { points, factor } => BasesRef::Precomputed {
+                points: &points[range.start * factor..range.end * factor],
+                factor,
+            },
+        }
+    }
+}
+
+/// How the commitment MSMs are spread over `CacheManager::with_msm_devices`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MsmSplit {
+    /// Every device computes a contiguous part of each MSM.
+    #[default]
+    Partition,
+    /// Each MSM runs whole on one device, balanced by size.
+    PerCommitment,
+}
+
+/// MSM parameters for the bases of one commitment.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MsmParams {
+    /// Number of points kept per base, see `icicle_core::msm::precompute_bases`. 1 disables
+    /// precomputation.
+    pub precompute_factor: i32,
+    /// Window size in bits; 0 lets the backend choose. Precomputed bases are only valid for the
+    /// window they were computed with, so a window is always fixed for them.
+    pub c: i32,
+    /// CUDA backend bucket sizing, see `CUDA_MSM_LARGE_BUCKET_FACTOR`; 0 keeps the backend default.
+    pub large_bucket_factor: i32,
+    /// CUDA backend bucket reduction, see `CUDA_MSM_IS_BIG_TRIANGLE`.
+    pub is_big_triangle: bool,
+}
+
+impl Default for MsmParams {
+    fn default() -> Self {
+        MsmParams {
+            precompute_factor: 1,
+            c: 0,
+            large_bucket_factor: 0,
+            is_big_triangle: false,
+        }
+    }
+}
+
+impl MsmParams {
+    pub fn precomputed(precompute_factor: i32, c: i32) -> Self {
+        MsmParams {
+            precompute_factor,
+            c,
+            ..Default::default()
+        }
+    }
+
+    /// Fixes the window for precomputed bases when none was given, using the log2(n) - 4
+    /// rule of the CUDA backend.
+    pub(crate) fn resolve(self, nof_bases: usize) -> Self {
+        if self.precompute_factor <= 1 || self.c > 0 {
+            return self;
+        }
+        MsmParams {
+            c: default_window(nof_bases),
+            ..self
+        }
+    }
+}
+
+pub(crate) fn default_window(nof_bases: usize) -> i32 {
+    let log_size = usize::BITS - nof_bases.max(1).leading_zeros() - 1;
+    (log_size as i32 - 4).max(4)
+}
+
+/// `MsmParams` for each commitment of a proof.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MsmSettings {
+    pub a: MsmParams,
+    pub b1: MsmParams,
+    pub b: MsmParams,
+    pub c: MsmParams,
+    pub h: MsmParams,
+}
+
+/// Bases `range` of one commitment, held by a single device.
+pub struct BaseShard<P> {
+    pub range: Range<usize>,
+    /// `None` on the device that owns the cache, which reads `range` out of its full bases.
+    pub points: Option<DeviceVec<P>>,
+}
+
+/// The part of every commitment's bases assigned to one device.
+pub struct DeviceShard {
+    pub device: Device,
+    /// Whether this is the device that owns the cache.
+    pub primary: bool,
+    pub a: Option<BaseShard<G1>>,
+    pub b1: Option<BaseShard<G1>>,
+    pub b: Option<BaseShard<G2>>,
+    pub c: Option<BaseShard<G1>>,
+    pub h: Option<BaseShard<G1>>,
+}
+
+impl Drop for DeviceShard {
+    fn drop(&mut self) {
+        // device memory can only be released while its device is active
+        let active = icicle_runtime::get_active_device().unwrap();
+        icicle_runtime::set_device(&self.device).unwrap();
+        self.a.take();
+        self.b1.take();
+        self.b.take();
+        self.c.take();
+        self.h.take();
+        icicle_runtime::set_device(&active).unwrap();
+    }
+}
+
+/// The A and B coefficient matrices of the zkey as one CSR matrix with `2 * domain_size` rows.
+///
+/// Rows `[0, domain_size)` hold B and rows `[domain_size, 2 * domain_size)` hold A, so the
+/// product with the witness lands in `d_vec` in the order the NTTs expect. The row therefore
+/// encodes both the constraint and the matrix id, and each coefficient only costs its u32
+/// signal index and its value, which is kept in plain (non-Montgomery) form.
+pub struct SparseMatrix {
+    pub nof_rows: usize,
+    pub storage: MatrixStorage,
+}
+
+/// Where the coefficient matrix is kept between proofs.
+pub enum MatrixStorage {
+    /// Evaluated on the host, or in place by the CPU backend.
+    Host(Csr<Vec<u32>, Vec<F>>),
+    /// Uploaded once; the witness gather and the products run on the device.
+    Device(Csr<DeviceVec<u32>, DeviceVec<F>>),
+}
+
+pub struct Csr<I, V> {
+    pub row_ptr: I,
+    pub col_idx: I,
+    pub values: V,
+}
+
+impl SparseMatrix {
+    /// Builds the matrix from the coefficient section (section 4) of a zkey, uploading it
+    /// unless `on_host` is set or its index does not fit in device memory. Fails if the index
+    /// fits but the values do not.
+    fn from_coefficients(
+        buff_coeffs: &[u8],
+        domain_size: usize,
+        n8r: usize,
+        on_host: bool,
+        stream: &IcicleStream,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let s_coef = 4 * 3 + n8r;
+        let n_coef = (buff_coeffs.len() - 4) / s_coef;
+        let nof_rows = 2 * domain_size;
+
+        if n_coef > u32::MAX as usize {
+            return Err(format!("{} coefficients do not fit in u32 row offsets", n_coef).into());
+        }
+
+        let entry = |i: usize| &buff_coeffs[4 + i * s_coef..4 + (i + 1) * s_coef];
+        let row = |entry: &[u8]| {
+            let c = u32::from_le_bytes([entry[4], entry[5], entry[6], entry[7]]) as usize;
+            if entry[0] == 1 { c } else { domain_size + c }
+        };
+
+        // counting sort of the entries by row; `order[k]` is the zkey entry stored at position k
+        let mut row_ptr = vec![0u32; nof_rows + 1];
+        for i in 0..n_coef {
+            row_ptr[row(entry(i)) + 1] += 1;
+        }
+        for r in 0..nof_rows {
+            row_ptr[r + 1] += row_ptr[r];
+        }
+        let mut next = row_ptr[..nof_rows].to_vec();
+        let mut order = vec![0u32; n_coef];
+        for i in 0..n_coef {
+            let r = row(entry(i));
+            order[next[r] as usize] = i as u32;
+            next[r] += 1;
+        }
+        drop(next);
+
+        let mut col_idx = vec![0u32; n_coef];
+        let mut values = vec![F::zero(); n_coef];
+        col_idx
+            .par_iter_mut()
+            .zip(values.par_iter_mut())
+            .zip(order.par_iter())
+            .for_each(|((col, value), &i)| {
+                let entry = entry(i as usize);
+                *col = u32::from_le_bytes([entry[8], entry[9], entry[10], entry[11]]);
+                *value = ScalarField::from_bytes_le(&entry[12..12 + n8r]);
+            });
+        drop(order);
+
+        // coefficients are stored as coef * R^2; converting twice leaves the plain coefficient,
+        // so the witness can be multiplied as is
+        let device_index = if on_host { None } else { upload_index(&row_ptr, &col_idx, stream) };
+        if let Some((d_row_ptr, d_col_idx)) = device_index {
+            let mut d_values = DeviceVec::device_malloc_async(n_coef, stream)
+                .map_err(|e| format!("Failed to allocate {} coefficients on the device: {:?}", n_coef, e))?;
+            d_values
+                .copy_from_host_async(HostSlice::from_slice(&values), stream)
+                .unwrap();
+            ScalarField::from_mont(&mut d_values, stream);
+            ScalarField::from_mont(&mut d_values, stream);
+            stream.synchronize().unwrap();
+
+            return Ok(SparseMatrix {
+                nof_rows,
+                storage: MatrixStorage::Device(Csr {
+                    row_ptr: d_row_ptr,
+                    col_idx: d_col_idx,
+                    values: d_values,
+                }),
+            });
+        }
+
+        // a host matrix takes no device memory, so it is converted on the host instead
+        let r_squared: BigUint = (BigUint::from(1u32) << 512u32) % modulus();
+        let r_squared_inv = F::from_bytes_le(&r_squared.to_bytes_le()).inv();
+        values
+            .par_iter_mut()
+            .for_each(|value| *value = *value * r_squared_inv);
+
+        Ok(SparseMatrix {
+            nof_rows,
+            storage: MatrixStorage::Host(Csr {
+                row_ptr,
+                col_idx,
+                values,
+            }),
+        })
+    }
+}
+
+/// Uploads the CSR index arrays, or returns `None` if they do not fit in device memory.
+fn upload_index(
+    row_ptr: &[u32],
+    col_idx: &[u32],
+    stream: &IcicleStream,
+) -> Option<(DeviceVec<u32>, DeviceVec<u32>)> {
+    let mut d_row_ptr = DeviceVec::device_malloc_async(row_ptr.len(), stream).ok()?;
+    let mut d_col_idx = DeviceVec::device_malloc_async(col_idx.len(), stream).ok()?;
+    d_row_ptr
+        .copy_from_host_async(HostSlice::from_slice(row_ptr), stream)
+        .unwrap();
+    d_col_idx
+        .copy_from_host_async(HostSlice::from_slice(col_idx), stream)
+        .unwrap();
+
+    Some((d_row_ptr, d_col_idx))
+}
+
+impl Csr<DeviceVec<u32>, DeviceVec<F>> {
+    /// Copies the matrix back to the host.
+    pub fn to_host(&self) -> Csr<Vec<u32>, Vec<F>> {
+        let mut row_ptr = vec![0u32; self.row_ptr.len()];
+        let mut col_idx = vec![0u32; self.col_idx.len()];
+        let mut values = vec![F::zero(); self.values.len()];
+        self.row_ptr
+            .copy_to_host(HostSlice::from_mut_slice(&mut row_ptr))
+            .unwrap();
+        self.col_idx
+            .copy_to_host(HostSlice::from_mut_slice(&mut col_idx))
+            .unwrap();
+        self.values
+            .copy_to_host(HostSlice::from_mut_slice(&mut values))
+            .unwrap();
+
+        Csr {
+            row_ptr,
+            col_idx,
+            values,
+        }
+    }
+}
+
+/// Uploads zkey points and converts them out of Montgomery form, once, so that the MSMs over
+/// them do not have to.
+pub(crate) fn upload_bases<C: Curve>(points: &[Affine<C>], stream: &IcicleStream) -> DeviceVec<Affine<C>> {
+    let mut d_points = DeviceVec::device_malloc_async(points.len(), stream).unwrap();
+    d_points
+        .copy_from_host_async(HostSlice::from_slice(points), stream)
+        .unwrap();
+    Affine::from_mont(&mut d_points, stream).wrap().unwrap();
+    d_points
+}
+
+fn upload_shard<C: Curve>(
+    points: &[Affine<C>],
+    range: Range<usize>,
+    owned: bool,
+    stream: &IcicleStream,
+) -> BaseShard<Affine<C>> {
+    let points = owned.then(|| upload_bases(&points[range.clone()], stream));
+
+    BaseShard { range, points }
 }
 
 #[derive(Debug)]
@@ -107,13 +708,196 @@ impl<'de> Deserialize<'de> for VerificationKey {
     }
 }
 
+/// Verification key of a PLONK circuit, as `snarkjs zkey export verificationkey` writes it.
+#[derive(Debug)]
+pub struct PlonkVerificationKey {
+    pub n_public: usize,
+    pub power: usize,
+    pub k1: F,
+    pub k2: F,
+    pub qm: G1,
+    pub ql: G1,
+    pub qr: G1,
+    pub qo: G1,
+    pub qc: G1,
+    pub s1: G1,
+    pub s2: G1,
+    pub s3: G1,
+    pub x_2: G2,
+    /// Generator of the domain.
+    pub w: F,
+}
+
+impl<'de> Deserialize<'de> for PlonkVerificationKey {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Helper {
+            n_public: usize,
+            power: usize,
+            k1: String,
+            k2: String,
+            #[serde(rename = "Qm")]
+            qm: Vec<String>,
+            #[serde(rename = "Ql")]
+            ql: Vec<String>,
+            #[serde(rename = "Qr")]
+            qr: Vec<String>,
+            #[serde(rename = "Qo")]
+            qo: Vec<String>,
+            #[serde(rename = "Qc")]
+            qc: Vec<String>,
+            #[serde(rename = "S1")]
+            s1: Vec<String>,
+            #[serde(rename = "S2")]
+            s2: Vec<String>,
+            #[serde(rename = "S3")]
+            s3: Vec<String>,
+            #[serde(rename = "X_2")]
+            x_2: Vec<Vec<String>>,
+            w: String,
+        }
+        let helper = Helper::deserialize(deserializer)?;
+        Ok(PlonkVerificationKey {
+            n_public: helper.n_public,
+            power: helper.power,
+            k1: deserialize_scalar(&helper.k1),
+            k2: deserialize_scalar(&helper.k2),
+            qm: crate::conversions::deserialize_g1_affine(&helper.qm),
+            ql: crate::conversions::deserialize_g1_affine(&helper.ql),
+            qr: crate::conversions::deserialize_g1_affine(&helper.qr),
+            qo: crate::conversions::deserialize_g1_affine(&helper.qo),
+            qc: crate::conversions::deserialize_g1_affine(&helper.qc),
+            s1: crate::conversions::deserialize_g1_affine(&helper.s1),
+            s2: crate::conversions::deserialize_g1_affine(&helper.s2),
+            s3: crate::conversions::deserialize_g1_affine(&helper.s3),
+            x_2: crate::conversions::deserialize_g2_affine(&helper.x_2),
+            w: deserialize_scalar(&helper.w),
+        })
+    }
+}
+
+/// Verification key of an FFLONK circuit, as `snarkjs zkey export verificationkey` writes it.
+#[derive(Debug)]
+pub struct FflonkVerificationKey {
+    pub n_public: usize,
+    pub power: usize,
+    pub k1: F,
+    pub k2: F,
+    /// Generator of the domain.
+    pub w: F,
+    pub w3: F,
+    pub w4: F,
+    pub w8: F,
+    pub wr: F,
+    pub x_2: G2,
+    pub c0: G1,
+}
+
+impl<'de> Deserialize<'de> for FflonkVerificationKey {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Helper {
+            n_public: usize,
+            power: usize,
+            k1: String,
+            k2: String,
+            w: String,
+            w3: String,
+            w4: String,
+            w8: String,
+            wr: String,
+            #[serde(rename = "X_2")]
+            x_2: Vec<Vec<String>>,
+            #[serde(rename = "C0")]
+            c0: Vec<String>,
+        }
+        let helper = Helper::deserialize(deserializer)?;
+        Ok(FflonkVerificationKey {
+            n_public: helper.n_public,
+            power: helper.power,
+            k1: deserialize_scalar(&helper.k1),
+            k2: deserialize_scalar(&helper.k2),
+            w: deserialize_scalar(&helper.w),
+            w3: deserialize_scalar(&helper.w3),
+            w4: deserialize_scalar(&helper.w4),
+            w8: deserialize_scalar(&helper.w8),
+            wr: deserialize_scalar(&helper.wr),
+            x_2: crate::conversions::deserialize_g2_affine(&helper.x_2),
+            c0: crate::conversions::deserialize_g1_affine(&helper.c0),
+        })
+    }
+}
+
 #[derive(Default)]
 pub struct CacheManager {
     cache: HashMap<String, ZKeyCache>,
-    last_key: String,
+    plonk_cache: HashMap<String, PlonkCache>,
+    fflonk_cache: HashMap<String, FflonkCache>,
+    witness_calculators: HashMap<String, WitnessCalculator>,
+    msm_devices: Vec<Device>,
+    msm_split: MsmSplit,
+    bases_chunk_size: Option<usize>,
+    host_matrix: bool,
+    msm_settings: MsmSettings,
+    msm_autotune: bool,
 }
 
 impl CacheManager {
+    /// Spreads the commitment MSMs of every cache computed from now on over `devices`.
+    ///
+    /// The device active when a cache is computed keeps the full bases and reads its share
+    /// from them; every other device gets its own copy of its share.
+    pub fn with_msm_devices(mut self, devices: Vec<Device>, split: MsmSplit) -> Self {
+        self.msm_devices = devices;
+        self.msm_split = split;
+        self
+    }
+
+    /// Keeps the MSM bases of every cache computed from now on in the mmapped zkey, and
+    /// streams them to the device `chunk_size` points at a time instead of uploading them once.
+    ///
+    /// This trades some proving time for fitting keys that are larger than device memory.
+    pub fn with_streamed_bases(mut self, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "chunk size must be positive");
+        self.bases_chunk_size = Some(chunk_size);
+        self
+    }
+
+    /// Computes the commitments of every cache computed from now on with `settings`.
+    ///
+    /// Sections with a `precompute_factor` above 1 keep that many points per base on the
+    /// device, trading device memory for faster MSMs. Precomputation does not apply to
+    /// streamed bases.
+    pub fn with_msm_settings(mut self, settings: MsmSettings) -> Self {
+        self.msm_settings = settings;
+        self
+    }
+
+    /// Benchmarks the MSM parameters of every commitment on the active device when a cache is
+    /// computed, and uses the fastest instead of `with_msm_settings`.
+    ///
+    /// The results are saved next to the zkey as `<zkey>.msm_tuning_*.json`, and reused by
+    /// every later cache of the zkey on the same device.
+    pub fn with_msm_autotune(mut self) -> Self {
+        self.msm_autotune = true;
+        self
+    }
+
+    /// Keeps the coefficient matrix of every cache computed from now on in host memory and
+    /// evaluates it there, leaving the device memory to the MSM bases.
+    pub fn with_host_matrix(mut self) -> Self {
+        self.host_matrix = true;
+        self
+    }
+
     pub fn compute(&mut self, zkey_path: &str) -> Result<ZKeyCache, Box<dyn std::error::Error>> {
         let mut stream = IcicleStream::create().unwrap();
 
@@ -125,46 +909,6 @@ impl CacheManager {
 
         let buff_coeffs = zkey_file.read_section(&sections_zkey[..], 4).unwrap();
 
-        let s_coef = 4 * 3 + zkey.n8r;
-        let n_coef = (buff_coeffs.len() - 4) / s_coef;
-
-        let mut first_slice = Vec::with_capacity(n_coef);
-        let mut s_values = Vec::with_capacity(n_coef);
-        let mut c_values = Vec::with_capacity(n_coef);
-        let mut m_values = Vec::with_capacity(n_coef);
-
-        unsafe {
-            first_slice.set_len(n_coef);
-            s_values.set_len(n_coef);
-            c_values.set_len(n_coef);
-            m_values.set_len(n_coef);
-        }
-        let n8 = 32;
-
-        s_values
-            .par_iter_mut()
-            .zip(c_values.par_iter_mut())
-            .zip(m_values.par_iter_mut())
-            .zip(first_slice.par_iter_mut())
-            .enumerate()
-            .for_each(|(i, (((s_val, c_val), m_val), coef_val))| {
-                let start = 4 + i * s_coef;
-                let buff_coef = &buff_coeffs[start..start + s_coef];
-
-                let s =
-                    u32::from_le_bytes([buff_coef[8], buff_coef[9], buff_coef[10], buff_coef[11]])
-                        as usize;
-                let c = u32::from_le_bytes([buff_coef[4], buff_coef[5], buff_coef[6], buff_coef[7]])
-                    as usize;
-                let m = buff_coef[0];
-                let coef = ScalarField::from_bytes_le(&buff_coef[12..12 + n8]);
-
-                *s_val = s;
-                *c_val = c;
-                *m_val = m as usize;
-                *coef_val = coef;
-            });
-
         let power = zkey.power + 1;
         let inc = F::from_hex(W[power]);
 
@@ -180,79 +924,162 @@ impl CacheManager {
         let points_c = from_u8(points_c);
         let points_h = from_u8(points_h);
 
-        let mut d_points_a = DeviceVec::device_malloc_async(points_a.len(), &stream).unwrap();
-        let mut d_points_b1 = DeviceVec::device_malloc_async(points_b1.len(), &stream).unwrap();
-        let mut d_points_b = DeviceVec::device_malloc_async(points_b.len(), &stream).unwrap();
-        let mut d_points_c = DeviceVec::device_malloc_async(points_c.len(), &stream).unwrap();
-        let mut d_points_h = DeviceVec::device_malloc_async(points_h.len(), &stream).unwrap();
-        let mut d_first_slice = DeviceVec::device_malloc_async(first_slice.len(), &stream).unwrap();
-
-        let points_a = HostSlice::from_slice(points_a);
-        let points_b1 = HostSlice::from_slice(points_b1);
-        let points_b = HostSlice::from_slice(points_b);
-        let points_c = HostSlice::from_slice(points_c);
-        let points_h = HostSlice::from_slice(points_h);
-        let first_slice = HostSlice::from_slice(&first_slice);
-
-        d_points_a.copy_from_host_async(points_a, &stream).unwrap();
-        d_points_b1
-            .copy_from_host_async(points_b1, &stream)
-            .unwrap();
-        d_points_b.copy_from_host_async(points_b, &stream).unwrap();
-        d_points_c.copy_from_host_async(points_c, &stream).unwrap();
-        d_points_h.copy_from_host_async(points_h, &stream).unwrap();
-        d_first_slice
-            .copy_from_host_async(first_slice, &stream)
-            .unwrap();
+        // the CPU backend works on host memory, so its MSMs can read the mmapped zkey directly
+        let on_cpu = icicle_runtime::get_active_device().unwrap().get_device_type() == "CPU";
 
-        G1::from_mont(&mut d_points_a, &stream);
-        G1::from_mont(&mut d_points_b1, &stream);
-        G2::from_mont(&mut d_points_b, &stream);
-        G1::from_mont(&mut d_points_c, &stream);
-        G1::from_mont(&mut d_points_h, &stream);
+        let matrix = SparseMatrix::from_coefficients(buff_coeffs, zkey.domain_size, zkey.n8r, on_cpu || self.host_matrix, &stream)?;
 
-        ScalarField::from_mont(&mut d_first_slice, &stream);
+        let msm = if self.msm_autotune {
+            let bases = MsmBases {
+                a: points_a,
+                b1: points_b1,
+                b: points_b,
+                c: points_c,
+                h: points_h,
+            };
+            let precompute = on_cpu || self.bases_chunk_size.is_none();
+            autotune::load_or_tune(zkey_path, &bases, precompute)?
+        } else {
+            self.msm_settings
+        };
+        let msm = MsmSettings {
+            a: msm.a.resolve(points_a.len()),
+            b1: msm.b1.resolve(points_b1.len()),
+            b: msm.b.resolve(points_b.len()),
+            c: msm.c.resolve(points_c.len()),
+            h: msm.h.resolve(points_h.len()),
+        };
+
+        let d_points_a = self.place_bases(points_a, 5, on_cpu, &msm.a, &stream);
+        let d_points_b1 = self.place_bases(points_b1, 6, on_cpu, &msm.b1, &stream);
+        let d_points_b = self.place_bases(points_b, 7, on_cpu, &msm.b, &stream);
+        let d_points_c = self.place_bases(points_c, 8, on_cpu, &msm.c, &stream);
+        let d_points_h = self.place_bases(points_h, 9, on_cpu, &msm.h, &stream);
 
         stream.synchronize().unwrap();
         stream.destroy().unwrap();
 
+        let shards = if self.msm_devices.len() > 1 {
+            self.compute_shards(points_a, points_b1, points_b, points_c, points_h)?
+        } else {
+            Vec::new()
+        };
+
         let cache_entry = ZKeyCache {
-            #[cfg(not(feature = "coset-gen"))]
-            keys: {
-                let keys = CacheManager::pre_compute_keys(F::one(), inc, zkey.domain_size).unwrap();
-                let mut d_keys = DeviceVec::device_malloc_async(zkey.domain_size, &stream).unwrap();
-                d_keys.copy_from_host_async(HostSlice::from_slice(&keys), &stream).unwrap();
-                d_keys
-            },
-            s_values,
-            c_values,
-            m_values,
+            keys: None,
+            matrix,
             zkey,
-            first_slice: d_first_slice,
             points_a: d_points_a,
             points_b1: d_points_b1,
             points_b: d_points_b,
             points_c: d_points_c,
             points_h: d_points_h,
+            msm,
             inc: inc,
+            shards,
+            zkey_file,
+            sections: sections_zkey,
         };
 
         Ok(cache_entry)
     }
-    pub fn get_cache(&mut self, key: &str) -> &mut ZKeyCache {
-        let cache = self.cache.get_mut(key).unwrap();
 
-        if !self.last_key.is_empty() && !key.eq(&self.last_key) {
-            release_domain::<F>().unwrap();
+    /// Decides where the bases of zkey `section` are kept.
+    fn place_bases<C: Curve + MSM<C>>(
+        &self,
+        points: &[Affine<C>],
+        section: usize,
+        on_cpu: bool,
+        params: &MsmParams,
+        stream: &IcicleStream,
+    ) -> Bases<Affine<C>> {
+        match self.bases_chunk_size {
+            // streamed bases are read from the zkey, so there is nowhere to keep extra points
+            Some(chunk_size) if !on_cpu => Bases::Mapped { section, chunk_size },
+            _ if params.precompute_factor > 1 => Bases::Precomputed {
+                points: precompute_bases_helper(points, params, stream).unwrap(),
+                factor: params.precompute_factor as usize,
+            },
+            _ if on_cpu => Bases::Host { section },
+            _ => Bases::Device(upload_bases(points, stream)),
         }
+    }
+
+    fn compute_shards(
+        &self,
+        points_a: &[G1],
+        points_b1: &[G1],
+        points_b: &[G2],
+        points_c: &[G1],
+        points_h: &[G1],
+    ) -> Result<Vec<DeviceShard>, Box<dyn std::error::Error>> {
+        let active = icicle_runtime::get_active_device().unwrap();
+        let primary = self
+            .msm_devices
+            .iter()
+            .position(|device| *device == active)
+            .ok_or_else(|| format!("Active device {:?} is not one of the MSM devices", active))?;
 
-        let domain: F = get_root_of_unity(cache.points_a.len() as u64);
-        let cfg = NTTInitDomainConfig::default();
-        initialize_domain(domain, &cfg).unwrap();
+        let n_devices = self.msm_devices.len();
+        let lens = [points_a.len(), points_b1.len(), points_b.len(), points_c.len(), points_h.len()];
 
-        self.last_key = key.to_string();
+        // ranges[commitment][device]
+        let ranges: Vec<Vec<Option<Range<usize>>>> = match self.msm_split {
+            MsmSplit::Partition => lens
+                .iter()
+                .map(|&len| {
+                    let chunk = len.div_ceil(n_devices);
+                    (0..n_devices)
+                        .map(|i| {
+                            let range = (i * chunk).min(len)..((i + 1) * chunk).min(len);
+                            (!range.is_empty()).then_some(range)
+                        })
+                        .collect()
+                })
+                .collect(),
+            MsmSplit::PerCommitment => {
+                // a G2 MSM costs roughly three G1 MSMs of the same size
+                let costs = [lens[0], lens[1], 3 * lens[2], lens[3], lens[4]];
+                let mut order: Vec<usize> = (0..costs.len()).collect();
+                order.sort_by_key(|&i| std::cmp::Reverse(costs[i]));
 
-        cache
+                let mut load = vec![0; n_devices];
+                let mut ranges = vec![vec![None; n_devices]; costs.len()];
+                for commitment in order {
+                    let device = (0..n_devices).min_by_key(|&d| load[d]).unwrap();
+                    load[device] += costs[commitment];
+                    ranges[commitment][device] = Some(0..lens[commitment]);
+                }
+                ranges
+            }
+        };
+
+        let mut shards = Vec::with_capacity(n_devices);
+        for (i, device) in self.msm_devices.iter().enumerate() {
+            let owned = i != primary;
+            icicle_runtime::set_device(device).unwrap();
+
+            let mut stream = IcicleStream::create().unwrap();
+            let shard = DeviceShard {
+                device: device.clone(),
+                primary: !owned,
+                a: ranges[0][i].clone().map(|r| upload_shard(points_a, r, owned, &stream)),
+                b1: ranges[1][i].clone().map(|r| upload_shard(points_b1, r, owned, &stream)),
+                b: ranges[2][i].clone().map(|r| upload_shard(points_b, r, owned, &stream)),
+                c: ranges[3][i].clone().map(|r| upload_shard(points_c, r, owned, &stream)),
+                h: ranges[4][i].clone().map(|r| upload_shard(points_h, r, owned, &stream)),
+            };
+            stream.synchronize().unwrap();
+            stream.destroy().unwrap();
+
+            shards.push(shard);
+        }
+        icicle_runtime::set_device(&active).unwrap();
+
+        Ok(shards)
+    }
+    pub fn get_cache(&mut self, key: &str) -> &mut ZKeyCache {
+        self.cache.get_mut(key).unwrap()
     }
     pub fn insert_cache(&mut self, key: &str, cache: ZKeyCache) {
         self.cache.insert(key.to_string(), cache);
@@ -260,7 +1087,117 @@ impl CacheManager {
     pub fn contains(&self, key: &str) -> bool {
         self.cache.contains_key(key)
     }
-    #[cfg(not(feature = "coset-gen"))]
+
+    /// Reads the proving key of a PLONK zkey.
+    pub fn compute_plonk(&mut self, zkey_path: &str) -> Result<PlonkCache, Box<dyn std::error::Error>> {
+        let (fd_zkey, sections) = FileWrapper::read_bin_file(zkey_path, "zkey", 2)?;
+        let mut zkey_file = FileWrapper::new(fd_zkey)?;
+        let zkey = zkey_file.read_plonk_zkey_header(&sections)?;
+
+        let n = zkey.domain_size;
+
+        // the quotient has 3n + 6 coefficients and is interpolated over 4n points
+        if n < 8 {
+            return Err(format!("PLONK domain of {} rows is too small, at least 8 are needed", n).into());
+        }
+
+        let (additions, wires) =
+            read_plonk_circuit(&zkey_file, &sections, zkey.n_additions, zkey.n_constraints, zkey.n8r)?;
+        let (coefficients, sigma) = read_plonk_polynomials(&zkey_file, &sections, &[7, 8, 9, 10, 11], &[12], n)?;
+
+        let ptau = &from_u8::<G1>(zkey_file.read_section(&sections, 14)?)[..n + 6];
+        let stream = IcicleStream::default();
+        let d_ptau = upload_bases(ptau, &stream);
+        stream.synchronize().unwrap();
+
+        Ok(PlonkCache {
+            omega: F::from_hex(W[zkey.power]),
+            coset_gen: F::from_u32(PLONK_COSET_GEN),
+            zkey,
+            additions,
+            wires,
+            coefficients,
+            sigma,
+            coset: None,
+            ptau: d_ptau,
+        })
+    }
+
+    pub fn get_plonk_cache(&mut self, key: &str) -> &mut PlonkCache {
+        self.plonk_cache.get_mut(key).unwrap()
+    }
+    pub fn insert_plonk_cache(&mut self, key: &str, cache: PlonkCache) {
+        self.plonk_cache.insert(key.to_string(), cache);
+    }
+    pub fn contains_plonk(&self, key: &str) -> bool {
+        self.plonk_cache.contains_key(key)
+    }
+
+    /// Reads the proving key of an FFLONK zkey.
+    pub fn compute_fflonk(&mut self, zkey_path: &str) -> Result<FflonkCache, Box<dyn std::error::Error>> {
+        let (fd_zkey, sections) = FileWrapper::read_bin_file(zkey_path, "zkey", 2)?;
+        let mut zkey_file = FileWrapper::new(fd_zkey)?;
+        let zkey = zkey_file.read_fflonk_zkey_header(&sections)?;
+
+        let n = zkey.domain_size;
+
+        // T2 has 3n + 6 coefficients and is interpolated over 4n points
+        if n < 8 {
+            return Err(format!("FFLONK domain of {} rows is too small, at least 8 are needed", n).into());
+        }
+
+        let (additions, wires) =
+            read_plonk_circuit(&zkey_file, &sections, zkey.n_additions, zkey.n_constraints, zkey.n8r)?;
+        let (coefficients, sigma) =
+            read_plonk_polynomials(&zkey_file, &sections, &[7, 8, 9, 10, 11], &[12, 13, 14], n)?;
+
+        // C2 = Z(X^3) + X T1(X^3) + X^2 T2(X^3) has 9n + 18 coefficients
+        let ptau = from_u8::<G1>(zkey_file.read_section(&sections, 16)?);
+        if ptau.len() < 9 * n + 18 {
+            return Err(format!(
+                "FFLONK zkey holds {} powers of tau, {} are needed",
+                ptau.len(),
+                9 * n + 18
+            )
+            .into());
+        }
+        let ptau = &ptau[..9 * n + 18];
+        let stream = IcicleStream::default();
+        let d_ptau = upload_bases(ptau, &stream);
+        stream.synchronize().unwrap();
+
+        Ok(FflonkCache {
+            omega: F::from_hex(W[zkey.power]),
+            coset_gen: F::from_u32(PLONK_COSET_GEN),
+            zkey,
+            additions,
+            wires,
+            coefficients,
+            sigma,
+            coset: None,
+            ptau: d_ptau,
+        })
+    }
+
+    pub fn get_fflonk_cache(&mut self, key: &str) -> &mut FflonkCache {
+        self.fflonk_cache.get_mut(key).unwrap()
+    }
+    pub fn insert_fflonk_cache(&mut self, key: &str, cache: FflonkCache) {
+        self.fflonk_cache.insert(key.to_string(), cache);
+    }
+    pub fn contains_fflonk(&self, key: &str) -> bool {
+        self.fflonk_cache.contains_key(key)
+    }
+
+    /// Returns the witness calculator of the circom `wasm`, compiling it on first use.
+    pub fn get_witness_calculator(&mut self, wasm: &str) -> Result<&mut WitnessCalculator, Box<dyn std::error::Error>> {
+        if !self.witness_calculators.contains_key(wasm) {
+            let calculator = WitnessCalculator::from_file(wasm)?;
+            self.witness_calculators.insert(wasm.to_string(), calculator);
+        }
+        Ok(self.witness_calculators.get_mut(wasm).unwrap())
+    }
+
     fn pre_compute_keys(
         mut key: ScalarField,
         inc: ScalarField,
@@ -288,9 +1225,10 @@ impl CacheManager {
         Ok(keys)
     }
 
-    #[cfg(not(feature = "coset-gen"))]
     fn save_to_binary_file(keys: &[ScalarField], file_path: &Path) -> io::Result<()> {
-        let mut file = File::create(file_path)?;
+        // several devices may compute the same keys at once, so publish the file atomically
+        let tmp_path = file_path.with_extension(format!("{:?}.tmp", std::thread::current().id()));
+        let mut file = File::create(&tmp_path)?;
 
         let bytes = unsafe {
             slice::from_raw_parts(keys.as_ptr() as *const u8, std::mem::size_of_val(keys))
@@ -298,10 +1236,9 @@ impl CacheManager {
 
         file.write_all(bytes)?;
 
-        Ok(())
+        std::fs::rename(tmp_path, file_path)
     }
 
-    #[cfg(not(feature = "coset-gen"))]
     fn load_from_binary_file(file_path: &Path) -> io::Result<Vec<ScalarField>> {
         let mut file = File::open(file_path)?;
         let mut buffer = Vec::new();
@@ -317,3 +1254,135 @@ impl CacheManager {
         Ok(scalars)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proof_helper::groth16_prove_helper;
+    use crate::proof_helper::tests::{cpu_config, fixture, fixture_cache};
+    use crate::Blinding;
+    use rand_core::OsRng;
+    use serde_json::Value;
+
+    const MID_ZKEY: &str = "groth16/mid/circuit_final.zkey";
+
+    /// Proof of a fixture witness with fixed blinding factors, so that caches holding the same
+    /// key in different places give the same proof. Fails unless the proof verifies.
+    fn fixed_proof(zkey_cache: &ZKeyCache) -> Value {
+        let config = ProverConfig {
+            blinding: Blinding::Fixed,
+            verify: true,
+            ..cpu_config()
+        };
+        let witness = fixture("groth16/mid/witness_1.wtns");
+
+        groth16_prove_helper(&witness, zkey_cache, &config, &mut OsRng).unwrap().0
+    }
+
+    /// Moves the matrix and bases of a CPU cache to device memory, where a GPU cache keeps them.
+    fn place_on_device(zkey_cache: &mut ZKeyCache) {
+        let stream = IcicleStream::default();
+        let zkey_file = &zkey_cache.zkey_file;
+        let sections = &zkey_cache.sections;
+        let section = |id| zkey_file.read_section(sections, id).unwrap();
+
+        zkey_cache.matrix =
+            SparseMatrix::from_coefficients(section(4), zkey_cache.zkey.domain_size, zkey_cache.zkey.n8r, false, &stream)
+                .unwrap();
+        zkey_cache.points_a = Bases::Device(upload_bases(from_u8::<G1>(section(5)), &stream));
+        zkey_cache.points_b1 = Bases::Device(upload_bases(from_u8::<G1>(section(6)), &stream));
+        zkey_cache.points_b = Bases::Device(upload_bases(from_u8::<G2>(section(7)), &stream));
+        zkey_cache.points_c = Bases::Device(upload_bases(from_u8::<G1>(section(8)), &stream));
+        zkey_cache.points_h = Bases::Device(upload_bases(from_u8::<G1>(section(9)), &stream));
+        stream.synchronize().unwrap();
+    }
+
+    #[test]
+    fn test_host_matrix_and_bases_match_device_proof() {
+        let mut manager = CacheManager::default();
+        let device_cache = fixture_cache(&mut manager, MID_ZKEY);
+        place_on_device(device_cache);
+        assert!(matches!(device_cache.matrix.storage, MatrixStorage::Device(_)));
+        let expected = fixed_proof(device_cache);
+
+        let mut manager = CacheManager::default().with_host_matrix();
+        let host_cache = fixture_cache(&mut manager, MID_ZKEY);
+        assert!(matches!(host_cache.matrix.storage, MatrixStorage::Host(_)));
+        assert!(matches!(host_cache.points_a, Bases::Host { .. }));
+        assert!(matches!(host_cache.points_b, Bases::Host { .. }));
+
+        assert_eq!(fixed_proof(host_cache), expected);
+    }
+
+    /// Section 4 of a zkey with `entries` of (matrix, constraint, signal, coefficient), where
+    /// matrix 0 is A and 1 is B. Coefficients are stored as `coef * R^2`.
+    fn coefficients_section(entries: &[(u32, u32, u32, u32)]) -> Vec<u8> {
+        let stream = IcicleStream::default();
+        let mut buff = (entries.len() as u32).to_le_bytes().to_vec();
+
+        for &(matrix, constraint, signal, coefficient) in entries {
+            let mut d_value = DeviceVec::device_malloc(1).unwrap();
+            d_value.copy_from_host(HostSlice::from_slice(&[F::from_u32(coefficient)])).unwrap();
+            F::to_mont(&mut d_value, &stream);
+            F::to_mont(&mut d_value, &stream);
+            let mut value = [F::zero()];
+            d_value.copy_to_host(HostSlice::from_mut_slice(&mut value)).unwrap();
+
+            for word in [matrix, constraint, signal] {
+                buff.extend_from_slice(&word.to_le_bytes());
+            }
+            buff.extend_from_slice(&value[0].to_bytes_le());
+        }
+        buff
+    }
+
+    #[test]
+    fn test_sparse_matrix_from_coefficients() {
+        icicle_runtime::set_device(&Device::new("CPU", 0)).unwrap();
+        let stream = IcicleStream::default();
+        let buff = coefficients_section(&[(0, 0, 1, 5), (1, 1, 2, 7), (0, 0, 3, 2), (1, 0, 0, 3), (0, 3, 2, 9)]);
+
+        let host = SparseMatrix::from_coefficients(&buff, 4, 32, true, &stream).unwrap();
+        let device = SparseMatrix::from_coefficients(&buff, 4, 32, false, &stream).unwrap();
+
+        let MatrixStorage::Host(csr) = &host.storage else {
+            panic!("expected a host matrix");
+        };
+        let MatrixStorage::Device(d_csr) = &device.storage else {
+            panic!("expected a device matrix");
+        };
+
+        // B rows first, then A rows; entries of a row keep their zkey order
+        assert_eq!(host.nof_rows, 8);
+        assert_eq!(csr.row_ptr, [0, 1, 2, 2, 2, 4, 4, 4, 5]);
+        assert_eq!(csr.col_idx, [0, 2, 1, 3, 2]);
+        assert_eq!(csr.values, [3, 7, 5, 2, 9].map(F::from_u32));
+
+        let d_csr = d_csr.to_host();
+        assert_eq!(device.nof_rows, host.nof_rows);
+        assert_eq!(d_csr.row_ptr, csr.row_ptr);
+        assert_eq!(d_csr.col_idx, csr.col_idx);
+        assert_eq!(d_csr.values, csr.values);
+    }
+
+    #[test]
+    fn test_precomputed_bases_match_default_proof() {
+        let mut manager = CacheManager::default();
+        let expected = fixed_proof(fixture_cache(&mut manager, MID_ZKEY));
+
+        let params = MsmParams::precomputed(4, 0);
+        let settings = MsmSettings {
+            a: params,
+            b1: params,
+            b: params,
+            c: params,
+            h: params,
+        };
+        let mut manager = CacheManager::default().with_msm_settings(settings);
+        let precomputed_cache = fixture_cache(&mut manager, MID_ZKEY);
+        assert!(matches!(precomputed_cache.points_a, Bases::Precomputed { factor: 4, .. }));
+        assert!(matches!(precomputed_cache.points_b, Bases::Precomputed { factor: 4, .. }));
+
+        assert_eq!(fixed_proof(precomputed_cache), expected);
+    }
+}
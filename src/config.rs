@@ -0,0 +1,89 @@
+use icicle_runtime::Device;
+use std::fmt;
+use std::sync::Arc;
+use zeroize::Zeroize;
+
+use crate::{symbols::Symbols, F};
+
+/// How the A, B and C evaluations are moved onto the coset the quotient is computed on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum QuotientStrategy {
+    /// Multiplies by the powers of the coset generator, computed once per cache and saved as
+    /// `precomputed_*.bin` in the working directory.
+    #[default]
+    PrecomputedKeys,
+    /// Lets the forward NTT apply the coset generator, which needs no extra memory or file.
+    CosetNtt,
+}
+
+/// Where the blinding factors `r` and `s` of a proof come from.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Blinding {
+    /// Fresh factors for every proof, drawn from the prover's RNG: the operating system's
+    /// CSPRNG unless one is passed to a `*_with_rng` function.
+    #[default]
+    Random,
+    /// `r = s = 1`. The proofs still verify but are deterministic and not zero-knowledge,
+    /// so this is only meant for reproducing proofs in tests.
+    Fixed,
+    /// The given factors, for reproducible test vectors. They are copied into every proof,
+    /// so proofs made with the same factors are linkable.
+    Explicit(BlindingFactors),
+}
+
+/// Blinding factors `r` and `s` of a Groth16 proof, wiped when dropped.
+#[derive(Clone, PartialEq)]
+pub struct BlindingFactors {
+    pub r: F,
+    pub s: F,
+}
+
+impl fmt::Debug for BlindingFactors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("BlindingFactors { .. }")
+    }
+}
+
+impl Drop for BlindingFactors {
+    fn drop(&mut self) {
+        for scalar in [&mut self.r, &mut self.s] {
+            let bytes = unsafe {
+                std::slice::from_raw_parts_mut(scalar as *mut F as *mut u8, std::mem::size_of::<F>())
+            };
+            bytes.zeroize();
+        }
+    }
+}
+
+/// Options of a single prove call.
+#[derive(Clone, Debug)]
+pub struct ProverConfig {
+    pub quotient: QuotientStrategy,
+    pub blinding: Blinding,
+    /// Device to prove on. Ignored by `DevicePool`, which picks the device itself.
+    pub device: Device,
+    /// Number of streams the five commitment MSMs are spread over, up to one per MSM. The
+    /// G2 MSM gets a stream of its own as soon as there are two.
+    pub streams: usize,
+    /// Checks every proof against the verification key of the zkey before it is returned or
+    /// written, failing with `InvalidProof` if it does not verify.
+    pub verify: bool,
+    /// Signal names of the circuit, from its `.sym` file, for the errors of witnesses that do
+    /// not satisfy the constraints. Only the PLONK and FFLONK provers check the constraints: a
+    /// Groth16 zkey does not hold the C matrix, so a bad Groth16 witness only shows up as a
+    /// proof that does not verify.
+    pub symbols: Option<Arc<Symbols>>,
+}
+
+impl Default for ProverConfig {
+    fn default() -> Self {
+        ProverConfig {
+            quotient: QuotientStrategy::default(),
+            blinding: Blinding::default(),
+            device: Device::new("CUDA", 0),
+            streams: 2,
+            verify: false,
+            symbols: None,
+        }
+    }
+}
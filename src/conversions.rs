@@ -8,7 +8,7 @@ use icicle_runtime::{
 };
 use num_bigint::BigUint;
 
-use crate::{G1, G2};
+use crate::{F, G1, G2};
 
 pub fn from_affine_mont<C: Curve>(points: &mut [Affine<C>]) {
     let mut stream = IcicleStream::create().unwrap();
@@ -27,6 +27,23 @@ pub fn from_affine_mont<C: Curve>(points: &mut [Affine<C>]) {
     stream.destroy().unwrap();
 }
 
+pub fn from_scalars_mont(scalars: &mut [F]) {
+    let mut stream = IcicleStream::create().unwrap();
+    let mut d_scalars = DeviceVec::device_malloc_async(scalars.len(), &stream).unwrap();
+    d_scalars
+        .copy_from_host_async(HostSlice::from_slice(scalars), &stream)
+        .unwrap();
+
+    F::from_mont(&mut d_scalars, &stream).wrap().unwrap();
+
+    d_scalars
+        .copy_to_host_async(HostSlice::from_mut_slice(scalars), &stream)
+        .unwrap();
+
+    stream.synchronize().unwrap();
+    stream.destroy().unwrap();
+}
+
 pub fn serialize_g1_affine(point: G1) -> Vec<String> {
     let x_bytes = BigUint::from_bytes_le(&point.x.to_bytes_le()[..]);
     let y_bytes = BigUint::from_bytes_le(&point.y.to_bytes_le()[..]);
@@ -95,6 +112,9 @@ pub fn deserialize_g2_affine(data: &[Vec<String>]) -> G2 {
     G2::from_limbs(x_limbs, y_limbs)
 }
 
+pub fn deserialize_scalar(data: &str) -> F {
+    F::from_bytes_le(&BigUint::parse_bytes(data.as_bytes(), 10).unwrap().to_bytes_le())
+}
 
 pub fn from_u8<T>(data: &[u8]) -> &[T] {
     let num_data = data.len() / size_of::<T>();
@@ -0,0 +1,342 @@
+use icicle_runtime::Device;
+use rand_core::OsRng;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::{self, JoinHandle};
+
+use crate::{prove_on_active_device, try_load_backend, CacheManager, ProverConfig};
+
+type Job = Box<dyn FnOnce(&Device, &mut CacheManager) + Send>;
+
+/// One worker thread per device.
+///
+/// icicle keeps the active device per thread, so each worker selects its device once and
+/// owns a `CacheManager` whose `ZKeyCache`s live in that device's memory.
+struct DeviceSlot {
+    device: Device,
+    pending: Arc<AtomicUsize>,
+    sender: Option<mpsc::Sender<Job>>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl DeviceSlot {
+    fn spawn(device: Device) -> Result<Self, Box<dyn std::error::Error>> {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let worker_device = device.clone();
+
+        let worker = thread::Builder::new()
+            .name(format!("icicle-{}-{}", device.get_device_type(), device.id))
+            .spawn(move || {
+                icicle_runtime::set_device(&worker_device).unwrap();
+                let mut cache_manager = CacheManager::default();
+
+                for job in receiver {
+                    job(&worker_device, &mut cache_manager);
+                }
+            })?;
+
+        Ok(DeviceSlot {
+            device,
+            pending: Arc::new(AtomicUsize::new(0)),
+            sender: Some(sender),
+            worker: Some(worker),
+        })
+    }
+}
+
+/// Result of a job dispatched to a `DevicePool`.
+pub struct PoolTask<T> {
+    device: Device,
+    receiver: mpsc::Receiver<Result<T, String>>,
+}
+
+impl<T> PoolTask<T> {
+    /// Device the job was dispatched to.
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    /// Blocks until the job has finished on its device.
+    pub fn wait(self) -> Result<T, Box<dyn std::error::Error>> {
+        match self.receiver.recv() {
+            Ok(result) => result.map_err(|e| e.into()),
+            Err(_) => Err(format!("job panicked on device {:?}", self.device).into()),
+        }
+    }
+}
+
+/// Schedules proofs over several devices, sending each one to the device with the fewest
+/// pending jobs.
+pub struct DevicePool {
+    slots: Vec<DeviceSlot>,
+}
+
+impl DevicePool {
+    /// Creates a pool over every device of `device_type` reported by the backend.
+    pub fn new(device_type: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        try_load_backend(device_type);
+
+        icicle_runtime::set_device(&Device::new(device_type, 0))
+            .map_err(|e| format!("Device {} is not available: {:?}", device_type, e))?;
+        let count = icicle_runtime::get_device_count()
+            .map_err(|e| format!("Failed to count {} devices: {:?}", device_type, e))?;
+
+        DevicePool::with_devices((0..count).map(|id| Device::new(device_type, id)).collect())
+    }
+
+    /// Creates a pool over all registered non-CPU devices, or a single CPU device if there
+    /// are none.
+    pub fn discover() -> Result<Self, Box<dyn std::error::Error>> {
+        icicle_runtime::runtime::load_backend_from_env_or_default()
+            .map_err(|e| format!("Failed to load backend: {:?}", e))?;
+
+        let registered = icicle_runtime::runtime::get_registered_devices()
+            .map_err(|e| format!("Failed to list registered devices: {:?}", e))?;
+
+        let mut devices = Vec::new();
+        for device_type in registered.iter().filter(|t| t.as_str() != "CPU") {
+            if icicle_runtime::set_device(&Device::new(device_type, 0)).is_err() {
+                continue;
+            }
+            let count = icicle_runtime::get_device_count().unwrap_or(0);
+            devices.extend((0..count).map(|id| Device::new(device_type, id)));
+        }
+
+        if devices.is_empty() {
+            devices.push(Device::new("CPU", 0));
+        }
+
+        DevicePool::with_devices(devices)
+    }
+
+    /// Creates a pool over an explicit list of devices.
+    ///
+    /// The same device may be listed more than once to run several workers on it.
+    pub fn with_devices(devices: Vec<Device>) -> Result<Self, Box<dyn std::error::Error>> {
+        if devices.is_empty() {
+            return Err("DevicePool needs at least one device".into());
+        }
+
+        let mut slots = Vec::with_capacity(devices.len());
+        for device in devices {
+            if !icicle_runtime::is_device_available(&device) {
+                return Err(format!("Device {:?} is not available", device).into());
+            }
+            slots.push(DeviceSlot::spawn(device)?);
+        }
+
+        Ok(DevicePool { slots })
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    pub fn devices(&self) -> Vec<Device> {
+        self.slots.iter().map(|slot| slot.device.clone()).collect()
+    }
+
+    /// Number of queued or running jobs per device, in pool order.
+    pub fn pending(&self) -> Vec<usize> {
+        self.slots
+            .iter()
+            .map(|slot| slot.pending.load(Ordering::SeqCst))
+            .collect()
+    }
+
+    /// Runs `job` on the least-loaded device, with that device active and its cache manager.
+    pub fn execute<T, J>(&self, job: J) -> PoolTask<T>
+    where
+        T: Send + 'static,
+        J: FnOnce(&Device, &mut CacheManager) -> T + Send + 'static,
+    {
+        self.submit(move |device, cache_manager| Ok(job(device, cache_manager)))
+    }
+
+    /// Proves `witness` on the least-loaded device. The zkey is cached per device on first use.
+    pub fn groth16_prove(&self, witness: &str, zkey: &str, proof: &str, public: &str) -> PoolTask<()> {
+        self.groth16_prove_with_config(witness, zkey, proof, public, &ProverConfig::default())
+    }
+
+    /// Like `groth16_prove`, with the prover options of `config`. Its device is ignored, and
+    /// random blinding factors always come from the operating system's CSPRNG.
+    pub fn groth16_prove_with_config(
+        &self,
+        witness: &str,
+        zkey: &str,
+        proof: &str,
+        public: &str,
+        config: &ProverConfig,
+    ) -> PoolTask<()> {
+        let (witness, zkey, proof, public) =
+            (witness.to_string(), zkey.to_string(), proof.to_string(), public.to_string());
+        let config = config.clone();
+
+        self.submit(move |_, cache_manager| {
+            prove_on_active_device(&witness, &zkey, &proof, &public, &config, &mut OsRng, cache_manager)
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    fn submit<T, J>(&self, job: J) -> PoolTask<T>
+    where
+        T: Send + 'static,
+        J: FnOnce(&Device, &mut CacheManager) -> Result<T, String> + Send + 'static,
+    {
+        let slot = self
+            .slots
+            .iter()
+            .min_by_key(|slot| slot.pending.load(Ordering::SeqCst))
+            .unwrap();
+
+        let (result_sender, receiver) = mpsc::channel();
+        let pending = slot.pending.clone();
+        pending.fetch_add(1, Ordering::SeqCst);
+
+        let job: Job = Box::new(move |device, cache_manager| {
+            // a panicking job drops `result_sender`, which `PoolTask::wait` reports as an error
+            let result = panic::catch_unwind(AssertUnwindSafe(|| job(device, cache_manager)));
+            pending.fetch_sub(1, Ordering::SeqCst);
+            if let Ok(result) = result {
+                let _ = result_sender.send(result);
+            }
+        });
+
+        slot.sender.as_ref().unwrap().send(job).unwrap();
+
+        PoolTask {
+            device: slot.device.clone(),
+            receiver,
+        }
+    }
+}
+
+impl Drop for DevicePool {
+    fn drop(&mut self) {
+        for slot in &mut self.slots {
+            slot.sender.take();
+        }
+        for slot in &mut self.slots {
+            if let Some(worker) = slot.worker.take() {
+                let _ = worker.join();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::groth16_verify;
+    use crate::proof_helper::tests::{cpu_config, fixture};
+    use std::sync::Barrier;
+
+    fn cpu_pool(workers: usize) -> DevicePool {
+        DevicePool::with_devices(vec![Device::new("CPU", 0); workers]).unwrap()
+    }
+
+    #[test]
+    fn test_discovers_cpu_device() {
+        let pool = DevicePool::new("CPU").unwrap();
+
+        assert_eq!(pool.len(), 1);
+        assert_eq!(pool.devices()[0].get_device_type(), "CPU");
+    }
+
+    #[test]
+    fn test_rejects_unavailable_device() {
+        assert!(DevicePool::with_devices(vec![Device::new("UNKNOWN", 0)]).is_err());
+        assert!(DevicePool::with_devices(Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_jobs_run_with_device_active() {
+        let pool = cpu_pool(3);
+
+        let tasks: Vec<_> = (0..6)
+            .map(|_| pool.execute(|device, _| icicle_runtime::get_active_device().unwrap() == *device))
+            .collect();
+
+        for task in tasks {
+            assert!(task.wait().unwrap());
+        }
+        assert_eq!(pool.pending(), vec![0; 3]);
+    }
+
+    #[test]
+    fn test_dispatches_to_least_loaded_device() {
+        let pool = cpu_pool(3);
+        let barrier = Arc::new(Barrier::new(3));
+
+        // block two workers so that only the third one is idle
+        let blocked: Vec<_> = (0..2)
+            .map(|_| {
+                let barrier = barrier.clone();
+                pool.execute(move |_, _| {
+                    barrier.wait();
+                })
+            })
+            .collect();
+        assert_eq!(pool.pending(), vec![1, 1, 0]);
+
+        let task = pool.execute(|_, _| thread::current().name().map(String::from));
+        assert_eq!(pool.pending()[..2], [1, 1]);
+        assert!(task.wait().unwrap().is_some());
+        assert_eq!(pool.pending(), vec![1, 1, 0]);
+
+        barrier.wait();
+        for task in blocked {
+            task.wait().unwrap();
+        }
+        assert_eq!(pool.pending(), vec![0; 3]);
+    }
+
+    #[test]
+    fn test_panicking_job_keeps_worker_alive() {
+        let pool = cpu_pool(1);
+
+        let task = pool.execute(|_, _| panic!("boom"));
+        assert!(task.wait().is_err());
+
+        assert_eq!(pool.execute(|_, _| 7).wait().unwrap(), 7);
+    }
+
+    #[test]
+    fn test_proves_circuits_of_different_sizes_concurrently() {
+        let pool = cpu_pool(4);
+        let config = ProverConfig {
+            verify: true,
+            ..cpu_config()
+        };
+
+        // every round gives each worker one proof, of the circuit it did not prove last, so
+        // the workers keep needing NTT domains of different sizes at the same time
+        for round in 0..6 {
+            let tasks: Vec<_> = (0..pool.len())
+                .map(|i| {
+                    let circuit = ["small", "mid"][(round + i) % 2];
+                    let witness = fixture(&format!("groth16/{}/witness_{}.wtns", circuit, 1 + round % 2));
+                    let zkey = fixture(&format!("groth16/{}/circuit_final.zkey", circuit));
+                    let out = std::env::temp_dir().join(format!("icicle_snark_pool_{}_{}", std::process::id(), i));
+                    let proof = format!("{}_proof.json", out.display());
+                    let public = format!("{}_public.json", out.display());
+
+                    let task = pool.groth16_prove_with_config(&witness, &zkey, &proof, &public, &config);
+                    (circuit, proof, public, task)
+                })
+                .collect();
+
+            for (circuit, proof, public, task) in tasks {
+                task.wait().unwrap();
+                groth16_verify(&proof, &public, &fixture(&format!("groth16/{}/verification_key.json", circuit))).unwrap();
+                std::fs::remove_file(proof).unwrap();
+                std::fs::remove_file(public).unwrap();
+            }
+        }
+    }
+}
@@ -0,0 +1,589 @@
+//! FFLONK prover and verifier for snarkjs FFLONK zkeys, compatible with `snarkjs fflonk verify`.
+//!
+//! The circuit is a PLONK one, whose polynomials are packed into three: C0 of the zkey, which
+//! interleaves the selectors and sigmas, C1 = A(X^4) + X B(X^4) + X^2 C(X^4) + X^3 T0(X^4) and
+//! C2 = Z(X^3) + X T1(X^3) + X^2 T2(X^3). All three are opened at once, C0 and C1 at the
+//! roots of X^8 - xi and X^4 - xi and C2 at those of (X^3 - xi)(X^3 - xi w), with the two
+//! commitments W1 and W2. The transcript is snarkjs' Keccak-256 one.
+
+use icicle_bn254::pairing::PairingTargetField;
+use icicle_core::{
+    curve::Curve,
+    pairing::pairing,
+    polynomials::Polynomial,
+    traits::{Arithmetic, FieldImpl},
+};
+use icicle_runtime::stream::IcicleStream;
+use rand_core::CryptoRngCore;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    cache::{FflonkCache, FflonkVerificationKey},
+    config::ProverConfig,
+    conversions::{deserialize_g1_affine, deserialize_scalar, serialize_g1_affine},
+    icicle_helper::{ntt_batch_helper, ntt_domain},
+    plonk_helper::{
+        blind, combine, commit, download, failing_gate, gate_error, permutation_values, polynomial, read_witness,
+        scale_argument, scalar_to_string, stack, upload, wire_values, Evaluations, PlonkBlinding,
+    },
+    proof_helper::{parse_public_signals, InvalidProof},
+    transcript::{Transcript, TranscriptHash},
+    ProjectiveG1, C1, C2, F, G1, G2,
+};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FflonkProof {
+    pub polynomials: FflonkCommitments,
+    pub evaluations: FflonkEvaluations,
+    pub protocol: String,
+    pub curve: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FflonkCommitments {
+    #[serde(rename = "C1")]
+    pub c1: Vec<String>,
+    #[serde(rename = "C2")]
+    pub c2: Vec<String>,
+    #[serde(rename = "W1")]
+    pub w1: Vec<String>,
+    #[serde(rename = "W2")]
+    pub w2: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FflonkEvaluations {
+    pub ql: String,
+    pub qr: String,
+    pub qm: String,
+    pub qo: String,
+    pub qc: String,
+    pub s1: String,
+    pub s2: String,
+    pub s3: String,
+    pub a: String,
+    pub b: String,
+    pub c: String,
+    pub z: String,
+    pub zw: String,
+    pub t1w: String,
+    pub t2w: String,
+    /// Inverse of the product of the denominators of the verifier, which the snarkjs Solidity
+    /// verifier checks instead of computing every inverse.
+    pub inv: String,
+}
+
+impl FflonkEvaluations {
+    /// The evaluations in transcript order: ql, qr, qm, qo, qc, s1, s2, s3, a, b, c, z, zw,
+    /// t1w and t2w.
+    fn scalars(&self) -> [F; 15] {
+        [
+            &self.ql, &self.qr, &self.qm, &self.qo, &self.qc, &self.s1, &self.s2, &self.s3, &self.a, &self.b,
+            &self.c, &self.z, &self.zw, &self.t1w, &self.t2w,
+        ]
+        .map(|value| deserialize_scalar(value))
+    }
+}
+
+/// Opening points derived from the challenge seed `s`: `h0 = s^3`, `h1 = s^6`, `h2 = s^8` and
+/// `h3 = h2 wr`, each times the roots of unity of its set, so that `h0^8 = h1^4 = h2^3 = xi`
+/// and `h3^3 = xi w`.
+struct Roots {
+    h0w8: [F; 8],
+    h1w4: [F; 4],
+    h2w3: [F; 3],
+    h3w3: [F; 3],
+    xi: F,
+}
+
+impl Roots {
+    fn new(seed: F, w3: F, w4: F, w8: F, wr: F) -> Self {
+        let powers = |first: F, root: F| {
+            let mut power = first;
+            move || {
+                let current = power;
+                power = power * root;
+                current
+            }
+        };
+
+        let seed2 = seed * seed;
+        let h0 = seed2 * seed;
+        let h1 = h0 * h0;
+        let h2 = h1 * seed2;
+        let h3 = h2 * wr;
+
+        let mut h0w8 = powers(h0, w8);
+        let mut h1w4 = powers(h1, w4);
+        let mut h2w3 = powers(h2, w3);
+        let mut h3w3 = powers(h3, w3);
+
+        Self {
+            h0w8: [(); 8].map(|_| h0w8()),
+            h1w4: [(); 4].map(|_| h1w4()),
+            h2w3: [(); 3].map(|_| h2w3()),
+            h3w3: [(); 3].map(|_| h3w3()),
+            xi: h2 * h2 * h2,
+        }
+    }
+}
+
+/// `sum_j X^j P_j(X^k)` of the `k` polynomials `P_j`. The coefficients are interleaved on the
+/// host.
+fn interleave(polynomials: &[&Polynomial<F>]) -> Polynomial<F> {
+    let k = polynomials.len();
+    let len = polynomials.iter().map(|p| p.degree() + 1).max().unwrap();
+    let mut result = vec![F::zero(); k * len];
+    for (j, polynomial) in polynomials.iter().enumerate() {
+        for (i, coefficient) in download(polynomial.coefficients()).into_iter().enumerate() {
+            result[i * k + j] = coefficient;
+        }
+    }
+    Polynomial::from_device(upload(&result)).unwrap()
+}
+
+/// Inverse of the product of every denominator the snarkjs verifier contract inverts: Z_H(xi),
+/// the vanishing polynomials of the opening sets at y, the Lagrange bases of the opening sets
+/// at y and those of the public inputs at xi.
+fn batched_inverse(roots: &Roots, y: F, z_s1: F, z_s2: F, omega: F, n: usize, n_public: usize) -> F {
+    let xi = roots.xi;
+    let xiw = xi * omega;
+    let mut product = (xi.pow(n) - F::one()) * z_s1 * z_s2;
+
+    let h0 = roots.h0w8[0];
+    let den = F::from_u32(8) * h0.pow(6);
+    for i in 0..8 {
+        product = product * den * roots.h0w8[7 * i % 8] * (y - roots.h0w8[i]);
+    }
+
+    let h1 = roots.h1w4[0];
+    let den = F::from_u32(4) * h1 * h1;
+    for i in 0..4 {
+        product = product * den * roots.h1w4[3 * i % 4] * (y - roots.h1w4[i]);
+    }
+
+    for (points, den) in [
+        (&roots.h2w3, F::from_u32(3) * roots.h2w3[0] * (xi - xiw)),
+        (&roots.h3w3, F::from_u32(3) * roots.h3w3[0] * (xiw - xi)),
+    ] {
+        for i in 0..3 {
+            product = product * den * points[2 * i % 3] * (y - points[i]);
+        }
+    }
+
+    let mut w = F::one();
+    for _ in 0..n_public.max(1) {
+        product = product * F::from_u32(n as u32) * (xi - w);
+        w = w * omega;
+    }
+
+    product.inv()
+}
+
+pub fn fflonk_prove_helper(
+    witness: &str,
+    cache: &FflonkCache,
+    config: &ProverConfig,
+    rng: &mut dyn CryptoRngCore,
+) -> Result<(Value, Value), Box<dyn std::error::Error>> {
+    let zkey = &cache.zkey;
+    let n = zkey.domain_size;
+    let m = 4 * n;
+    let coset = cache.coset.as_ref().expect("FFLONK cache is not prepared");
+    let fixed = |i: usize| &coset[i * m..(i + 1) * m];
+    let stream = IcicleStream::default();
+    let ops = Evaluations::new();
+
+    let signals = read_witness(witness, &zkey.r, zkey.n_vars, &cache.additions)?;
+    let factors = PlonkBlinding::new(&config.blinding, rng)?;
+    let blinding = &factors.b;
+    let mut transcript = Transcript::new(TranscriptHash::Keccak256);
+
+    // round 1: the wires and the gate quotient T0, committed to together as C1
+    let values = wire_values(&signals, &cache.wires, n, zkey.n_public);
+
+    let mut d_coefficients = upload(&values);
+    ntt_batch_helper(&mut d_coefficients[..], true, None, 4, &stream);
+
+    let pol_a = blind(&polynomial(&d_coefficients[..n]), n, &[blinding[2], blinding[1]]);
+    let pol_b = blind(&polynomial(&d_coefficients[n..2 * n]), n, &[blinding[4], blinding[3]]);
+    let pol_c = blind(&polynomial(&d_coefficients[2 * n..3 * n]), n, &[blinding[6], blinding[5]]);
+    let pol_pi = polynomial(&d_coefficients[3 * n..]);
+
+    let mut d_evals = stack(&[&pol_a, &pol_b, &pol_c, &pol_pi], m);
+    ntt_batch_helper(&mut d_evals[..], false, Some(&cache.coset_gen), 4, &stream);
+    let on_coset = |i: usize| &d_evals[i * m..(i + 1) * m];
+    let (a, b, c, pi) = (on_coset(0), on_coset(1), on_coset(2), on_coset(3));
+    let (ql, qr, qm, qo, qc) = (fixed(0), fixed(1), fixed(2), fixed(3), fixed(4));
+    let (x, l1, zh_inv) = (fixed(8), fixed(9), fixed(10));
+
+    // a b qm + a ql + b qr + c qo + pi + qc
+    let gate = ops.add(
+        &ops.add(
+            &ops.add(&ops.mul(&ops.mul(a, b), qm), &ops.mul(a, ql)),
+            &ops.add(&ops.mul(b, qr), &ops.mul(c, qo)),
+        ),
+        &ops.add(pi, qc),
+    );
+    let mut d_t0 = ops.mul(&gate, zh_inv);
+    ntt_batch_helper(&mut d_t0[..], true, Some(&cache.coset_gen), 1, &stream);
+
+    if download(&d_t0[2 * n + 2..]).iter().any(|coefficient| *coefficient != F::zero()) {
+        // the selectors are stored as ql, qr, qm, qo, qc
+        let selectors = [2, 0, 1, 3, 4].map(|i| &cache.coefficients[i * n..(i + 1) * n]);
+        let row = failing_gate(selectors, &values, &stream);
+        let n_signals = zkey.n_vars - cache.additions.len();
+        return Err(gate_error(witness, row, &cache.wires, &signals, n_signals, config.symbols.as_deref()));
+    }
+    let t0 = polynomial(&d_t0[..2 * n + 2]);
+
+    let pol_c1 = interleave(&[&pol_a, &pol_b, &pol_c, &t0]);
+    let [commit_c1] = commit(&[&pol_c1], &cache.ptau, &stream).try_into().unwrap();
+
+    // round 2: the permutation polynomial Z and its quotients T1 and T2, committed to as C2
+    transcript.add_point(&zkey.c0);
+    for value in &values[..zkey.n_public] {
+        transcript.add_scalar(value);
+    }
+    transcript.add_point(&commit_c1);
+    let beta = transcript.challenge();
+    transcript.add_scalar(&beta);
+    let gamma = transcript.challenge();
+
+    let mut omega_powers = Vec::with_capacity(n + 3);
+    let mut power = F::one();
+    for _ in 0..n + 3 {
+        omega_powers.push(power);
+        power = power * cache.omega;
+    }
+
+    let z_values = permutation_values(&values[..3 * n], &cache.sigma, &omega_powers[..n], beta, gamma, zkey.k1, zkey.k2)
+        .ok_or_else(|| format!("Copy constraints of {} do not match", witness))?;
+
+    let mut d_z = upload(&z_values);
+    ntt_batch_helper(&mut d_z[..], true, None, 1, &stream);
+    let pol_z = blind(&Polynomial::from_device(d_z).unwrap(), n, &[blinding[9], blinding[8], blinding[7]]);
+    let pol_zw = scale_argument(&pol_z, &omega_powers);
+
+    let mut d_evals = stack(&[&pol_z, &pol_zw], m);
+    ntt_batch_helper(&mut d_evals[..], false, Some(&cache.coset_gen), 2, &stream);
+    let (z, zw) = (&d_evals[..m], &d_evals[m..]);
+
+    let numerator = ops.mul(
+        &ops.mul(
+            &ops.mul(&ops.wire(a, x, beta, gamma), &ops.wire(b, x, beta * zkey.k1, gamma)),
+            &ops.wire(c, x, beta * zkey.k2, gamma),
+        ),
+        z,
+    );
+    let denominator = ops.mul(
+        &ops.mul(
+            &ops.mul(&ops.wire(a, fixed(5), beta, gamma), &ops.wire(b, fixed(6), beta, gamma)),
+            &ops.wire(c, fixed(7), beta, gamma),
+        ),
+        zw,
+    );
+    let mut d_t1 = ops.mul(&ops.mul(&ops.shift(F::zero() - F::one(), z), l1), zh_inv);
+    let mut d_t2 = ops.mul(&ops.sub(&numerator, &denominator), zh_inv);
+    ntt_batch_helper(&mut d_t1[..], true, Some(&cache.coset_gen), 1, &stream);
+    ntt_batch_helper(&mut d_t2[..], true, Some(&cache.coset_gen), 1, &stream);
+
+    let (t1_excess, t2_excess) = (download(&d_t1[n + 2..]), download(&d_t2[3 * n + 6..]));
+    if t1_excess.iter().chain(&t2_excess).any(|coefficient| *coefficient != F::zero()) {
+        return Err(format!("Witness {} does not satisfy the constraints of the circuit", witness).into());
+    }
+    let (t1, t2) = (polynomial(&d_t1[..n + 2]), polynomial(&d_t2[..3 * n + 6]));
+
+    let pol_c2 = interleave(&[&pol_z, &t1, &t2]);
+    let [commit_c2] = commit(&[&pol_c2], &cache.ptau, &stream).try_into().unwrap();
+
+    // round 3: evaluations at xi and xi w
+    transcript.add_scalar(&gamma);
+    transcript.add_point(&commit_c2);
+    let seed = transcript.challenge();
+    let roots = Roots::new(seed, zkey.w3, zkey.w4, zkey.w8, zkey.wr);
+    let xi = roots.xi;
+    let xiw = xi * cache.omega;
+
+    let d_fixed = upload(&cache.coefficients);
+    let q = |i: usize| polynomial(&d_fixed[i * n..(i + 1) * n]);
+    let mut evaluations: Vec<F> = (0..8).map(|i| q(i).evaluate(&xi).unwrap()).collect();
+    let openings = [(&pol_a, xi), (&pol_b, xi), (&pol_c, xi), (&pol_z, xi), (&pol_z, xiw), (&t1, xiw), (&t2, xiw)];
+    for (polynomial, point) in openings {
+        evaluations.push(polynomial.evaluate(&point).unwrap());
+    }
+
+    // round 4: W1, the sum of the quotients of C0, C1 and C2 by the vanishing polynomials
+    // of their opening sets
+    transcript.add_scalar(&seed);
+    for value in &evaluations {
+        transcript.add_scalar(value);
+    }
+    let alpha = transcript.challenge();
+    let alpha2 = alpha * alpha;
+
+    // ql, qr, qo, qm, qc, sigma1, sigma2, sigma3
+    let pol_c0 = interleave(&[&q(0), &q(1), &q(3), &q(2), &q(4), &q(5), &q(6), &q(7)]);
+    // every division from here on fits in the domain of the largest dividend
+    let domain = ntt_domain(pol_c0.degree().max(pol_c1.degree()).max(pol_c2.degree()) + 1);
+    let (f0, r0) = pol_c0.divide_by_binomial(8, &xi).unwrap();
+    let (f1, r1) = pol_c1.divide_by_binomial(4, &xi).unwrap();
+    let (f2, r2_xi) = pol_c2.divide_by_binomial(3, &xi).unwrap();
+    let (f2, r2_xiw) = f2.divide_by_binomial(3, &xiw).unwrap();
+
+    let pol_f = combine(&[(&f0, F::one()), (&f1, alpha), (&f2, alpha2)]);
+
+    let [commit_w1] = commit(&[&pol_f], &cache.ptau, &stream).try_into().unwrap();
+
+    // round 5: W2, opening at y the combination that W1 makes vanish there
+    transcript.add_scalar(&alpha);
+    transcript.add_point(&commit_w1);
+    let y = transcript.challenge();
+
+    let y3 = y * y * y;
+    let z_s0 = y3 * y3 * y * y - xi;
+    let z_s1 = y3 * y - xi;
+    let z_s2 = (y3 - xi) * (y3 - xiw);
+    let quotient1 = alpha * z_s0 * z_s1.inv();
+    let quotient2 = alpha2 * z_s0 * z_s2.inv();
+
+    // the remainder of C2 is r2_xi + (X^3 - xi) r2_xiw
+    let r0_y = r0.evaluate(&y).unwrap();
+    let r1_y = r1.evaluate(&y).unwrap();
+    let r2_y = r2_xi.evaluate(&y).unwrap() + (y3 - xi) * r2_xiw.evaluate(&y).unwrap();
+
+    let pol_l = combine(&[(&pol_c0, F::one()), (&pol_c1, quotient1), (&pol_c2, quotient2), (&pol_f, F::zero() - z_s0)])
+        .add_monomial(&(F::zero() - r0_y - quotient1 * r1_y - quotient2 * r2_y), 0)
+        .unwrap();
+    let pol_w2 = pol_l.divide_by_linear(&y).unwrap();
+    drop(domain);
+
+    let [commit_w2] = commit(&[&pol_w2], &cache.ptau, &stream).try_into().unwrap();
+
+    let inv = batched_inverse(&roots, y, z_s1, z_s2, cache.omega, n, zkey.n_public);
+
+    let [ql, qr, qm, qo, qc, s1, s2, s3, a, b, c, z, zw, t1w, t2w] =
+        <[F; 15]>::try_from(evaluations).unwrap().map(|value| scalar_to_string(&value));
+
+    let proof = FflonkProof {
+        polynomials: FflonkCommitments {
+            c1: serialize_g1_affine(commit_c1),
+            c2: serialize_g1_affine(commit_c2),
+            w1: serialize_g1_affine(commit_w1),
+            w2: serialize_g1_affine(commit_w2),
+        },
+        evaluations: FflonkEvaluations {
+            ql,
+            qr,
+            qm,
+            qo,
+            qc,
+            s1,
+            s2,
+            s3,
+            a,
+            b,
+            c,
+            z,
+            zw,
+            t1w,
+            t2w,
+            inv: scalar_to_string(&inv),
+        },
+        protocol: "fflonk".to_string(),
+        curve: "bn128".to_string(),
+    };
+
+    let public_signals: Vec<String> = signals[1..=zkey.n_public].iter().map(scalar_to_string).collect();
+
+    if config.verify && !fflonk_verify_helper(&proof, &public_signals, &cache.verification_key())? {
+        return Err(InvalidProof {
+            witness: witness.to_string(),
+        }
+        .into());
+    }
+
+    Ok((serde_json::json!(proof), serde_json::json!(public_signals)))
+}
+
+/// Value at `x` of the verifier's polynomials, a few coefficients each.
+fn evaluate(coefficients: &[F], x: &F) -> F {
+    coefficients
+        .iter()
+        .rev()
+        .fold(F::zero(), |acc, coefficient| acc * *x + *coefficient)
+}
+
+pub fn fflonk_verify_helper(
+    proof: &FflonkProof,
+    public: &[String],
+    vk: &FflonkVerificationKey,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    if public.len() != vk.n_public {
+        return Err(format!(
+            "Invalid number of public signals. Circuit: {}, given: {}",
+            vk.n_public,
+            public.len()
+        )
+        .into());
+    }
+
+    let commit_c1 = deserialize_g1_affine(&proof.polynomials.c1);
+    let commit_c2 = deserialize_g1_affine(&proof.polynomials.c2);
+    let commit_w1 = deserialize_g1_affine(&proof.polynomials.w1);
+    let commit_w2 = deserialize_g1_affine(&proof.polynomials.w2);
+    let evaluations = proof.evaluations.scalars();
+    let [ql, qr, qm, qo, qc, s1, s2, s3, a, b, c, z, zw, t1w, t2w] = evaluations;
+    let public = parse_public_signals(public);
+
+    let mut transcript = Transcript::new(TranscriptHash::Keccak256);
+    transcript.add_point(&vk.c0);
+    for value in &public {
+        transcript.add_scalar(value);
+    }
+    transcript.add_point(&commit_c1);
+    let beta = transcript.challenge();
+    transcript.add_scalar(&beta);
+    let gamma = transcript.challenge();
+    transcript.add_scalar(&gamma);
+    transcript.add_point(&commit_c2);
+    let seed = transcript.challenge();
+    transcript.add_scalar(&seed);
+    for value in &evaluations {
+        transcript.add_scalar(value);
+    }
+    let alpha = transcript.challenge();
+    transcript.add_scalar(&alpha);
+    transcript.add_point(&commit_w1);
+    let y = transcript.challenge();
+
+    let xi = Roots::new(seed, vk.w3, vk.w4, vk.w8, vk.wr).xi;
+    let xiw = xi * vk.w;
+    let n = 1usize << vk.power;
+    let zh = xi.pow(n) - F::one();
+    let zh_inv = zh.inv();
+
+    // L_i(xi) = w^i Z_H(xi) / (n (xi - w^i)), and PI(xi) = -sum(public_i L_i(xi))
+    let mut lagrange = Vec::with_capacity(vk.n_public.max(1));
+    let mut w = F::one();
+    for _ in 0..vk.n_public.max(1) {
+        lagrange.push(w * zh * (F::from_u32(n as u32) * (xi - w)).inv());
+        w = w * vk.w;
+    }
+    let pi = public
+        .iter()
+        .zip(&lagrange)
+        .fold(F::zero(), |acc, (value, l)| acc - *value * *l);
+
+    let t0 = (ql * a + qr * b + qm * a * b + qo * c + qc + pi) * zh_inv;
+    let t1 = (z - F::one()) * lagrange[0] * zh_inv;
+    let t2 = ((a + beta * xi + gamma) * (b + beta * vk.k1 * xi + gamma) * (c + beta * vk.k2 * xi + gamma) * z
+        - (a + beta * s1 + gamma) * (b + beta * s2 + gamma) * (c + beta * s3 + gamma) * zw)
+        * zh_inv;
+
+    // The remainders of C0 and C1 modulo X^8 - xi and X^4 - xi take the evaluations at xi as
+    // coefficients. Modulo (X^3 - xi)(X^3 - xi w), each part P of C2 leaves the line through
+    // (xi, P(xi)) and (xi w, P(xi w)) in X^3.
+    let y3 = y * y * y;
+    let r0 = evaluate(&[ql, qr, qo, qm, qc, s1, s2, s3], &y);
+    let r1 = evaluate(&[a, b, c, t0], &y);
+    let slope = (y3 - xi) * (xiw - xi).inv();
+    let r2 = evaluate(&[z + (zw - z) * slope, t1 + (t1w - t1) * slope, t2 + (t2w - t2) * slope], &y);
+
+    let z_s0 = y3 * y3 * y * y - xi;
+    let z_s1 = y3 * y - xi;
+    let z_s2 = (y3 - xi) * (y3 - xiw);
+    let quotient1 = alpha * z_s0 * z_s1.inv();
+    let quotient2 = alpha * alpha * z_s0 * z_s2.inv();
+
+    // e(-(F - E - J + y W2), g2) e(W2, x_2) = 1
+    let f = vk.c0.to_projective() + commit_c1.to_projective() * quotient1 + commit_c2.to_projective() * quotient2;
+    let e = C1::get_generator() * (r0 + quotient1 * r1 + quotient2 * r2);
+    let j = commit_w1.to_projective() * z_s0;
+    let a1 = f - e - j + commit_w2.to_projective() * y;
+
+    let neg_a1: G1 = (ProjectiveG1::zero() - a1).into();
+    let g2: G2 = C2::get_generator().into();
+    let x_2 = vk.x_2;
+
+    let first_thread = std::thread::spawn(move || pairing(&neg_a1, &g2).unwrap());
+    let second_thread = std::thread::spawn(move || pairing(&commit_w2, &x_2).unwrap());
+
+    let first = first_thread.join().unwrap();
+    let second = second_thread.join().unwrap();
+
+    Ok(PairingTargetField::one() == first * second)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Blinding;
+    use crate::proof_helper::tests::{cpu_config, fixture};
+    use crate::CacheManager;
+    use icicle_runtime::Device;
+    use rand_core::OsRng;
+
+    const ZKEY: &str = "fflonk/circuit.zkey";
+
+    fn fflonk_cache(manager: &mut CacheManager) -> &mut FflonkCache {
+        icicle_runtime::set_device(&Device::new("CPU", 0)).unwrap();
+        let cache = manager.compute_fflonk(&fixture(ZKEY)).unwrap();
+        manager.insert_fflonk_cache(ZKEY, cache);
+        let cache = manager.get_fflonk_cache(ZKEY);
+        cache.prepare();
+        cache
+    }
+
+    fn read_json(path: &str) -> Value {
+        serde_json::from_str(&std::fs::read_to_string(fixture(path)).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_fixed_proof_matches_fixture() {
+        let mut manager = CacheManager::default();
+        let cache = fflonk_cache(&mut manager);
+        let config = ProverConfig {
+            blinding: Blinding::Fixed,
+            ..cpu_config()
+        };
+
+        let (proof, public) = fflonk_prove_helper(&fixture("fflonk/witness.wtns"), cache, &config, &mut OsRng).unwrap();
+
+        assert_eq!(proof, read_json("fflonk/proof.json"));
+        assert_eq!(public, read_json("fflonk/public.json"));
+    }
+
+    /// `proof_blinded.json` was made with random blinding and checked with a separate verifier;
+    /// `proof_tampered.json` is a copy with the evaluation of `a` changed.
+    #[test]
+    fn test_fixture_proof_verifies() {
+        let vk: FflonkVerificationKey = serde_json::from_value(read_json("fflonk/verification_key.json")).unwrap();
+        let public: Vec<String> = serde_json::from_value(read_json("fflonk/public.json")).unwrap();
+
+        let proof: FflonkProof = serde_json::from_value(read_json("fflonk/proof_blinded.json")).unwrap();
+        assert!(fflonk_verify_helper(&proof, &public, &vk).unwrap());
+
+        let tampered: FflonkProof = serde_json::from_value(read_json("fflonk/proof_tampered.json")).unwrap();
+        assert!(!fflonk_verify_helper(&tampered, &public, &vk).unwrap());
+        let (public, vk) = (fixture("fflonk/public.json"), fixture("fflonk/verification_key.json"));
+        assert!(!crate::fflonk_verify(&fixture("fflonk/proof_tampered.json"), &public, &vk).unwrap());
+    }
+
+    #[test]
+    fn test_blinded_proof_round_trip() {
+        let mut manager = CacheManager::default();
+        let cache = fflonk_cache(&mut manager);
+        let vk: FflonkVerificationKey = serde_json::from_value(read_json("fflonk/verification_key.json")).unwrap();
+
+        let (proof, public) =
+            fflonk_prove_helper(&fixture("fflonk/witness.wtns"), cache, &cpu_config(), &mut OsRng).unwrap();
+        let proof: FflonkProof = serde_json::from_value(proof).unwrap();
+        let mut public: Vec<String> = serde_json::from_value(public).unwrap();
+
+        assert!(fflonk_verify_helper(&proof, &public, &vk).unwrap());
+        public[0] = "37".to_string();
+        assert!(!fflonk_verify_helper(&proof, &public, &vk).unwrap());
+    }
+}
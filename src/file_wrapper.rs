@@ -3,14 +3,16 @@ use icicle_core::traits::FieldImpl;
 use memmap::{Mmap, MmapOptions};
 use serde::Serialize;
 use std::fs::{File, OpenOptions};
-use std::io::{self, BufWriter, Read, Seek, SeekFrom};
+use std::io::{self, BufWriter, Read, Seek, SeekFrom, Write};
 use std::mem;
 use std::path::Path;
 
-use crate::zkey::ZKey;
+use crate::zkey::{FflonkZKey, PlonkZKey, ZKey};
 use crate::{F, G1, G2};
 
-const GROTH16_PROTOCOL_ID: u32 = 1;
+pub(crate) const GROTH16_PROTOCOL_ID: u32 = 1;
+pub(crate) const PLONK_PROTOCOL_ID: u32 = 2;
+pub(crate) const FFLONK_PROTOCOL_ID: u32 = 10;
 
 #[derive(Clone, Debug)]
 pub struct Wtsn {
@@ -51,10 +53,10 @@ impl FileWrapper {
             .read(true)
             .write(true)
             .open(file_name)
-            .unwrap();
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", file_name, e)))?;
 
         let mut buf = [0; 4];
-        file.read_exact(&mut buf).unwrap();
+        file.read_exact(&mut buf)?;
         let read_type = String::from_utf8(buf.to_vec()).expect("Invalid UTF-8 sequence");
 
         if read_type != expected_type {
@@ -65,7 +67,7 @@ impl FileWrapper {
         }
 
         let mut version_buf = [0; 4];
-        file.read_exact(&mut version_buf).unwrap();
+        file.read_exact(&mut version_buf)?;
         let version = u32::from_le_bytes(version_buf);
 
         if version > max_version {
@@ -76,27 +78,27 @@ impl FileWrapper {
         }
 
         let mut sections_count_buf = [0; 4];
-        file.read_exact(&mut sections_count_buf).unwrap();
+        file.read_exact(&mut sections_count_buf)?;
         let n_sections = u32::from_le_bytes(sections_count_buf);
 
         let mut sections: Vec<Vec<Section>> = vec![Vec::new(); (n_sections + 1) as usize];
 
         for _ in 0..n_sections {
             let mut ht_buf = [0; 4];
-            file.read_exact(&mut ht_buf).unwrap();
+            file.read_exact(&mut ht_buf)?;
             let ht = u32::from_le_bytes(ht_buf) as usize;
 
             let mut hl_buf = [0; 8];
-            file.read_exact(&mut hl_buf).unwrap();
+            file.read_exact(&mut hl_buf)?;
             let hl = u64::from_le_bytes(hl_buf);
 
-            let current_pos = file.stream_position().unwrap();
+            let current_pos = file.stream_position()?;
             sections[ht].push(Section {
                 p: current_pos,
                 size: hl,
             });
 
-            file.seek(SeekFrom::Current(hl as i64)).unwrap();
+            file.seek(SeekFrom::Current(hl as i64))?;
         }
 
         Ok((file, sections))
@@ -112,6 +114,33 @@ impl FileWrapper {
         Ok(())
     }
 
+    /// Writes `witness` as a version 2 `.wtns` file over the field of order `q`, given as its
+    /// `n8` little-endian bytes.
+    pub fn save_wtns_file<P: AsRef<Path>>(path: P, q: &[u8], witness: &[F]) -> io::Result<()> {
+        let n8 = q.len();
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        writer.write_all(b"wtns")?;
+        writer.write_all(&2u32.to_le_bytes())?;
+        writer.write_all(&2u32.to_le_bytes())?;
+
+        writer.write_all(&1u32.to_le_bytes())?;
+        writer.write_all(&(4 + n8 as u64 + 4).to_le_bytes())?;
+        writer.write_all(&(n8 as u32).to_le_bytes())?;
+        writer.write_all(q)?;
+        writer.write_all(&(witness.len() as u32).to_le_bytes())?;
+
+        writer.write_all(&2u32.to_le_bytes())?;
+        writer.write_all(&((witness.len() * n8) as u64).to_le_bytes())?;
+        for value in witness {
+            let mut bytes = value.to_bytes_le();
+            bytes.resize(n8, 0);
+            writer.write_all(&bytes)?;
+        }
+
+        writer.flush()
+    }
+
     pub fn start_read_unique_section(
         &mut self,
         sections: &[Vec<Section>],
@@ -167,11 +196,11 @@ impl FileWrapper {
     }
 
     pub fn read_wtns_header(&mut self, sections: &[Vec<Section>]) -> io::Result<Wtsn> {
-        self.start_read_unique_section(sections, 1).unwrap();
-        let n8 = self.read_u32_le().unwrap() as usize;
-        let q = self.read_big_int(n8, None).unwrap();
-        let n_witness = self.read_u32_le().unwrap() as usize;
-        self.end_read_section(false).unwrap();
+        self.start_read_unique_section(sections, 1)?;
+        let n8 = self.read_u32_le()? as usize;
+        let q = self.read_big_int(n8, None)?;
+        let n_witness = self.read_u32_le()? as usize;
+        self.end_read_section(false)?;
 
         Ok(Wtsn { n8, q, n_witness })
     }
@@ -193,13 +222,25 @@ impl FileWrapper {
         Ok(&self.mmap[start..end])
     }
 
-    pub fn read_zkey_header(&mut self, sections: &[Vec<Section>]) -> io::Result<ZKey> {
-        self.start_read_unique_section(sections, 1).unwrap();
-        let protocol_id = self.read_u32_le().unwrap();
-        self.end_read_section(false).unwrap();
+    pub fn read_protocol_id(&mut self, sections: &[Vec<Section>]) -> io::Result<u32> {
+        self.start_read_unique_section(sections, 1)?;
+        let protocol_id = self.read_u32_le()?;
+        self.end_read_section(false)?;
 
-        match protocol_id {
+        Ok(protocol_id)
+    }
+
+    pub fn read_zkey_header(&mut self, sections: &[Vec<Section>]) -> io::Result<ZKey> {
+        match self.read_protocol_id(sections)? {
             GROTH16_PROTOCOL_ID => ZKey::read_header_groth16(self, sections),
+            PLONK_PROTOCOL_ID => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "PLONK zkey, prove it with the plonk proof system",
+            )),
+            FFLONK_PROTOCOL_ID => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "FFLONK zkey, prove it with the fflonk proof system",
+            )),
             _ => Err(io::Error::new(
                 io::ErrorKind::InvalidData,
                 "Protocol not supported",
@@ -207,6 +248,26 @@ impl FileWrapper {
         }
     }
 
+    pub fn read_plonk_zkey_header(&mut self, sections: &[Vec<Section>]) -> io::Result<PlonkZKey> {
+        match self.read_protocol_id(sections)? {
+            PLONK_PROTOCOL_ID => PlonkZKey::read_header_plonk(self, sections),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Not a PLONK zkey",
+            )),
+        }
+    }
+
+    pub fn read_fflonk_zkey_header(&mut self, sections: &[Vec<Section>]) -> io::Result<FflonkZKey> {
+        match self.read_protocol_id(sections)? {
+            FFLONK_PROTOCOL_ID => FflonkZKey::read_header_fflonk(self, sections),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Not an FFLONK zkey",
+            )),
+        }
+    }
+
     pub fn read_g1(&mut self) -> G1 {
         let mut x = [0u8; 32];
         self.file.read_exact(&mut x).unwrap();
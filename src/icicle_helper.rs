@@ -1,18 +1,86 @@
-use crate::F;
+use crate::{cache::{BasesRef, MsmParams}, F};
 use icicle_core::{
     curve::{Affine, Curve, Projective},
-    msm::{msm, MSMConfig, MSM},
-    ntt::{ntt_inplace, NTTConfig, NTTDir, NTT},
-    traits::FieldImpl,
+    msm::{msm, precompute_bases, MSMConfig, CUDA_MSM_IS_BIG_TRIANGLE, CUDA_MSM_LARGE_BUCKET_FACTOR, MSM},
+    ntt::{get_root_of_unity, initialize_domain, ntt_inplace, release_domain, NTTConfig, NTTDir, NTTInitDomainConfig, NTT},
+    traits::{FieldImpl, MontgomeryConvertible},
 };
 use icicle_runtime::{
-    memory::{DeviceSlice, DeviceVec, HostOrDeviceSlice},
+    errors::eIcicleError,
+    memory::{DeviceSlice, DeviceVec, HostOrDeviceSlice, HostSlice},
     stream::IcicleStream,
+    Device,
 };
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock, RwLock, RwLockReadGuard};
+
+/// Size of the NTT domain initialized on each device, keyed as in `domain_key`.
+///
+/// icicle keeps a single domain per device, shared by every thread and cache that uses the
+/// device. Domains are never released for a smaller one: they only grow, and smaller NTTs run
+/// on the larger domain.
+fn ntt_domains() -> &'static Mutex<HashMap<String, &'static RwLock<u64>>> {
+    static DOMAINS: OnceLock<Mutex<HashMap<String, &'static RwLock<u64>>>> = OnceLock::new();
+    DOMAINS.get_or_init(Default::default)
+}
+
+/// The CPU backend keeps one domain for all CPU devices, other backends one per device.
+fn domain_key(device: &Device) -> String {
+    match device.get_device_type().as_str() {
+        "CPU" => "CPU".to_string(),
+        device_type => format!("{}:{}", device_type, device.id),
+    }
+}
+
+/// Makes sure the NTT domain of the active device supports NTTs of `size` points, and keeps it
+/// from being replaced until the guard is dropped.
+///
+/// Growing the domain waits for every guard of the device to be dropped, then for the device
+/// to finish the NTTs already queued on it.
+pub(crate) fn ntt_domain(size: usize) -> RwLockReadGuard<'static, u64> {
+    let device = icicle_runtime::get_active_device().unwrap();
+    let domain: &'static RwLock<u64> = ntt_domains()
+        .lock()
+        .unwrap()
+        .entry(domain_key(&device))
+        .or_insert_with(|| Box::leak(Box::new(RwLock::new(0))));
+    let size = size.next_power_of_two() as u64;
+
+    loop {
+        let initialized = domain.read().unwrap();
+        if *initialized >= size {
+            return initialized;
+        }
+        drop(initialized);
+
+        let mut initialized = domain.write().unwrap();
+        if *initialized < size {
+            if *initialized > 0 {
+                icicle_runtime::runtime::device_synchronize().unwrap();
+                release_domain::<F>().unwrap();
+            }
+            initialize_domain(get_root_of_unity::<F>(size), &NTTInitDomainConfig::default()).unwrap();
+            *initialized = size;
+        }
+    }
+}
 
 pub fn ntt_helper(vec: &mut DeviceSlice<F>, inverse: bool, coset_gen: Option<&F>, stream: &IcicleStream)
 where
     <F as FieldImpl>::Config: NTT<F, F>,
+{
+    ntt_batch_helper(vec, inverse, coset_gen, 3, stream);
+}
+
+/// Transforms the `batch_size` consecutive vectors `vec` is made of.
+pub fn ntt_batch_helper(
+    vec: &mut DeviceSlice<F>,
+    inverse: bool,
+    coset_gen: Option<&F>,
+    batch_size: usize,
+    stream: &IcicleStream,
+) where
+    <F as FieldImpl>::Config: NTT<F, F>,
 {
     let dir = if inverse {
         NTTDir::kInverse
@@ -22,26 +90,278 @@ where
 
     let mut cfg1 = NTTConfig::<F>::default();
     cfg1.is_async = true;
-    cfg1.batch_size = 3;
+    cfg1.batch_size = batch_size as i32;
     cfg1.stream_handle = stream.into();
     if let Some(coset_gen) = coset_gen {
         cfg1.coset_gen = *coset_gen;
     }
 
+    let _domain = ntt_domain(vec.len() / batch_size);
     ntt_inplace(vec, dir, &cfg1).unwrap();
 }
 
-pub fn msm_helper<C: Curve + MSM<C>>(
+/// `montgomery` tells whether the bases are still in Montgomery form, as stored in a zkey.
+fn msm_config(params: &MsmParams, montgomery: bool, stream: &IcicleStream) -> MSMConfig {
+    let mut msm_config = MSMConfig::default();
+    msm_config.stream_handle = stream.into();
+    msm_config.is_async = true;
+    msm_config.are_bases_montgomery_form = montgomery;
+    msm_config.precompute_factor = params.precompute_factor;
+    msm_config.c = params.c;
+    if params.large_bucket_factor > 0 {
+        msm_config
+            .ext
+            .set_int(CUDA_MSM_LARGE_BUCKET_FACTOR, params.large_bucket_factor);
+    }
+    if params.is_big_triangle {
+        msm_config
+            .ext
+            .set_bool(CUDA_MSM_IS_BIG_TRIANGLE, true);
+    }
+    msm_config
+}
+
+/// Extends `points`, in Montgomery form, with `params.precompute_factor - 1` shifted copies
+/// each, for MSMs with window `params.c`. The extended bases come out of Montgomery form.
+/// Fails if they do not fit in device memory.
+///
+/// The points are converted out of Montgomery form before they are extended: given Montgomery
+/// input, the CPU backend writes Montgomery points back but the CUDA one writes plain points.
+pub fn precompute_bases_helper<C: Curve + MSM<C>>(
+    points: &[Affine<C>],
+    params: &MsmParams,
+    stream: &IcicleStream,
+) -> Result<DeviceVec<Affine<C>>, eIcicleError> {
+    let mut d_points = DeviceVec::<Affine<C>>::device_malloc_async(points.len(), stream)?;
+    d_points.copy_from_host_async(HostSlice::from_slice(points), stream)?;
+    Affine::from_mont(&mut d_points, stream).wrap()?;
+
+    let factor = params.precompute_factor as usize;
+    let mut precomputed = DeviceVec::<Affine<C>>::device_malloc_async(points.len() * factor, stream)?;
+    let msm_config = msm_config(params, false, stream);
+
+    precompute_bases(&d_points[..], &msm_config, &mut precomputed[..])?;
+    stream.synchronize()?;
+
+    Ok(precomputed)
+}
+
+/// Computes `batch_size` MSMs over consecutive chunks of `scalars` that all share `points`,
+/// extended by `params.precompute_factor`. `montgomery` tells whether the points are in
+/// Montgomery form, which the backend then converts them out of on every call.
+pub fn msm_batch_helper<C: Curve + MSM<C>>(
     scalars: &(impl HostOrDeviceSlice<C::ScalarField> + ?Sized),
     points: &(impl HostOrDeviceSlice<Affine<C>> + ?Sized),
+    params: &MsmParams,
+    montgomery: bool,
+    batch_size: usize,
     stream: &IcicleStream,
 ) -> DeviceVec<Projective<C>> {
-    let mut msm_result = DeviceVec::<Projective<C>>::device_malloc_async(1, stream).unwrap();
-    let mut msm_config = MSMConfig::default();
-    msm_config.stream_handle = stream.into();
-    msm_config.is_async = true;
+    let mut msm_result = DeviceVec::<Projective<C>>::device_malloc_async(batch_size, stream).unwrap();
+    let msm_config = msm_config(params, montgomery, stream);
 
     msm(scalars, points, &msm_config, &mut msm_result[..]).unwrap();
 
     msm_result
 }
+
+/// Computes `batch_size` MSMs of `scalars` over `bases`, wherever the bases are kept, with the
+/// window of `params`. The precompute factor is the one `bases` were extended with.
+///
+/// Device and precomputed bases were converted out of Montgomery form when the cache was
+/// built. Host bases are read in place, in the Montgomery form of the zkey.
+/// Mapped bases are copied to the device one chunk at a time through two buffers, alternating
+/// between `stream` and a second stream so that copying a chunk overlaps the MSM of the previous
+/// one. The result holds one partial sum per chunk and proof, chunk-major; see `sum_partials`.
+pub fn msm_bases_helper<C: Curve + MSM<C>>(
+    scalars: &DeviceSlice<C::ScalarField>,
+    bases: BasesRef<Affine<C>>,
+    params: &MsmParams,
+    batch_size: usize,
+    stream: &IcicleStream,
+) -> DeviceVec<Projective<C>> {
+    let params = MsmParams {
+        precompute_factor: 1,
+        ..*params
+    };
+
+    let (points, chunk_size) = match bases {
+        BasesRef::Device(points) => return msm_batch_helper(scalars, points, &params, false, batch_size, stream),
+        BasesRef::Host(points) => {
+            return msm_batch_helper(scalars, HostSlice::from_slice(points), &params, true, batch_size, stream)
+        }
+        BasesRef::Precomputed { points, factor } => {
+            let params = MsmParams {
+                precompute_factor: factor as i32,
+                ..params
+            };
+            return msm_batch_helper(scalars, points, &params, false, batch_size, stream);
+        }
+        BasesRef::Mapped { points, chunk_size } => (points, chunk_size.min(points.len()).max(1)),
+    };
+
+    let n_points = points.len();
+    let n_chunks = n_points.div_ceil(chunk_size);
+
+    let mut results = DeviceVec::<Projective<C>>::device_malloc_async(n_chunks * batch_size, stream).unwrap();
+    let mut buffers = [
+        DeviceVec::<Affine<C>>::device_malloc_async(chunk_size, stream).unwrap(),
+        DeviceVec::<Affine<C>>::device_malloc_async(chunk_size, stream).unwrap(),
+    ];
+    let mut side_stream = IcicleStream::create().unwrap();
+    stream.synchronize().unwrap();
+
+    for (i, chunk) in points.chunks(chunk_size).enumerate() {
+        // reusing a buffer is safe because every use of it is queued on the same stream
+        let chunk_stream = if i % 2 == 0 { stream } else { &side_stream };
+        let buffer = &mut buffers[i % 2][..chunk.len()];
+
+        buffer
+            .copy_from_host_async(HostSlice::from_slice(chunk), chunk_stream)
+            .unwrap();
+
+        let msm_config = msm_config(&params, true, chunk_stream);
+
+        let start = i * chunk_size;
+        for proof in 0..batch_size {
+            let offset = proof * n_points + start;
+            let result = i * batch_size + proof;
+            msm(
+                &scalars[offset..offset + chunk.len()],
+                &buffer[..],
+                &msm_config,
+                &mut results[result..result + 1],
+            )
+            .unwrap();
+        }
+    }
+
+    // the buffers are released on return
+    stream.synchronize().unwrap();
+    side_stream.synchronize().unwrap();
+    side_stream.destroy().unwrap();
+
+    results
+}
+
+/// Folds the chunk-major partial results of `msm_bases_helper` into one point per proof.
+pub fn sum_partials<C: Curve>(partials: &[Projective<C>], batch_size: usize) -> Vec<Projective<C>> {
+    let mut sums = vec![Projective::<C>::zero(); batch_size];
+    for chunk in partials.chunks(batch_size) {
+        for (sum, partial) in sums.iter_mut().zip(chunk) {
+            *sum = *sum + *partial;
+        }
+    }
+    sums
+}
+
+/// Computes a single MSM of host scalars over host points in canonical form, such as points
+/// parsed from proofs, verification keys or a ptau, on the active device.
+pub fn msm_host_helper<C: Curve + MSM<C>>(scalars: &[C::ScalarField], points: &[Affine<C>]) -> Projective<C> {
+    let mut result = [Projective::<C>::zero()];
+
+    msm(
+        HostSlice::from_slice(scalars),
+        HostSlice::from_slice(points),
+        &MSMConfig::default(),
+        HostSlice::from_mut_slice(&mut result),
+    )
+    .unwrap();
+
+    result[0]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{C1, G1, ProjectiveG1};
+    use icicle_bn254::curve::ScalarCfg;
+    use icicle_core::traits::{GenerateRandom, MontgomeryConvertible};
+
+    /// Random points in Montgomery form, as zkeys store them.
+    fn random_bases(size: usize, stream: &IcicleStream) -> Vec<G1> {
+        let mut d_points = DeviceVec::device_malloc(size).unwrap();
+        d_points
+            .copy_from_host(HostSlice::from_slice(&C1::generate_random_affine_points(size)))
+            .unwrap();
+        G1::to_mont(&mut d_points, stream).wrap().unwrap();
+        stream.synchronize().unwrap();
+
+        let mut points = vec![G1::zero(); size];
+        d_points.copy_to_host(HostSlice::from_mut_slice(&mut points)).unwrap();
+        points
+    }
+
+    #[test]
+    fn test_mapped_bases_match_unchunked_msm() {
+        icicle_runtime::set_device(&Device::new("CPU", 0)).unwrap();
+        let stream = IcicleStream::default();
+
+        let n_points = 37;
+        let batch_size = 2;
+        let points = random_bases(n_points, &stream);
+        let scalars = ScalarCfg::generate_random(n_points * batch_size);
+        let mut d_scalars = DeviceVec::device_malloc(scalars.len()).unwrap();
+        d_scalars.copy_from_host(HostSlice::from_slice(&scalars)).unwrap();
+        let params = MsmParams::default();
+
+        let unchunked = msm_bases_helper(&d_scalars[..], BasesRef::Host(&points), &params, batch_size, &stream);
+        let mut expected = vec![ProjectiveG1::zero(); batch_size];
+        unchunked.copy_to_host(HostSlice::from_mut_slice(&mut expected)).unwrap();
+
+        // 10 does not divide 37, so the last chunk is shorter
+        let mapped = BasesRef::Mapped {
+            points: &points[..],
+            chunk_size: 10,
+        };
+        let chunked = msm_bases_helper(&d_scalars[..], mapped, &params, batch_size, &stream);
+        let mut partials = vec![ProjectiveG1::zero(); chunked.len()];
+        chunked.copy_to_host(HostSlice::from_mut_slice(&mut partials)).unwrap();
+
+        assert_eq!(partials.len(), n_points.div_ceil(10) * batch_size);
+        assert_eq!(sum_partials(&partials, batch_size), expected);
+    }
+
+    /// Every device type with a device 0, starting with the CPU.
+    fn available_devices() -> Vec<Device> {
+        let _ = icicle_runtime::runtime::load_backend_from_env_or_default();
+        let mut devices = vec![Device::new("CPU", 0)];
+        for device_type in icicle_runtime::runtime::get_registered_devices().unwrap_or_default() {
+            let device = Device::new(&device_type, 0);
+            if device_type != "CPU" && icicle_runtime::is_device_available(&device) {
+                devices.push(device);
+            }
+        }
+        devices
+    }
+
+    #[test]
+    fn test_precomputed_bases_match_plain_msm() {
+        for device in available_devices() {
+            icicle_runtime::set_device(&device).unwrap();
+            let stream = IcicleStream::default();
+
+            let n_points = 64;
+            let points = random_bases(n_points, &stream);
+            let scalars = ScalarCfg::generate_random(n_points);
+            let mut d_scalars = DeviceVec::device_malloc(n_points).unwrap();
+            d_scalars.copy_from_host(HostSlice::from_slice(&scalars)).unwrap();
+
+            let plain = msm_bases_helper(&d_scalars[..], BasesRef::Host(&points), &MsmParams::default(), 1, &stream);
+            let mut expected = [ProjectiveG1::zero()];
+            plain.copy_to_host(HostSlice::from_mut_slice(&mut expected)).unwrap();
+
+            let params = MsmParams::precomputed(4, 0).resolve(n_points);
+            let precomputed = precompute_bases_helper(&points, &params, &stream).unwrap();
+            let bases = BasesRef::Precomputed {
+                points: &precomputed[..],
+                factor: 4,
+            };
+            let result = msm_bases_helper(&d_scalars[..], bases, &params, 1, &stream);
+            let mut actual = [ProjectiveG1::zero()];
+            result.copy_to_host(HostSlice::from_mut_slice(&mut actual)).unwrap();
+
+            assert_eq!(actual, expected, "precomputed bases on {:?}", device);
+        }
+    }
+}
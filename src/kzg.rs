@@ -0,0 +1,316 @@
+//! KZG polynomial commitments over the powers of tau of a `.ptau`.
+//!
+//! A commitment to `f` is `f(tau) * g1`. An opening at `z` is the commitment to
+//! `(f(X) - f(z)) / (X - z)`, checked with `e(proof, tau * g2 - z * g2) = e(C - f(z) * g1, g2)`.
+//! Several polynomials are opened at one point with a single proof of their combination
+//! `sum gamma^i f_i`, where `gamma` is a Keccak-256 challenge on the commitments, the point
+//! and the values.
+
+use icicle_core::{
+    msm::{msm, MSMConfig},
+    pairing::pairing,
+    polynomials::Polynomial,
+    traits::FieldImpl,
+};
+use icicle_runtime::memory::{DeviceVec, HostOrDeviceSlice, HostSlice};
+use std::io;
+
+use crate::{
+    icicle_helper::ntt_domain,
+    plonk_helper::combine,
+    ptau::PowersOfTau,
+    transcript::{Transcript, TranscriptHash},
+    ProjectiveG1, F, G1, G2, GT,
+};
+
+/// Powers `tau^i * g1` on the active device, enough to commit to polynomials of degree up to
+/// `max_degree`, and the verifier's share of the SRS.
+pub struct KzgSrs {
+    pub powers: DeviceVec<G1>,
+    pub verifier_key: KzgVerifierKey,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct KzgVerifierKey {
+    pub g1: G1,
+    pub g2: G2,
+    pub tau_g2: G2,
+}
+
+/// `f(z) = value`, proven by `proof`.
+#[derive(Clone, Copy, Debug)]
+pub struct KzgOpening {
+    pub value: F,
+    pub proof: G1,
+}
+
+/// The values of several polynomials at one point, proven together by `proof`.
+#[derive(Clone, Debug)]
+pub struct KzgBatchOpening {
+    pub values: Vec<F>,
+    pub proof: G1,
+}
+
+impl KzgSrs {
+    /// Uploads the powers of tau `powers` in G1, given `g2` and `tau * g2`.
+    pub fn new(powers: &[G1], g2: G2, tau_g2: G2) -> Self {
+        let mut d_powers = DeviceVec::device_malloc(powers.len()).unwrap();
+        d_powers.copy_from_host(HostSlice::from_slice(powers)).unwrap();
+
+        Self {
+            powers: d_powers,
+            verifier_key: KzgVerifierKey {
+                g1: powers[0],
+                g2,
+                tau_g2,
+            },
+        }
+    }
+
+    /// Takes the SRS for polynomials of degree up to `max_degree` from a `.ptau` file, which
+    /// must hold at least `max_degree + 1` G1 powers.
+    pub fn from_ptau(path: &str, max_degree: usize) -> io::Result<Self> {
+        let ptau = PowersOfTau::read(path, max_degree + 1, 2)?;
+
+        Ok(Self::new(&ptau.tau_g1, ptau.tau_g2[0], ptau.tau_g2[1]))
+    }
+
+    pub fn max_degree(&self) -> usize {
+        self.powers.len() - 1
+    }
+}
+
+fn check_degree(srs: &KzgSrs, degree: usize) -> Result<(), Box<dyn std::error::Error>> {
+    if degree > srs.max_degree() {
+        return Err(format!(
+            "Polynomial of degree {} exceeds the SRS maximum degree {}",
+            degree,
+            srs.max_degree()
+        )
+        .into());
+    }
+    Ok(())
+}
+
+fn commit_coefficients(srs: &KzgSrs, coefficients: &(impl HostOrDeviceSlice<F> + ?Sized)) -> G1 {
+    let mut result = [ProjectiveG1::zero()];
+    msm(
+        coefficients,
+        &srs.powers[..coefficients.len()],
+        &MSMConfig::default(),
+        HostSlice::from_mut_slice(&mut result),
+    )
+    .unwrap();
+
+    result[0].into()
+}
+
+fn batch_challenge(commitments: &[G1], z: &F, values: &[F]) -> F {
+    let mut transcript = Transcript::new(TranscriptHash::Keccak256);
+    for commitment in commitments {
+        transcript.add_point(commitment);
+    }
+    transcript.add_scalar(z);
+    for value in values {
+        transcript.add_scalar(value);
+    }
+    transcript.challenge()
+}
+
+pub fn kzg_commit_helper(srs: &KzgSrs, polynomial: &Polynomial<F>) -> Result<G1, Box<dyn std::error::Error>> {
+    check_degree(srs, polynomial.degree())?;
+    Ok(commit_coefficients(srs, polynomial.coefficients()))
+}
+
+pub fn kzg_open_helper(
+    srs: &KzgSrs,
+    polynomial: &Polynomial<F>,
+    z: &F,
+) -> Result<KzgOpening, Box<dyn std::error::Error>> {
+    check_degree(srs, polynomial.degree())?;
+
+    let value = polynomial.evaluate(z).unwrap();
+    let proof = open(srs, polynomial, &value, z);
+
+    Ok(KzgOpening { value, proof })
+}
+
+/// Commits to `(f(X) - f(z)) / (X - z)`, given `value = f(z)`.
+fn open(srs: &KzgSrs, polynomial: &Polynomial<F>, value: &F, z: &F) -> G1 {
+    if polynomial.degree() == 0 {
+        return G1::zero();
+    }
+
+    let _domain = ntt_domain(polynomial.degree() + 1);
+    let quotient = polynomial
+        .add_monomial(&(F::zero() - *value), 0)
+        .and_then(|shifted| shifted.divide_by_linear(z))
+        .unwrap();
+    commit_coefficients(srs, quotient.coefficients())
+}
+
+/// Opens every polynomial at `z` with one proof. `commitments` must be the commitments to
+/// `polynomials`, in the same order, as the batching challenge depends on them.
+pub fn kzg_batch_open_helper(
+    srs: &KzgSrs,
+    polynomials: &[&Polynomial<F>],
+    commitments: &[G1],
+    z: &F,
+) -> Result<KzgBatchOpening, Box<dyn std::error::Error>> {
+    if polynomials.len() != commitments.len() {
+        return Err(format!(
+            "{} polynomials given with {} commitments",
+            polynomials.len(),
+            commitments.len()
+        )
+        .into());
+    }
+
+    let mut values = Vec::with_capacity(polynomials.len());
+    for polynomial in polynomials {
+        check_degree(srs, polynomial.degree())?;
+        values.push(polynomial.evaluate(z).unwrap());
+    }
+
+    let gamma = batch_challenge(commitments, z, &values);
+
+    if polynomials.is_empty() {
+        return Ok(KzgBatchOpening { values, proof: G1::zero() });
+    }
+
+    // sum gamma^i f_i and its value at z
+    let mut terms = Vec::with_capacity(polynomials.len());
+    let mut value = F::zero();
+    let mut power = F::one();
+    for (polynomial, polynomial_value) in polynomials.iter().zip(&values) {
+        terms.push((*polynomial, power));
+        value = value + power * *polynomial_value;
+        power = power * gamma;
+    }
+
+    let proof = open(srs, &combine(&terms), &value, z);
+
+    Ok(KzgBatchOpening { values, proof })
+}
+
+pub fn kzg_verify_helper(vk: &KzgVerifierKey, commitment: &G1, z: &F, opening: &KzgOpening) -> bool {
+    check_opening(vk, commitment.to_projective(), z, &opening.value, &opening.proof)
+}
+
+pub fn kzg_batch_verify_helper(
+    vk: &KzgVerifierKey,
+    commitments: &[G1],
+    z: &F,
+    opening: &KzgBatchOpening,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    if opening.values.len() != commitments.len() {
+        return Err(format!(
+            "{} values opened for {} commitments",
+            opening.values.len(),
+            commitments.len()
+        )
+        .into());
+    }
+
+    let gamma = batch_challenge(commitments, z, &opening.values);
+
+    // sum gamma^i * C_i and sum gamma^i * v_i
+    let commitment = commitments
+        .iter()
+        .rev()
+        .fold(ProjectiveG1::zero(), |acc, c| acc * gamma + c.to_projective());
+    let value = opening
+        .values
+        .iter()
+        .rev()
+        .fold(F::zero(), |acc, v| acc * gamma + *v);
+
+    Ok(check_opening(vk, commitment, z, &value, &opening.proof))
+}
+
+/// `e(-proof, tau * g2) * e(C - value * g1 + z * proof, g2) = 1`
+fn check_opening(vk: &KzgVerifierKey, commitment: ProjectiveG1, z: &F, value: &F, proof: &G1) -> bool {
+    let lhs: G1 = (ProjectiveG1::zero() - proof.to_projective()).into();
+    let rhs: G1 = (commitment - vk.g1.to_projective() * *value + proof.to_projective() * *z).into();
+
+    let first: GT = pairing(&lhs, &vk.tau_g2).unwrap();
+    let second: GT = pairing(&rhs, &vk.g2).unwrap();
+
+    first * second == GT::one()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{C1, C2};
+    use icicle_core::curve::Curve;
+
+    fn test_srs(max_degree: usize) -> KzgSrs {
+        let tau = F::from_u32(0x1234_5678);
+        let g1 = C1::get_generator();
+        let g2 = C2::get_generator();
+
+        let mut powers = Vec::with_capacity(max_degree + 1);
+        let mut power = F::one();
+        for _ in 0..=max_degree {
+            powers.push((g1 * power).into());
+            power = power * tau;
+        }
+
+        KzgSrs::new(&powers, g2.into(), (g2 * tau).into())
+    }
+
+    fn polynomial(coefficients: &[u32]) -> Polynomial<F> {
+        let coefficients: Vec<F> = coefficients.iter().map(|&c| F::from_u32(c)).collect();
+        Polynomial::from_coeffs(HostSlice::from_slice(&coefficients)).unwrap()
+    }
+
+    #[test]
+    fn test_open_verifies() {
+        let srs = test_srs(8);
+        let f = polynomial(&[3, 1, 4, 1, 5, 9, 2, 6]);
+        let z = F::from_u32(10);
+
+        let commitment = kzg_commit_helper(&srs, &f).unwrap();
+        let opening = kzg_open_helper(&srs, &f, &z).unwrap();
+
+        assert_eq!(opening.value, F::from_u32(62_951_413));
+        assert!(kzg_verify_helper(&srs.verifier_key, &commitment, &z, &opening));
+
+        let wrong = KzgOpening {
+            value: opening.value + F::one(),
+            ..opening
+        };
+        assert!(!kzg_verify_helper(&srs.verifier_key, &commitment, &z, &wrong));
+    }
+
+    #[test]
+    fn test_batch_open_verifies() {
+        let srs = test_srs(8);
+        let f = polynomial(&[3, 1, 4, 1, 5]);
+        let g = polynomial(&[2, 7, 1, 8, 2, 8, 1, 8]);
+        let h = polynomial(&[42]);
+        let z = F::from_u32(7);
+
+        let commitments: Vec<G1> = [&f, &g, &h]
+            .iter()
+            .map(|p| kzg_commit_helper(&srs, p).unwrap())
+            .collect();
+        let opening = kzg_batch_open_helper(&srs, &[&f, &g, &h], &commitments, &z).unwrap();
+
+        assert_eq!(opening.values[2], F::from_u32(42));
+        assert!(kzg_batch_verify_helper(&srs.verifier_key, &commitments, &z, &opening).unwrap());
+
+        let mut swapped = commitments.clone();
+        swapped.swap(0, 1);
+        assert!(!kzg_batch_verify_helper(&srs.verifier_key, &swapped, &z, &opening).unwrap());
+    }
+
+    #[test]
+    fn test_commit_rejects_large_degree() {
+        let srs = test_srs(2);
+        let f = polynomial(&[1, 2, 3, 4]);
+
+        assert!(kzg_commit_helper(&srs, &f).is_err());
+    }
+}
@@ -1,16 +1,59 @@
+mod aggregation;
+mod autotune;
 mod cache;
+mod config;
 mod conversions;
+mod device_pool;
+mod fflonk_helper;
 mod file_wrapper;
 mod icicle_helper;
+mod kzg;
+mod plonk_helper;
+mod poseidon;
 mod proof_helper;
+mod ptau;
+mod symbols;
+mod transcript;
+mod witness;
+mod witness_calculator;
 mod zkey;
+mod zkey_inspect;
 
-use cache::VerificationKey;
-pub use cache::{CacheManager, ZKeyCache};
+pub use aggregation::{AggregateProof, AggregationSrs, AggregationVerifierKey, GipaRound};
+pub use cache::{
+    CacheManager, FflonkCache, FflonkVerificationKey, MsmParams, MsmSettings, MsmSplit, PlonkCache, PlonkVerificationKey,
+    VerificationKey,
+    ZKeyCache,
+};
+pub use config::{Blinding, BlindingFactors, ProverConfig, QuotientStrategy};
+pub use symbols::Symbols;
+pub use zkey_inspect::{CacheMemory, ZKeyInfo};
+use zkey_inspect::inspect_zkey_helper;
+pub use device_pool::{DevicePool, PoolTask};
+pub use icicle_core::polynomials::Polynomial;
+pub use icicle_runtime::Device;
 use file_wrapper::FileWrapper;
+use aggregation::{aggregate_helper, aggregate_verify_helper};
 use icicle_bn254::curve::{CurveCfg, G2CurveCfg, ScalarField};
+use icicle_bn254::pairing::PairingTargetField;
 use icicle_core::curve::{Affine, Projective};
-use proof_helper::{groth16_prove_helper, groth16_verify_helper, Proof};
+use fflonk_helper::{fflonk_prove_helper, fflonk_verify_helper};
+use kzg::{kzg_batch_open_helper, kzg_batch_verify_helper, kzg_commit_helper, kzg_open_helper, kzg_verify_helper};
+pub use kzg::{KzgBatchOpening, KzgOpening, KzgSrs, KzgVerifierKey};
+pub use fflonk_helper::{FflonkCommitments, FflonkEvaluations, FflonkProof};
+use plonk_helper::{plonk_prove_helper, plonk_verify_helper};
+pub use plonk_helper::PlonkProof;
+use proof_helper::{groth16_prove_batch_helper, groth16_prove_computed_helper, groth16_prove_helper, groth16_verify_helper, rerandomize_helper};
+pub use proof_helper::{InvalidProof, Proof};
+pub use poseidon::poseidon;
+pub use ptau::PowersOfTau;
+pub use transcript::{Transcript, TranscriptHash};
+use witness::{
+    describe_public_helper, describe_witness_helper, json_to_wtns_helper, read_wtns_helper, write_wtns_helper,
+    wtns_to_json_helper,
+};
+pub use witness_calculator::WitnessCalculator;
+use rand_core::{CryptoRng, CryptoRngCore, OsRng, RngCore};
 use std::time::Instant;
 use serde_json;
 
@@ -21,15 +64,54 @@ pub type G1 = Affine<C1>;
 pub type G2 = Affine<C2>;
 pub type ProjectiveG1 = Projective<C1>;
 pub type ProjectiveG2 = Projective<C2>;
+pub type GT = PairingTargetField;
 
-fn try_load_and_set_backend_device(device_type: &str) {
+fn try_load_backend(device_type: &str) {
     if device_type != "CPU" {
         icicle_runtime::runtime::load_backend_from_env_or_default().unwrap();
     }
-    let device = icicle_runtime::Device::new(device_type, 0 /* =device_id*/);
+}
+
+fn try_load_and_set_backend_device(device_type: &str, device_id: i32) {
+    try_load_backend(device_type);
+    let device = Device::new(device_type, device_id);
     icicle_runtime::set_device(&device).unwrap();
 }
 
+/// Key the cache of `zkey` on the active device is kept under.
+fn cache_key(zkey: &str) -> String {
+    let device = icicle_runtime::get_active_device().unwrap();
+    format!("{}_{}_{}", zkey, device.get_device_type(), device.id)
+}
+
+/// Proves on whatever device is active on the calling thread, caching the zkey per device.
+fn prove_on_active_device(
+    witness: &str,
+    zkey: &str,
+    proof: &str,
+    public: &str,
+    config: &ProverConfig,
+    rng: &mut dyn CryptoRngCore,
+    cache_manager: &mut CacheManager,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let cache_key = cache_key(zkey);
+
+    if !cache_manager.contains(&cache_key) {
+        let computed_cache = cache_manager.compute(zkey)?;
+        cache_manager.insert_cache(&cache_key, computed_cache);
+    }
+
+    let zkey_cache = cache_manager.get_cache(&cache_key);
+    zkey_cache.prepare(config)?;
+
+    let (proof_data, public_signals) = groth16_prove_helper(witness, zkey_cache, config, rng)?;
+
+    FileWrapper::save_json_file(proof, &proof_data)?;
+    FileWrapper::save_json_file(public, &public_signals)?;
+
+    Ok(())
+}
+
 pub fn groth16_prove(
     witness: &str,
     zkey: &str,
@@ -37,11 +119,82 @@ pub fn groth16_prove(
     public: &str,
     device: &str,
     cache_manager: &mut CacheManager,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config = ProverConfig {
+        device: Device::new(device, 0),
+        ..Default::default()
+    };
+    groth16_prove_with_config(witness, zkey, proof, public, &config, cache_manager)
+}
+
+/// Like `groth16_prove`, with every prover option taken from `config`.
+pub fn groth16_prove_with_config(
+    witness: &str,
+    zkey: &str,
+    proof: &str,
+    public: &str,
+    config: &ProverConfig,
+    cache_manager: &mut CacheManager,
+) -> Result<(), Box<dyn std::error::Error>> {
+    groth16_prove_with_rng(witness, zkey, proof, public, config, &mut OsRng, cache_manager)
+}
+
+/// Like `groth16_prove_with_config`, drawing `Blinding::Random` factors from `rng`.
+pub fn groth16_prove_with_rng<R: RngCore + CryptoRng>(
+    witness: &str,
+    zkey: &str,
+    proof: &str,
+    public: &str,
+    config: &ProverConfig,
+    rng: &mut R,
+    cache_manager: &mut CacheManager,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let start = Instant::now();
-    try_load_and_set_backend_device(device);
+    try_load_and_set_backend_device(&config.device.get_device_type(), config.device.id);
 
-    let cache_key = format!("{}_{}", zkey, device);
+    prove_on_active_device(witness, zkey, proof, public, config, rng, cache_manager)?;
+
+    println!("proof took: {:?}", start.elapsed());
+
+    Ok(())
+}
+
+/// Proves the circuit inputs in `input` with the circom witness calculator `wasm`, computing
+/// the witness in memory instead of reading a `.wtns` file. The calculator is compiled once
+/// and kept in `cache_manager`.
+pub fn groth16_prove_from_input(
+    input: &str,
+    wasm: &str,
+    zkey: &str,
+    proof: &str,
+    public: &str,
+    device: &str,
+    cache_manager: &mut CacheManager,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config = ProverConfig {
+        device: Device::new(device, 0),
+        ..Default::default()
+    };
+    groth16_prove_from_input_with_config(input, wasm, zkey, proof, public, &config, cache_manager)
+}
+
+/// Like `groth16_prove_from_input`, with every prover option taken from `config`.
+pub fn groth16_prove_from_input_with_config(
+    input: &str,
+    wasm: &str,
+    zkey: &str,
+    proof: &str,
+    public: &str,
+    config: &ProverConfig,
+    cache_manager: &mut CacheManager,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let start = Instant::now();
+    let witness = cache_manager.get_witness_calculator(wasm)?.calculate_from_file(input)?;
+    println!("witness took: {:?}", start.elapsed());
+
+    try_load_and_set_backend_device(&config.device.get_device_type(), config.device.id);
+
+    let cache_key = cache_key(zkey);
 
     if !cache_manager.contains(&cache_key) {
         let computed_cache = cache_manager.compute(zkey)?;
@@ -49,8 +202,211 @@ pub fn groth16_prove(
     }
 
     let zkey_cache = cache_manager.get_cache(&cache_key);
+    zkey_cache.prepare(config)?;
 
-    let (proof_data, public_signals) = groth16_prove_helper(witness, zkey_cache)?;
+    let (proof_data, public_signals) = groth16_prove_computed_helper(witness, input, zkey_cache, config, &mut OsRng)?;
+
+    FileWrapper::save_json_file(proof, &proof_data)?;
+    FileWrapper::save_json_file(public, &public_signals)?;
+
+    println!("proof took: {:?}", start.elapsed());
+
+    Ok(())
+}
+
+/// Proves several witnesses of the same circuit in one pipelined pass.
+///
+/// `witnesses`, `proofs` and `publics` are matched by index.
+pub fn groth16_prove_batch(
+    witnesses: &[&str],
+    zkey: &str,
+    proofs: &[&str],
+    publics: &[&str],
+    device: &str,
+    cache_manager: &mut CacheManager,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config = ProverConfig {
+        device: Device::new(device, 0),
+        ..Default::default()
+    };
+    groth16_prove_batch_with_config(witnesses, zkey, proofs, publics, &config, cache_manager)
+}
+
+/// Like `groth16_prove_batch`, with every prover option taken from `config`.
+pub fn groth16_prove_batch_with_config(
+    witnesses: &[&str],
+    zkey: &str,
+    proofs: &[&str],
+    publics: &[&str],
+    config: &ProverConfig,
+    cache_manager: &mut CacheManager,
+) -> Result<(), Box<dyn std::error::Error>> {
+    groth16_prove_batch_with_rng(witnesses, zkey, proofs, publics, config, &mut OsRng, cache_manager)
+}
+
+/// Like `groth16_prove_batch_with_config`, drawing `Blinding::Random` factors from `rng`.
+pub fn groth16_prove_batch_with_rng<R: RngCore + CryptoRng>(
+    witnesses: &[&str],
+    zkey: &str,
+    proofs: &[&str],
+    publics: &[&str],
+    config: &ProverConfig,
+    rng: &mut R,
+    cache_manager: &mut CacheManager,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if witnesses.len() != proofs.len() || witnesses.len() != publics.len() {
+        return Err(format!(
+            "Mismatched batch: {} witnesses, {} proof paths, {} public paths",
+            witnesses.len(),
+            proofs.len(),
+            publics.len()
+        )
+        .into());
+    }
+
+    let start = Instant::now();
+    try_load_and_set_backend_device(&config.device.get_device_type(), config.device.id);
+
+    let cache_key = cache_key(zkey);
+
+    if !cache_manager.contains(&cache_key) {
+        let computed_cache = cache_manager.compute(zkey)?;
+        cache_manager.insert_cache(&cache_key, computed_cache);
+    }
+
+    let zkey_cache = cache_manager.get_cache(&cache_key);
+    zkey_cache.prepare(config)?;
+
+    let results = groth16_prove_batch_helper(witnesses, zkey_cache, config, rng)?;
+
+    for ((proof_data, public_signals), (proof, public)) in
+        results.iter().zip(proofs.iter().zip(publics.iter()))
+    {
+        FileWrapper::save_json_file(proof, proof_data)?;
+        FileWrapper::save_json_file(public, public_signals)?;
+    }
+
+    println!("{} proofs took: {:?}", witnesses.len(), start.elapsed());
+
+    Ok(())
+}
+
+/// Proves `witness` against a snarkjs PLONK zkey, writing a proof `snarkjs plonk verify` accepts.
+pub fn plonk_prove(
+    witness: &str,
+    zkey: &str,
+    proof: &str,
+    public: &str,
+    device: &str,
+    cache_manager: &mut CacheManager,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config = ProverConfig {
+        device: Device::new(device, 0),
+        ..Default::default()
+    };
+    plonk_prove_with_config(witness, zkey, proof, public, &config, cache_manager)
+}
+
+/// Like `plonk_prove`, with the options taken from `config`. `Blinding::Fixed` leaves the
+/// polynomials unblinded; the quotient strategy and stream count only apply to Groth16.
+pub fn plonk_prove_with_config(
+    witness: &str,
+    zkey: &str,
+    proof: &str,
+    public: &str,
+    config: &ProverConfig,
+    cache_manager: &mut CacheManager,
+) -> Result<(), Box<dyn std::error::Error>> {
+    plonk_prove_with_rng(witness, zkey, proof, public, config, &mut OsRng, cache_manager)
+}
+
+/// Like `plonk_prove_with_config`, drawing `Blinding::Random` factors from `rng`.
+pub fn plonk_prove_with_rng<R: RngCore + CryptoRng>(
+    witness: &str,
+    zkey: &str,
+    proof: &str,
+    public: &str,
+    config: &ProverConfig,
+    rng: &mut R,
+    cache_manager: &mut CacheManager,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let start = Instant::now();
+    try_load_and_set_backend_device(&config.device.get_device_type(), config.device.id);
+
+    let cache_key = cache_key(zkey);
+
+    if !cache_manager.contains_plonk(&cache_key) {
+        let computed_cache = cache_manager.compute_plonk(zkey)?;
+        cache_manager.insert_plonk_cache(&cache_key, computed_cache);
+    }
+
+    let plonk_cache = cache_manager.get_plonk_cache(&cache_key);
+    plonk_cache.prepare();
+
+    let (proof_data, public_signals) = plonk_prove_helper(witness, plonk_cache, config, rng)?;
+
+    FileWrapper::save_json_file(proof, &proof_data)?;
+    FileWrapper::save_json_file(public, &public_signals)?;
+
+    println!("proof took: {:?}", start.elapsed());
+
+    Ok(())
+}
+
+/// Proves `witness` against a snarkjs FFLONK zkey, writing a proof `snarkjs fflonk verify`
+/// accepts.
+pub fn fflonk_prove(
+    witness: &str,
+    zkey: &str,
+    proof: &str,
+    public: &str,
+    device: &str,
+    cache_manager: &mut CacheManager,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config = ProverConfig {
+        device: Device::new(device, 0),
+        ..Default::default()
+    };
+    fflonk_prove_with_config(witness, zkey, proof, public, &config, cache_manager)
+}
+
+/// Like `fflonk_prove`, with the options taken from `config`. `Blinding::Fixed` leaves the
+/// polynomials unblinded; the quotient strategy and stream count only apply to Groth16.
+pub fn fflonk_prove_with_config(
+    witness: &str,
+    zkey: &str,
+    proof: &str,
+    public: &str,
+    config: &ProverConfig,
+    cache_manager: &mut CacheManager,
+) -> Result<(), Box<dyn std::error::Error>> {
+    fflonk_prove_with_rng(witness, zkey, proof, public, config, &mut OsRng, cache_manager)
+}
+
+/// Like `fflonk_prove_with_config`, drawing `Blinding::Random` factors from `rng`.
+pub fn fflonk_prove_with_rng<R: RngCore + CryptoRng>(
+    witness: &str,
+    zkey: &str,
+    proof: &str,
+    public: &str,
+    config: &ProverConfig,
+    rng: &mut R,
+    cache_manager: &mut CacheManager,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let start = Instant::now();
+    try_load_and_set_backend_device(&config.device.get_device_type(), config.device.id);
+
+    let cache_key = cache_key(zkey);
+
+    if !cache_manager.contains_fflonk(&cache_key) {
+        let computed_cache = cache_manager.compute_fflonk(zkey)?;
+        cache_manager.insert_fflonk_cache(&cache_key, computed_cache);
+    }
+
+    let fflonk_cache = cache_manager.get_fflonk_cache(&cache_key);
+    fflonk_cache.prepare();
+
+    let (proof_data, public_signals) = fflonk_prove_helper(witness, fflonk_cache, config, rng)?;
 
     FileWrapper::save_json_file(proof, &proof_data)?;
     FileWrapper::save_json_file(public, &public_signals)?;
@@ -80,3 +436,162 @@ pub fn groth16_verify(
 
     Ok(())
 }
+
+/// Checks a snarkjs PLONK proof against its `verification_key.json`. Returns `false` for a
+/// proof that does not verify, and an error only if the files cannot be read or parsed.
+pub fn plonk_verify(
+    proof: &str,
+    public: &str,
+    vk: &str,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let proof_str = std::fs::read_to_string(proof)?;
+    let proof: PlonkProof = serde_json::from_str(&proof_str)?;
+
+    let public_str = std::fs::read_to_string(public)?;
+    let public: Vec<String> = serde_json::from_str(&public_str)?;
+
+    let vk_str = std::fs::read_to_string(vk)?;
+    let vk: PlonkVerificationKey = serde_json::from_str(&vk_str)?;
+
+    plonk_verify_helper(&proof, &public, &vk)
+}
+
+/// Checks a snarkjs FFLONK proof against its `verification_key.json`. Like `plonk_verify`,
+/// returns `false` for a proof that does not verify.
+pub fn fflonk_verify(
+    proof: &str,
+    public: &str,
+    vk: &str,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let proof_str = std::fs::read_to_string(proof)?;
+    let proof: FflonkProof = serde_json::from_str(&proof_str)?;
+
+    let public_str = std::fs::read_to_string(public)?;
+    let public: Vec<String> = serde_json::from_str(&public_str)?;
+
+    let vk_str = std::fs::read_to_string(vk)?;
+    let vk: FflonkVerificationKey = serde_json::from_str(&vk_str)?;
+
+    fflonk_verify_helper(&proof, &public, &vk)
+}
+
+/// Returns a fresh proof of the same statement as `proof`, which verifies against `vk` but
+/// cannot be linked to `proof`. No witness or zkey is needed.
+pub fn rerandomize<R: RngCore + CryptoRng>(proof: &Proof, vk: &VerificationKey, rng: &mut R) -> Proof {
+    rerandomize_helper(proof, vk, rng)
+}
+
+/// Aggregates Groth16 proofs of one circuit, a power of two of them, into an
+/// `AggregateProof` whose size and verification time grow with the logarithm of their number.
+/// `public[i]` are the public signals of `proofs[i]`. The MSMs run on the active device.
+pub fn aggregate_proofs(
+    proofs: &[Proof],
+    public: &[Vec<String>],
+    srs: &AggregationSrs,
+) -> Result<AggregateProof, Box<dyn std::error::Error>> {
+    aggregate_helper(proofs, public, srs)
+}
+
+/// Checks that `aggregate` holds valid proofs, under `vk`, of the statements `public`. Fails if a
+/// statement does not have exactly `vk.n_public` signals.
+pub fn verify_aggregate(
+    aggregate: &AggregateProof,
+    public: &[Vec<String>],
+    vk: &VerificationKey,
+    srs: &AggregationVerifierKey,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    aggregate_verify_helper(aggregate, public, vk, srs)
+}
+
+/// Commits to `polynomial`, whose degree must not exceed `srs.max_degree()`.
+pub fn kzg_commit(srs: &KzgSrs, polynomial: &Polynomial<F>) -> Result<G1, Box<dyn std::error::Error>> {
+    kzg_commit_helper(srs, polynomial)
+}
+
+/// Evaluates `polynomial` at `z` and proves the value.
+pub fn kzg_open(
+    srs: &KzgSrs,
+    polynomial: &Polynomial<F>,
+    z: &F,
+) -> Result<KzgOpening, Box<dyn std::error::Error>> {
+    kzg_open_helper(srs, polynomial, z)
+}
+
+/// Evaluates every polynomial at `z` and proves all values with one proof. `commitments` are
+/// the commitments to `polynomials`, in order.
+pub fn kzg_batch_open(
+    srs: &KzgSrs,
+    polynomials: &[&Polynomial<F>],
+    commitments: &[G1],
+    z: &F,
+) -> Result<KzgBatchOpening, Box<dyn std::error::Error>> {
+    kzg_batch_open_helper(srs, polynomials, commitments, z)
+}
+
+/// Checks that the polynomial committed to by `commitment` takes `opening.value` at `z`.
+pub fn kzg_verify(vk: &KzgVerifierKey, commitment: &G1, z: &F, opening: &KzgOpening) -> bool {
+    kzg_verify_helper(vk, commitment, z, opening)
+}
+
+/// Checks a batch opening at `z` of the polynomials committed to by `commitments`.
+pub fn kzg_batch_verify(
+    vk: &KzgVerifierKey,
+    commitments: &[G1],
+    z: &F,
+    opening: &KzgBatchOpening,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    kzg_batch_verify_helper(vk, commitments, z, opening)
+}
+
+/// Reads the witness in a `.wtns` file over the BN254 scalar field.
+pub fn read_wtns(wtns: &str) -> Result<Vec<F>, Box<dyn std::error::Error>> {
+    read_wtns_helper(wtns)
+}
+
+/// Writes `witness` as a `.wtns` file. Given a `zkey`, the witness is first checked against it:
+/// the zkey must work over the same field with the same scalar size, and take as many signals.
+pub fn write_wtns(witness: &[F], wtns: &str, zkey: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    write_wtns_helper(witness, wtns, zkey)
+}
+
+/// Converts a `.wtns` file to the JSON `snarkjs wtns export json` writes.
+pub fn wtns_export_json(wtns: &str, json: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let witness = read_wtns_helper(wtns)?;
+    FileWrapper::save_json_file(json, &wtns_to_json_helper(&witness))
+}
+
+/// Converts a witness JSON, as `wtns_export_json` writes it, to a `.wtns` file, checking it
+/// against `zkey` if one is given.
+pub fn wtns_import_json(json: &str, wtns: &str, zkey: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let json_str = std::fs::read_to_string(json)?;
+    let witness = json_to_wtns_helper(&serde_json::from_str(&json_str)?)?;
+    write_wtns_helper(&witness, wtns, zkey)
+}
+
+/// The witness in a `.wtns` file as one `name = value` line per signal, named after the `.sym`
+/// file of the circuit if one is given.
+pub fn inspect_wtns(wtns: &str, sym: Option<&str>) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let symbols = sym.map(Symbols::from_file).transpose()?;
+    let witness = read_wtns_helper(wtns)?;
+    Ok(describe_witness_helper(&witness, symbols.as_ref()))
+}
+
+/// The public signals in `public`, as a prover writes them, as `name = value` lines.
+pub fn describe_public_signals(public: &str, symbols: &Symbols) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let public_str = std::fs::read_to_string(public)?;
+    let public: Vec<String> = serde_json::from_str(&public_str)?;
+    Ok(describe_public_helper(&public, symbols))
+}
+
+/// Reads the statistics of `zkey` and estimates the device memory of its cache under the default
+/// `CacheManager` settings, next to the memory available on `device`.
+pub fn inspect_zkey(zkey: &str, device: &Device) -> Result<ZKeyInfo, Box<dyn std::error::Error>> {
+    let mut info = inspect_zkey_helper(zkey)?;
+
+    let device_type = device.get_device_type();
+    try_load_backend(&device_type);
+    icicle_runtime::set_device(device)
+        .map_err(|e| format!("Device {}:{} is not available: {:?}", device_type, device.id, e))?;
+    info.available_memory = icicle_runtime::get_available_memory().ok();
+    Ok(info)
+}
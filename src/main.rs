@@ -1,25 +1,46 @@
-use icicle_snark::{groth16_prove, groth16_verify, CacheManager};
+use icicle_snark::{describe_public_signals, fflonk_prove_with_config, fflonk_verify, groth16_prove_from_input_with_config, groth16_prove_with_config, groth16_verify, inspect_wtns, inspect_zkey, plonk_prove_with_config, plonk_verify, wtns_export_json, wtns_import_json, Blinding, CacheManager, Device, ProverConfig, QuotientStrategy, Symbols};
 use std::io::{self, BufRead, Write};
+use std::sync::Arc;
 
 enum ProofSystem {
     Groth16,
+    Plonk,
+    Fflonk,
 }
 
 enum Command {
     Prove {
         system: ProofSystem,
         witness: String,
+        calculator: Option<(String, String)>,
         zkey: String,
         proof: String,
         public: String,
-        device: String,
+        config: Box<ProverConfig>,
     },
     Verify {
         system: ProofSystem,
         proof: String,
         public: String,
         vk: String,
-    }
+    },
+    WitnessExport {
+        witness: String,
+        json: String,
+    },
+    WitnessImport {
+        json: String,
+        witness: String,
+        zkey: Option<String>,
+    },
+    WitnessInspect {
+        witness: String,
+        sym: Option<String>,
+    },
+    ZKeyInspect {
+        zkey: String,
+        device: Device,
+    },
 }
 
 impl Command {
@@ -27,15 +48,47 @@ impl Command {
         println!(
             "Usage: prove [OPTIONS]\n\n\
             Options:\n\
-            --system <system>   Set the proof system (default: Groth16)\n\
+            --system <system>   Set the proof system: groth16, plonk or fflonk (default: groth16)\n\
             --witness <path>    Path to the witness file\n\
+            --input <path>      Path to the circuit input, to compute the witness with --wasm (Groth16 only)\n\
+            --wasm <path>       Path to the circom witness calculator\n\
             --zkey <path>       Path to the zkey file\n\
             --proof <path>      Path to the proof output file\n\
             --public <path>     Path to the public output file\n\
-            --device <device>   Set the computation device (default: CUDA)\n\
-            --help              Show this message and exit"
+            --device <device>   Set the computation device, as TYPE or TYPE:ID (default: CUDA)\n\
+            --quotient <mode>   Coset evaluation: keys or coset (default: keys)\n\
+            --blinding <mode>   Blinding factors: random or fixed (default: random)\n\
+            --streams <count>   Streams for the commitment MSMs (default: 2)\n\
+            --verify            Verify every proof before writing it\n\
+            --sym <path>        Name the signals after the circuit's .sym file (PLONK and FFLONK only)\n\
+            --help              Show this message and exit\n\n\
+            Usage: witness export|import|inspect [OPTIONS]\n\n\
+            Options:\n\
+            --witness <path>    Path to the .wtns file\n\
+            --json <path>       Path to the witness JSON\n\
+            --zkey <path>       Check the imported witness against the zkey\n\
+            --sym <path>        Name the inspected signals after the circuit's .sym file\n\n\
+            Usage: zkey inspect [OPTIONS]\n\n\
+            Options:\n\
+            --zkey <path>       Path to the zkey file\n\
+            --device <device>   Device to check the cache memory against, as TYPE or TYPE:ID (default: CUDA)"
         );
     }
+    /// Parses `TYPE` or `TYPE:ID`; the id defaults to 0.
+    fn parse_device(device: &str) -> Option<Device> {
+        let (device_type, id) = match device.split_once(':') {
+            Some((device_type, id)) => (device_type, id.parse().ok()),
+            None => (device, Some(0)),
+        };
+        match id {
+            Some(id) if !device_type.is_empty() => Some(Device::new(device_type, id)),
+            _ => {
+                eprintln!("Invalid device: {}, expected TYPE or TYPE:ID", device);
+                None
+            }
+        }
+    }
+
     fn parse_command(command: &str) -> Option<Self> {
         let mut parts = command.split_whitespace();
         let command_type = parts.next()?;
@@ -44,10 +97,12 @@ impl Command {
         match command_type {
             "prove" => {
                 let mut witness = "witness.wtns".to_string();
+                let mut input = None;
+                let mut wasm = None;
                 let mut zkey = "circuit_final.zkey".to_string();
                 let mut proof = "proof.json".to_string();
                 let mut public = "public.json".to_string();
-                let mut device = "CUDA".to_string();
+                let mut config = ProverConfig::default();
 
                 while let Some(arg) = parts.next() {
                     match arg {
@@ -55,6 +110,8 @@ impl Command {
                             if let Some(val) = parts.next() {
                                 proof_system = match val.to_lowercase().as_str() {
                                     "groth16" => ProofSystem::Groth16,
+                                    "plonk" => ProofSystem::Plonk,
+                                    "fflonk" => ProofSystem::Fflonk,
                                     _ => {
                                         eprintln!("Unknown proof system: {}", val);
                                         return None;
@@ -63,21 +120,70 @@ impl Command {
                             }
                         }
                         "--witness" => witness = parts.next()?.to_string(),
+                        "--input" => input = Some(parts.next()?.to_string()),
+                        "--wasm" => wasm = Some(parts.next()?.to_string()),
                         "--zkey" => zkey = parts.next()?.to_string(),
                         "--proof" => proof = parts.next()?.to_string(),
                         "--public" => public = parts.next()?.to_string(),
-                        "--device" => device = parts.next()?.to_string(),
+                        "--device" => config.device = Command::parse_device(parts.next()?)?,
+                        "--quotient" => {
+                            config.quotient = match parts.next()? {
+                                "keys" => QuotientStrategy::PrecomputedKeys,
+                                "coset" => QuotientStrategy::CosetNtt,
+                                val => {
+                                    eprintln!("Unknown quotient strategy: {}", val);
+                                    return None;
+                                }
+                            }
+                        }
+                        "--blinding" => {
+                            config.blinding = match parts.next()? {
+                                "random" => Blinding::Random,
+                                "fixed" => Blinding::Fixed,
+                                val => {
+                                    eprintln!("Unknown blinding: {}", val);
+                                    return None;
+                                }
+                            }
+                        }
+                        "--streams" => config.streams = parts.next()?.parse().ok()?,
+                        "--verify" => config.verify = true,
+                        "--sym" => match Symbols::from_file(parts.next()?) {
+                            Ok(symbols) => config.symbols = Some(Arc::new(symbols)),
+                            Err(err) => {
+                                eprintln!("Could not read symbols: {}", err);
+                                return None;
+                            }
+                        },
                         _ => Command::print_help(),
                     }
                 }
 
+                let calculator = match (input, wasm) {
+                    (Some(input), Some(wasm)) => Some((input, wasm)),
+                    (None, None) => None,
+                    _ => {
+                        eprintln!("--input and --wasm must be given together");
+                        return None;
+                    }
+                };
+                if calculator.is_some() && !matches!(proof_system, ProofSystem::Groth16) {
+                    eprintln!("--input and --wasm are only supported with groth16");
+                    return None;
+                }
+                if config.symbols.is_some() && matches!(proof_system, ProofSystem::Groth16) {
+                    eprintln!("--sym is only supported with plonk and fflonk");
+                    return None;
+                }
+
                 Some(Command::Prove {
                     system: proof_system,
                     witness,
+                    calculator,
                     zkey,
                     proof,
                     public,
-                    device,
+                    config: Box::new(config),
                 })
             }
             "verify" => {
@@ -92,6 +198,8 @@ impl Command {
                             if let Some(val) = parts.next() {
                                 system = match val.to_lowercase().as_str() {
                                     "groth16" => ProofSystem::Groth16,
+                                    "plonk" => ProofSystem::Plonk,
+                                    "fflonk" => ProofSystem::Fflonk,
                                     _ => {
                                         eprintln!("Unknown proof system: {}", val);
                                         return None;
@@ -113,6 +221,48 @@ impl Command {
                     vk,
                 })
             }
+            "witness" => {
+                let subcommand = parts.next()?;
+                let mut witness = "witness.wtns".to_string();
+                let mut json = "witness.json".to_string();
+                let mut zkey = None;
+                let mut sym = None;
+
+                while let Some(arg) = parts.next() {
+                    match arg {
+                        "--witness" => witness = parts.next()?.to_string(),
+                        "--json" => json = parts.next()?.to_string(),
+                        "--zkey" => zkey = Some(parts.next()?.to_string()),
+                        "--sym" => sym = Some(parts.next()?.to_string()),
+                        _ => Command::print_help(),
+                    }
+                }
+
+                match subcommand {
+                    "export" => Some(Command::WitnessExport { witness, json }),
+                    "import" => Some(Command::WitnessImport { json, witness, zkey }),
+                    "inspect" => Some(Command::WitnessInspect { witness, sym }),
+                    _ => None,
+                }
+            }
+            "zkey" => {
+                let subcommand = parts.next()?;
+                let mut zkey = "circuit_final.zkey".to_string();
+                let mut device = Device::new("CUDA", 0);
+
+                while let Some(arg) = parts.next() {
+                    match arg {
+                        "--zkey" => zkey = parts.next()?.to_string(),
+                        "--device" => device = Command::parse_device(parts.next()?)?,
+                        _ => Command::print_help(),
+                    }
+                }
+
+                match subcommand {
+                    "inspect" => Some(Command::ZKeyInspect { zkey, device }),
+                    _ => None,
+                }
+            }
             _ => None,
         }
     }
@@ -148,32 +298,111 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             Some(Command::Prove {
                 system,
                 witness,
+                calculator,
                 zkey,
                 proof,
                 public,
-                device,
+                config,
             }) => {
-                match system {
-                    ProofSystem::Groth16 => groth16_prove(
+                let result = match system {
+                    ProofSystem::Groth16 => match calculator {
+                        Some((input, wasm)) => groth16_prove_from_input_with_config(
+                            &input,
+                            &wasm,
+                            &zkey,
+                            &proof,
+                            &public,
+                            &config,
+                            &mut cache_manager,
+                        ),
+                        None => groth16_prove_with_config(
+                            &witness,
+                            &zkey,
+                            &proof,
+                            &public,
+                            &config,
+                            &mut cache_manager,
+                        ),
+                    },
+                    ProofSystem::Plonk => plonk_prove_with_config(
                         &witness,
                         &zkey,
                         &proof,
                         &public,
-                        &device,
+                        &config,
                         &mut cache_manager,
-                    )
-                    .unwrap(),
+                    ),
+                    ProofSystem::Fflonk => fflonk_prove_with_config(
+                        &witness,
+                        &zkey,
+                        &proof,
+                        &public,
+                        &config,
+                        &mut cache_manager,
+                    ),
+                };
+                match result {
+                    Ok(()) => {
+                        if let Some(symbols) = &config.symbols {
+                            match describe_public_signals(&public, symbols) {
+                                Ok(lines) => lines.iter().for_each(|line| println!("{}", line)),
+                                Err(e) => eprintln!("Describing the public signals failed: {}", e),
+                            }
+                        }
+                    }
+                    Err(e) => eprintln!("Proving failed: {}", e),
                 }
                 println!("COMMAND_COMPLETED");
             }
             Some(Command::Verify { system, proof, public, vk }) => {
-                match system {
+                let result = match system {
                     ProofSystem::Groth16 => groth16_verify(
                         &proof,
                         &public,
                         &vk,
                     )
-                    .unwrap(),
+                    .map(|()| true),
+                    ProofSystem::Plonk => plonk_verify(
+                        &proof,
+                        &public,
+                        &vk,
+                    ),
+                    ProofSystem::Fflonk => fflonk_verify(
+                        &proof,
+                        &public,
+                        &vk,
+                    ),
+                };
+                match result {
+                    Ok(true) => println!("Verification OK"),
+                    Ok(false) => println!("Invalid proof"),
+                    Err(e) => eprintln!("Verification failed: {}", e),
+                }
+                println!("COMMAND_COMPLETED");
+            }
+            Some(Command::WitnessExport { witness, json }) => {
+                if let Err(e) = wtns_export_json(&witness, &json) {
+                    eprintln!("Exporting the witness failed: {}", e);
+                }
+                println!("COMMAND_COMPLETED");
+            }
+            Some(Command::WitnessImport { json, witness, zkey }) => {
+                if let Err(e) = wtns_import_json(&json, &witness, zkey.as_deref()) {
+                    eprintln!("Importing the witness failed: {}", e);
+                }
+                println!("COMMAND_COMPLETED");
+            }
+            Some(Command::WitnessInspect { witness, sym }) => {
+                match inspect_wtns(&witness, sym.as_deref()) {
+                    Ok(lines) => lines.iter().for_each(|line| println!("{}", line)),
+                    Err(e) => eprintln!("Inspecting the witness failed: {}", e),
+                }
+                println!("COMMAND_COMPLETED");
+            }
+            Some(Command::ZKeyInspect { zkey, device }) => {
+                match inspect_zkey(&zkey, &device) {
+                    Ok(info) => print!("{}", info),
+                    Err(e) => eprintln!("Inspecting the zkey failed: {}", e),
                 }
                 println!("COMMAND_COMPLETED");
             }
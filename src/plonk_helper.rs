@@ -0,0 +1,818 @@
+//! PLONK prover for snarkjs PLONK zkeys, producing the proofs `snarkjs plonk verify` accepts,
+//! and a verifier for snarkjs PLONK proofs.
+//!
+//! The rounds follow snarkjs' prover: the same blinding, the same Keccak-256 transcript and the
+//! same linearisation polynomial. The quotient is computed over a coset of four times the
+//! domain rather than over the domain itself, which gives the same polynomial.
+
+use icicle_bn254::pairing::PairingTargetField;
+use icicle_core::{
+    curve::Curve,
+    pairing::pairing,
+    traits::{Arithmetic, FieldImpl},
+    polynomials::Polynomial,
+    vec_ops::{add_scalars, div_scalars, mul_scalars, scalar_add, scalar_mul, sub_scalars, VecOpsConfig},
+};
+use icicle_runtime::{
+    memory::{DeviceSlice, DeviceVec, HostOrDeviceSlice, HostSlice},
+    stream::IcicleStream,
+};
+use num_bigint::BigUint;
+use rand_core::CryptoRngCore;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use zeroize::Zeroize;
+
+use crate::{
+    cache::{MsmParams, PlonkCache, PlonkVerificationKey},
+    config::{Blinding, ProverConfig},
+    conversions::{deserialize_g1_affine, deserialize_scalar, from_u8, serialize_g1_affine},
+    file_wrapper::FileWrapper,
+    icicle_helper::{msm_batch_helper, ntt_batch_helper, ntt_domain},
+    proof_helper::{parse_public_signals, random_scalar, InvalidProof},
+    symbols::Symbols,
+    transcript::{Transcript, TranscriptHash},
+    ProjectiveG1, C1, C2, F, G1, G2,
+};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PlonkProof {
+    #[serde(rename = "A")]
+    pub a: Vec<String>,
+    #[serde(rename = "B")]
+    pub b: Vec<String>,
+    #[serde(rename = "C")]
+    pub c: Vec<String>,
+    #[serde(rename = "Z")]
+    pub z: Vec<String>,
+    #[serde(rename = "T1")]
+    pub t1: Vec<String>,
+    #[serde(rename = "T2")]
+    pub t2: Vec<String>,
+    #[serde(rename = "T3")]
+    pub t3: Vec<String>,
+    #[serde(rename = "Wxi")]
+    pub wxi: Vec<String>,
+    #[serde(rename = "Wxiw")]
+    pub wxiw: Vec<String>,
+    pub eval_a: String,
+    pub eval_b: String,
+    pub eval_c: String,
+    pub eval_s1: String,
+    pub eval_s2: String,
+    pub eval_zw: String,
+    pub protocol: String,
+    pub curve: String,
+}
+
+/// Blinding scalars `b1..b11` of one proof, indexed from 1 as in the PLONK paper, wiped when
+/// dropped.
+pub(crate) struct PlonkBlinding {
+    pub(crate) b: [F; 12],
+}
+
+impl PlonkBlinding {
+    pub(crate) fn new(blinding: &Blinding, rng: &mut dyn CryptoRngCore) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut b = [F::zero(); 12];
+        match blinding {
+            Blinding::Random => b[1..].iter_mut().for_each(|b| *b = random_scalar(rng)),
+            Blinding::Fixed => {}
+            Blinding::Explicit { .. } => {
+                return Err("Explicit blinding factors only apply to Groth16 proofs".into())
+            }
+        }
+        Ok(Self { b })
+    }
+}
+
+impl Drop for PlonkBlinding {
+    fn drop(&mut self) {
+        let bytes = unsafe {
+            std::slice::from_raw_parts_mut(self.b.as_mut_ptr() as *mut u8, std::mem::size_of_val(&self.b))
+        };
+        bytes.zeroize();
+    }
+}
+
+pub(crate) fn scalar_to_string(scalar: &F) -> String {
+    BigUint::from_bytes_le(&scalar.to_bytes_le()).to_str_radix(10)
+}
+
+/// Reads the witness of a circuit of `n_vars` signals over the field of order `r`, and appends
+/// the internal signals of the additions section.
+pub(crate) fn read_witness(
+    witness: &str,
+    r: &F,
+    n_vars: usize,
+    additions: &[(u32, u32, F, F)],
+) -> Result<Vec<F>, Box<dyn std::error::Error>> {
+    let (fd_wtns, sections_wtns) = FileWrapper::read_bin_file(witness, "wtns", 2)?;
+    let mut wtns_file = FileWrapper::new(fd_wtns)?;
+    let wtns = wtns_file.read_wtns_header(&sections_wtns)?;
+
+    if !F::eq(r, &wtns.q) {
+        return Err("Curve of the witness does not match the curve of the proving key".into());
+    }
+
+    let n_signals = n_vars - additions.len();
+    if wtns.n_witness != n_signals {
+        return Err(format!(
+            "Invalid witness length. Circuit: {}, witness: {}",
+            n_signals, wtns.n_witness
+        )
+        .into());
+    }
+
+    let mut signals = Vec::with_capacity(n_vars);
+    signals.extend_from_slice(&from_u8::<F>(wtns_file.read_section(&sections_wtns, 2)?)[..n_signals]);
+
+    for &(a, b, factor_a, factor_b) in additions {
+        let value = factor_a * signal(&signals, a) + factor_b * signal(&signals, b);
+        signals.push(value);
+    }
+
+    Ok(signals)
+}
+
+/// Value of signal `id`; signals past the witness, like the padding wires, are zero.
+pub(crate) fn signal(signals: &[F], id: u32) -> F {
+    signals.get(id as usize).copied().unwrap_or(F::zero())
+}
+
+/// Copies `coefficients` into a polynomial of its own.
+pub(crate) fn polynomial(coefficients: &DeviceSlice<F>) -> Polynomial<F> {
+    let mut d_coefficients = DeviceVec::device_malloc(coefficients.len()).unwrap();
+    d_coefficients.copy(coefficients).unwrap();
+    Polynomial::from_device(d_coefficients).unwrap()
+}
+
+/// Adds `(b[0] + b[1] X + ...) * (X^n - 1)` to `polynomial`, which keeps its values over the
+/// domain of size `n`.
+pub(crate) fn blind(polynomial: &Polynomial<F>, n: usize, factors: &[F]) -> Polynomial<F> {
+    let factors = Polynomial::from_coeffs(HostSlice::from_slice(factors)).unwrap();
+    polynomial.add(&factors.mul_by_vanishing(n).unwrap()).unwrap()
+}
+
+/// `sum(factor * polynomial)`
+pub(crate) fn combine(terms: &[(&Polynomial<F>, F)]) -> Polynomial<F> {
+    let (first, rest) = terms.split_first().unwrap();
+    rest.iter().fold(first.0.scale(&first.1).unwrap(), |acc, (polynomial, factor)| {
+        acc.add(&polynomial.scale(factor).unwrap()).unwrap()
+    })
+}
+
+/// `p(w X)`, given the powers of `w` up to the degree of `p`.
+pub(crate) fn scale_argument(polynomial: &Polynomial<F>, powers: &[F]) -> Polynomial<F> {
+    let len = polynomial.degree() + 1;
+    let mut coefficients = DeviceVec::device_malloc(len).unwrap();
+    mul_scalars(
+        polynomial.coefficients(),
+        HostSlice::from_slice(&powers[..len]),
+        &mut coefficients[..],
+        &VecOpsConfig::default(),
+    )
+    .unwrap();
+    Polynomial::from_device(coefficients).unwrap()
+}
+
+/// Lays the coefficients of `polynomials` out one after another, zero-padded to `len` each.
+pub(crate) fn stack(polynomials: &[&Polynomial<F>], len: usize) -> DeviceVec<F> {
+    let mut stacked = DeviceVec::device_malloc(len * polynomials.len()).unwrap();
+    stacked.memset(0, len * polynomials.len()).unwrap();
+    for (i, polynomial) in polynomials.iter().enumerate() {
+        stacked[i * len..(i + 1) * len].copy(polynomial.coefficients()).unwrap();
+    }
+    stacked
+}
+
+/// Commits to every polynomial with one batched MSM over the powers of tau `ptau`.
+pub(crate) fn commit(polynomials: &[&Polynomial<F>], ptau: &DeviceSlice<G1>, stream: &IcicleStream) -> Vec<G1> {
+    let len = polynomials.iter().map(|p| p.degree() + 1).max().unwrap();
+    let scalars = stack(polynomials, len);
+
+    let d_commitments = msm_batch_helper(
+        &scalars[..],
+        &ptau[..len],
+        &MsmParams::default(),
+        false,
+        polynomials.len(),
+        stream,
+    );
+
+    let mut commitments = vec![ProjectiveG1::zero(); polynomials.len()];
+    d_commitments
+        .copy_to_host(HostSlice::from_mut_slice(&mut commitments))
+        .unwrap();
+
+    commitments.into_iter().map(G1::from).collect()
+}
+
+pub(crate) fn upload(values: &[F]) -> DeviceVec<F> {
+    let mut d_values = DeviceVec::device_malloc(values.len()).unwrap();
+    d_values.copy_from_host(HostSlice::from_slice(values)).unwrap();
+    d_values
+}
+
+pub(crate) fn download(d_values: &DeviceSlice<F>) -> Vec<F> {
+    let mut values = vec![F::zero(); d_values.len()];
+    d_values.copy_to_host(HostSlice::from_mut_slice(&mut values)).unwrap();
+    values
+}
+
+/// Element-wise arithmetic over device vectors, each result in a new vector.
+pub(crate) struct Evaluations {
+    cfg: VecOpsConfig,
+}
+
+impl Evaluations {
+    pub(crate) fn new() -> Self {
+        Self {
+            cfg: VecOpsConfig::default(),
+        }
+    }
+
+    pub(crate) fn mul(&self, a: &DeviceSlice<F>, b: &DeviceSlice<F>) -> DeviceVec<F> {
+        let mut result = DeviceVec::device_malloc(a.len()).unwrap();
+        mul_scalars(a, b, &mut result[..], &self.cfg).unwrap();
+        result
+    }
+
+    pub(crate) fn div(&self, a: &DeviceSlice<F>, b: &DeviceSlice<F>) -> DeviceVec<F> {
+        let mut result = DeviceVec::device_malloc(a.len()).unwrap();
+        div_scalars(a, b, &mut result[..], &self.cfg).unwrap();
+        result
+    }
+
+    pub(crate) fn add(&self, a: &DeviceSlice<F>, b: &DeviceSlice<F>) -> DeviceVec<F> {
+        let mut result = DeviceVec::device_malloc(a.len()).unwrap();
+        add_scalars(a, b, &mut result[..], &self.cfg).unwrap();
+        result
+    }
+
+    pub(crate) fn sub(&self, a: &DeviceSlice<F>, b: &DeviceSlice<F>) -> DeviceVec<F> {
+        let mut result = DeviceVec::device_malloc(a.len()).unwrap();
+        sub_scalars(a, b, &mut result[..], &self.cfg).unwrap();
+        result
+    }
+
+    /// `scalar + a`
+    pub(crate) fn shift(&self, scalar: F, a: &DeviceSlice<F>) -> DeviceVec<F> {
+        let mut result = DeviceVec::device_malloc(a.len()).unwrap();
+        scalar_add(HostSlice::from_slice(&[scalar]), a, &mut result[..], &self.cfg).unwrap();
+        result
+    }
+
+    /// `scalar * a`
+    pub(crate) fn scale(&self, scalar: F, a: &DeviceSlice<F>) -> DeviceVec<F> {
+        let mut result = DeviceVec::device_malloc(a.len()).unwrap();
+        scalar_mul(HostSlice::from_slice(&[scalar]), a, &mut result[..], &self.cfg).unwrap();
+        result
+    }
+
+    /// `(a + beta * s + gamma)`, the factor of one wire in the permutation argument.
+    pub(crate) fn wire(&self, a: &DeviceSlice<F>, s: &DeviceSlice<F>, beta: F, gamma: F) -> DeviceVec<F> {
+        self.shift(gamma, &self.add(a, &self.scale(beta, s)))
+    }
+}
+
+/// Values over the domain of the A, B and C wires, followed by those of the public input
+/// polynomial -sum(A_i L_i), `n` each.
+pub(crate) fn wire_values(signals: &[F], wires: &[Vec<u32>; 3], n: usize, n_public: usize) -> Vec<F> {
+    let mut values = vec![F::zero(); 4 * n];
+    for (wire, signal_ids) in wires.iter().enumerate() {
+        for (row, id) in signal_ids.iter().enumerate() {
+            values[wire * n + row] = signal(signals, *id);
+        }
+    }
+    for i in 0..n_public {
+        values[3 * n + i] = F::zero() - values[i];
+    }
+    values
+}
+
+/// Values over the domain of the permutation polynomial Z, given the wire values and the
+/// sigma evaluations, or `None` if the copy constraints do not hold.
+pub(crate) fn permutation_values(
+    values: &[F],
+    sigma: &DeviceSlice<F>,
+    omega_powers: &[F],
+    beta: F,
+    gamma: F,
+    k1: F,
+    k2: F,
+) -> Option<Vec<F>> {
+    let n = omega_powers.len();
+    let ops = Evaluations::new();
+    let d_values = upload(values);
+    let d_omega = upload(omega_powers);
+    let (wire_a, wire_b, wire_c) = (&d_values[..n], &d_values[n..2 * n], &d_values[2 * n..]);
+    let sigma = |i: usize| &sigma[i * n..(i + 1) * n];
+
+    let numerator = ops.mul(
+        &ops.mul(
+            &ops.wire(wire_a, &d_omega, beta, gamma),
+            &ops.wire(wire_b, &d_omega, beta * k1, gamma),
+        ),
+        &ops.wire(wire_c, &d_omega, beta * k2, gamma),
+    );
+    let denominator = ops.mul(
+        &ops.mul(
+            &ops.wire(wire_a, sigma(0), beta, gamma),
+            &ops.wire(wire_b, sigma(1), beta, gamma),
+        ),
+        &ops.wire(wire_c, sigma(2), beta, gamma),
+    );
+    let ratios = download(&ops.div(&numerator, &denominator));
+
+    let mut z_values = Vec::with_capacity(n);
+    let mut product = F::one();
+    for ratio in &ratios {
+        z_values.push(product);
+        product = product * *ratio;
+    }
+    (product == F::one()).then_some(z_values)
+}
+
+/// First row of the domain whose gate `qm a b + ql a + qr b + qo c + qc + pi` does not vanish,
+/// given the coefficients of qm, ql, qr, qo and qc and the values `wire_values` returns.
+pub(crate) fn failing_gate(selectors: [&[F]; 5], values: &[F], stream: &IcicleStream) -> Option<usize> {
+    let n = selectors[0].len();
+    let mut d_selectors = upload(&selectors.concat());
+    ntt_batch_helper(&mut d_selectors[..], false, None, 5, stream);
+    let q = download(&d_selectors);
+
+    (0..n).find(|&row| {
+        let [qm, ql, qr, qo, qc] = [0, 1, 2, 3, 4].map(|i| q[i * n + row]);
+        let [a, b, c, pi] = [0, 1, 2, 3].map(|i| values[i * n + row]);
+        qm * a * b + ql * a + qr * b + qo * c + qc + pi != F::zero()
+    })
+}
+
+/// The error of a witness that does not satisfy the gate on `row`, naming the signals on its
+/// wires. Signals from `n_signals` on are the internal signals of the additions.
+pub(crate) fn gate_error(
+    witness: &str,
+    row: Option<usize>,
+    wires: &[Vec<u32>; 3],
+    signals: &[F],
+    n_signals: usize,
+    symbols: Option<&Symbols>,
+) -> Box<dyn std::error::Error> {
+    let Some(row) = row else {
+        return format!("Witness {} does not satisfy the constraints of the circuit", witness).into();
+    };
+
+    let operands: Vec<String> = wires
+        .iter()
+        .map(|wire| {
+            let id = wire[row] as usize;
+            let label = match symbols {
+                _ if id >= n_signals => format!("internal signal {}", id),
+                Some(symbols) => symbols.label(id),
+                None => format!("signal {}", id),
+            };
+            format!("{} = {}", label, scalar_to_string(&signal(signals, id as u32)))
+        })
+        .collect();
+
+    format!(
+        "Witness {} does not satisfy gate {} of the circuit: {}",
+        witness,
+        row,
+        operands.join(", ")
+    )
+    .into()
+}
+
+pub fn plonk_prove_helper(
+    witness: &str,
+    cache: &PlonkCache,
+    config: &ProverConfig,
+    rng: &mut dyn CryptoRngCore,
+) -> Result<(Value, Value), Box<dyn std::error::Error>> {
+    let zkey = &cache.zkey;
+    let n = zkey.domain_size;
+    let m = 4 * n;
+    let coset = cache.coset.as_ref().expect("PLONK cache is not prepared");
+    let fixed = |i: usize| &coset[i * m..(i + 1) * m];
+    let stream = IcicleStream::default();
+    let ops = Evaluations::new();
+
+    let signals = read_witness(witness, &zkey.r, zkey.n_vars, &cache.additions)?;
+    let factors = PlonkBlinding::new(&config.blinding, rng)?;
+    let blinding = &factors.b;
+    let mut transcript = Transcript::new(TranscriptHash::Keccak256);
+
+    // round 1: the wires, and the public input polynomial -sum(A_i L_i) along with them
+    let values = wire_values(&signals, &cache.wires, n, zkey.n_public);
+
+    let mut d_coefficients = upload(&values);
+    ntt_batch_helper(&mut d_coefficients[..], true, None, 4, &stream);
+
+    let pol_a = blind(&polynomial(&d_coefficients[..n]), n, &[blinding[2], blinding[1]]);
+    let pol_b = blind(&polynomial(&d_coefficients[n..2 * n]), n, &[blinding[4], blinding[3]]);
+    let pol_c = blind(&polynomial(&d_coefficients[2 * n..3 * n]), n, &[blinding[6], blinding[5]]);
+    let pol_pi = polynomial(&d_coefficients[3 * n..]);
+
+    let [commit_a, commit_b, commit_c] = commit(&[&pol_a, &pol_b, &pol_c], &cache.ptau, &stream).try_into().unwrap();
+
+    // round 2: the permutation polynomial
+    for point in [&zkey.qm, &zkey.ql, &zkey.qr, &zkey.qo, &zkey.qc, &zkey.s1, &zkey.s2, &zkey.s3] {
+        transcript.add_point(point);
+    }
+    for value in &values[..zkey.n_public] {
+        transcript.add_scalar(value);
+    }
+    for point in [&commit_a, &commit_b, &commit_c] {
+        transcript.add_point(point);
+    }
+    let beta = transcript.challenge();
+    transcript.add_scalar(&beta);
+    let gamma = transcript.challenge();
+
+    let mut omega_powers = Vec::with_capacity(n + 3);
+    let mut power = F::one();
+    for _ in 0..n + 3 {
+        omega_powers.push(power);
+        power = power * cache.omega;
+    }
+
+    let z_values = permutation_values(&values[..3 * n], &cache.sigma, &omega_powers[..n], beta, gamma, zkey.k1, zkey.k2)
+        .ok_or_else(|| format!("Copy constraints of {} do not match", witness))?;
+
+    let mut d_z = upload(&z_values);
+    ntt_batch_helper(&mut d_z[..], true, None, 1, &stream);
+    let pol_z = blind(&Polynomial::from_device(d_z).unwrap(), n, &[blinding[9], blinding[8], blinding[7]]);
+
+    let [commit_z] = commit(&[&pol_z], &cache.ptau, &stream).try_into().unwrap();
+
+    // round 3: the quotient, over the coset g * <w4>
+    transcript.add_scalar(&beta);
+    transcript.add_scalar(&gamma);
+    transcript.add_point(&commit_z);
+    let alpha = transcript.challenge();
+
+    let pol_zw = scale_argument(&pol_z, &omega_powers);
+
+    let mut d_evals = stack(&[&pol_a, &pol_b, &pol_c, &pol_z, &pol_zw, &pol_pi], m);
+    ntt_batch_helper(&mut d_evals[..], false, Some(&cache.coset_gen), 6, &stream);
+    let on_coset = |i: usize| &d_evals[i * m..(i + 1) * m];
+    let (a, b, c, z, zw, pi) = (on_coset(0), on_coset(1), on_coset(2), on_coset(3), on_coset(4), on_coset(5));
+    let (qm, ql, qr, qo, qc) = (fixed(0), fixed(1), fixed(2), fixed(3), fixed(4));
+    let (x, l1, zh_inv) = (fixed(8), fixed(9), fixed(10));
+
+    // a b qm + a ql + b qr + c qo + pi + qc
+    let gate = ops.add(
+        &ops.add(
+            &ops.add(&ops.mul(&ops.mul(a, b), qm), &ops.mul(a, ql)),
+            &ops.add(&ops.mul(b, qr), &ops.mul(c, qo)),
+        ),
+        &ops.add(pi, qc),
+    );
+    let numerator = ops.mul(
+        &ops.mul(
+            &ops.mul(&ops.wire(a, x, beta, gamma), &ops.wire(b, x, beta * zkey.k1, gamma)),
+            &ops.wire(c, x, beta * zkey.k2, gamma),
+        ),
+        z,
+    );
+    let denominator = ops.mul(
+        &ops.mul(
+            &ops.mul(&ops.wire(a, fixed(5), beta, gamma), &ops.wire(b, fixed(6), beta, gamma)),
+            &ops.wire(c, fixed(7), beta, gamma),
+        ),
+        zw,
+    );
+    let permutation = ops.scale(alpha, &ops.sub(&numerator, &denominator));
+    let first = ops.scale(alpha * alpha, &ops.mul(&ops.shift(F::zero() - F::one(), z), l1));
+
+    let mut d_t = ops.mul(&ops.add(&ops.add(&gate, &permutation), &first), zh_inv);
+    ntt_batch_helper(&mut d_t[..], true, Some(&cache.coset_gen), 1, &stream);
+
+    if download(&d_t[3 * n + 6..]).iter().any(|coefficient| *coefficient != F::zero()) {
+        let selectors = [0, 1, 2, 3, 4].map(|i| &cache.coefficients[i * n..(i + 1) * n]);
+        let row = failing_gate(selectors, &values, &stream);
+        let n_signals = zkey.n_vars - cache.additions.len();
+        return Err(gate_error(witness, row, &cache.wires, &signals, n_signals, config.symbols.as_deref()));
+    }
+
+    let t1 = polynomial(&d_t[..n]).add_monomial(&blinding[10], n).unwrap();
+    let t2 = polynomial(&d_t[n..2 * n])
+        .add_monomial(&(F::zero() - blinding[10]), 0)
+        .and_then(|t2| t2.add_monomial(&blinding[11], n))
+        .unwrap();
+    let t3 = polynomial(&d_t[2 * n..3 * n + 6]).add_monomial(&(F::zero() - blinding[11]), 0).unwrap();
+
+    let [commit_t1, commit_t2, commit_t3] = commit(&[&t1, &t2, &t3], &cache.ptau, &stream).try_into().unwrap();
+
+    // round 4: evaluations at xi
+    transcript.add_scalar(&alpha);
+    for point in [&commit_t1, &commit_t2, &commit_t3] {
+        transcript.add_point(point);
+    }
+    let xi = transcript.challenge();
+    let xiw = xi * cache.omega;
+
+    let d_fixed = upload(&cache.coefficients);
+    let q = |i: usize| polynomial(&d_fixed[i * n..(i + 1) * n]);
+    let (s1, s2, s3) = (q(5), q(6), q(7));
+
+    let eval_a = pol_a.evaluate(&xi).unwrap();
+    let eval_b = pol_b.evaluate(&xi).unwrap();
+    let eval_c = pol_c.evaluate(&xi).unwrap();
+    let eval_s1 = s1.evaluate(&xi).unwrap();
+    let eval_s2 = s2.evaluate(&xi).unwrap();
+    let eval_zw = pol_z.evaluate(&xiw).unwrap();
+
+    // round 5: openings at xi and xi w
+    transcript.add_scalar(&xi);
+    for value in [&eval_a, &eval_b, &eval_c, &eval_s1, &eval_s2, &eval_zw] {
+        transcript.add_scalar(value);
+    }
+    let v1 = transcript.challenge();
+    let v = [v1, v1 * v1, v1 * v1 * v1, v1 * v1 * v1 * v1, v1 * v1 * v1 * v1 * v1];
+
+    let xin = xi.pow(n);
+    let zh = xin - F::one();
+    let l1_xi = zh * (F::from_u32(n as u32) * (xi - F::one())).inv();
+    let alpha2 = alpha * alpha;
+
+    let e2 = alpha
+        * (eval_a + beta * xi + gamma)
+        * (eval_b + beta * zkey.k1 * xi + gamma)
+        * (eval_c + beta * zkey.k2 * xi + gamma);
+    let e3 = alpha * (eval_a + beta * eval_s1 + gamma) * (eval_b + beta * eval_s2 + gamma) * eval_zw;
+    let e4 = alpha2 * l1_xi;
+
+    let r = combine(&[
+        (&q(0), eval_a * eval_b),
+        (&q(1), eval_a),
+        (&q(2), eval_b),
+        (&q(3), eval_c),
+        (&q(4), F::one()),
+        (&pol_z, e2 + e4),
+        (&s3, F::zero() - e3 * beta),
+        (&t1, F::zero() - zh),
+        (&t2, F::zero() - zh * xin),
+        (&t3, F::zero() - zh * xin * xin),
+    ]);
+    let r0 = pol_pi.evaluate(&xi).unwrap() - e3 * (eval_c + gamma) - e4;
+
+    let openings = [eval_a, eval_b, eval_c, eval_s1, eval_s2];
+    let opened = v.iter().zip(openings).fold(F::zero(), |acc, (v, value)| acc + *v * value);
+    let wxi = combine(&[(&r, F::one()), (&pol_a, v[0]), (&pol_b, v[1]), (&pol_c, v[2]), (&s1, v[3]), (&s2, v[4])])
+        .add_monomial(&(r0 - opened), 0)
+        .unwrap();
+    let wxiw = pol_z.add_monomial(&(F::zero() - eval_zw), 0).unwrap();
+
+    let (wxi, wxiw) = {
+        let _domain = ntt_domain(wxi.degree() + 1);
+        (wxi.divide_by_linear(&xi).unwrap(), wxiw.divide_by_linear(&xiw).unwrap())
+    };
+
+    let [commit_wxi, commit_wxiw] = commit(&[&wxi, &wxiw], &cache.ptau, &stream).try_into().unwrap();
+
+    let proof = PlonkProof {
+        a: serialize_g1_affine(commit_a),
+        b: serialize_g1_affine(commit_b),
+        c: serialize_g1_affine(commit_c),
+        z: serialize_g1_affine(commit_z),
+        t1: serialize_g1_affine(commit_t1),
+        t2: serialize_g1_affine(commit_t2),
+        t3: serialize_g1_affine(commit_t3),
+        wxi: serialize_g1_affine(commit_wxi),
+        wxiw: serialize_g1_affine(commit_wxiw),
+        eval_a: scalar_to_string(&eval_a),
+        eval_b: scalar_to_string(&eval_b),
+        eval_c: scalar_to_string(&eval_c),
+        eval_s1: scalar_to_string(&eval_s1),
+        eval_s2: scalar_to_string(&eval_s2),
+        eval_zw: scalar_to_string(&eval_zw),
+        protocol: "plonk".to_string(),
+        curve: "bn128".to_string(),
+    };
+
+    let public_signals: Vec<String> = signals[1..=zkey.n_public].iter().map(scalar_to_string).collect();
+
+    if config.verify && !plonk_verify_helper(&proof, &public_signals, &cache.verification_key())? {
+        return Err(InvalidProof {
+            witness: witness.to_string(),
+        }
+        .into());
+    }
+
+    Ok((serde_json::json!(proof), serde_json::json!(public_signals)))
+}
+
+pub fn plonk_verify_helper(
+    proof: &PlonkProof,
+    public: &[String],
+    vk: &PlonkVerificationKey,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    if public.len() != vk.n_public {
+        return Err(format!(
+            "Invalid number of public signals. Circuit: {}, given: {}",
+            vk.n_public,
+            public.len()
+        )
+        .into());
+    }
+
+    let [commit_a, commit_b, commit_c, commit_z, commit_t1, commit_t2, commit_t3, commit_wxi, commit_wxiw] = [
+        &proof.a, &proof.b, &proof.c, &proof.z, &proof.t1, &proof.t2, &proof.t3, &proof.wxi, &proof.wxiw,
+    ]
+    .map(|point| deserialize_g1_affine(point));
+    let [eval_a, eval_b, eval_c, eval_s1, eval_s2, eval_zw] = [
+        &proof.eval_a, &proof.eval_b, &proof.eval_c, &proof.eval_s1, &proof.eval_s2, &proof.eval_zw,
+    ]
+    .map(|value| deserialize_scalar(value));
+    let public = parse_public_signals(public);
+
+    let mut transcript = Transcript::new(TranscriptHash::Keccak256);
+    for point in [&vk.qm, &vk.ql, &vk.qr, &vk.qo, &vk.qc, &vk.s1, &vk.s2, &vk.s3] {
+        transcript.add_point(point);
+    }
+    for value in &public {
+        transcript.add_scalar(value);
+    }
+    for point in [&commit_a, &commit_b, &commit_c] {
+        transcript.add_point(point);
+    }
+    let beta = transcript.challenge();
+    transcript.add_scalar(&beta);
+    let gamma = transcript.challenge();
+    transcript.add_scalar(&beta);
+    transcript.add_scalar(&gamma);
+    transcript.add_point(&commit_z);
+    let alpha = transcript.challenge();
+    transcript.add_scalar(&alpha);
+    for point in [&commit_t1, &commit_t2, &commit_t3] {
+        transcript.add_point(point);
+    }
+    let xi = transcript.challenge();
+    transcript.add_scalar(&xi);
+    for value in [&eval_a, &eval_b, &eval_c, &eval_s1, &eval_s2, &eval_zw] {
+        transcript.add_scalar(value);
+    }
+    let v1 = transcript.challenge();
+    let v = [v1, v1 * v1, v1 * v1 * v1, v1 * v1 * v1 * v1, v1 * v1 * v1 * v1 * v1];
+    transcript.add_point(&commit_wxi);
+    transcript.add_point(&commit_wxiw);
+    let u = transcript.challenge();
+
+    let n = 1usize << vk.power;
+    let xin = xi.pow(n);
+    let zh = xin - F::one();
+
+    // L_i(xi) = w^i Z_H(xi) / (n (xi - w^i)), and PI(xi) = -sum(public_i L_i(xi))
+    let mut lagrange = Vec::with_capacity(vk.n_public.max(1));
+    let mut w = F::one();
+    for _ in 0..vk.n_public.max(1) {
+        lagrange.push(w * zh * (F::from_u32(n as u32) * (xi - w)).inv());
+        w = w * vk.w;
+    }
+    let pi = public
+        .iter()
+        .zip(&lagrange)
+        .fold(F::zero(), |acc, (value, l)| acc - *value * *l);
+
+    let alpha2 = alpha * alpha;
+    let e3 = (eval_a + beta * eval_s1 + gamma) * (eval_b + beta * eval_s2 + gamma) * alpha;
+    let r0 = pi - lagrange[0] * alpha2 - e3 * (eval_c + gamma) * eval_zw;
+
+    // D, the commitment to the linearisation polynomial without its constant term
+    let betaxi = beta * xi;
+    let e2 = (eval_a + betaxi + gamma) * (eval_b + betaxi * vk.k1 + gamma) * (eval_c + betaxi * vk.k2 + gamma) * alpha;
+    let d = vk.qm.to_projective() * (eval_a * eval_b)
+        + vk.ql.to_projective() * eval_a
+        + vk.qr.to_projective() * eval_b
+        + vk.qo.to_projective() * eval_c
+        + vk.qc.to_projective()
+        + commit_z.to_projective() * (e2 + lagrange[0] * alpha2 + u)
+        - vk.s3.to_projective() * (e3 * beta * eval_zw)
+        - (commit_t1.to_projective() + commit_t2.to_projective() * xin + commit_t3.to_projective() * (xin * xin)) * zh;
+
+    let f = d
+        + commit_a.to_projective() * v[0]
+        + commit_b.to_projective() * v[1]
+        + commit_c.to_projective() * v[2]
+        + vk.s1.to_projective() * v[3]
+        + vk.s2.to_projective() * v[4];
+    let e = C1::get_generator()
+        * (v[0] * eval_a + v[1] * eval_b + v[2] * eval_c + v[3] * eval_s1 + v[4] * eval_s2 + u * eval_zw - r0);
+
+    // e(-(Wxi + u Wxiw), x_2) e(xi Wxi + u xi w Wxiw + F - E, g2) = 1
+    let a1 = commit_wxi.to_projective() + commit_wxiw.to_projective() * u;
+    let b1 = commit_wxi.to_projective() * xi + commit_wxiw.to_projective() * (u * xi * vk.w) + f - e;
+
+    let neg_a1: G1 = (ProjectiveG1::zero() - a1).into();
+    let b1: G1 = b1.into();
+    let g2: G2 = C2::get_generator().into();
+    let x_2 = vk.x_2;
+
+    let first_thread = std::thread::spawn(move || pairing(&neg_a1, &x_2).unwrap());
+    let second_thread = std::thread::spawn(move || pairing(&b1, &g2).unwrap());
+
+    let first = first_thread.join().unwrap();
+    let second = second_thread.join().unwrap();
+
+    Ok(PairingTargetField::one() == first * second)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proof_helper::tests::{cpu_config, fixture};
+    use crate::CacheManager;
+    use icicle_runtime::Device;
+    use rand_core::OsRng;
+
+    const ZKEY: &str = "plonk/circuit.zkey";
+
+    fn plonk_cache(manager: &mut CacheManager) -> &mut PlonkCache {
+        icicle_runtime::set_device(&Device::new("CPU", 0)).unwrap();
+        let cache = manager.compute_plonk(&fixture(ZKEY)).unwrap();
+        manager.insert_plonk_cache(ZKEY, cache);
+        let cache = manager.get_plonk_cache(ZKEY);
+        cache.prepare();
+        cache
+    }
+
+    fn read_json(path: &str) -> Value {
+        serde_json::from_str(&std::fs::read_to_string(fixture(path)).unwrap()).unwrap()
+    }
+
+    fn verification_key() -> PlonkVerificationKey {
+        serde_json::from_value(read_json("plonk/verification_key.json")).unwrap()
+    }
+
+    #[test]
+    fn test_fixed_proof_matches_fixture() {
+        let mut manager = CacheManager::default();
+        let cache = plonk_cache(&mut manager);
+        let config = ProverConfig {
+            blinding: Blinding::Fixed,
+            ..cpu_config()
+        };
+
+        let (proof, public) = plonk_prove_helper(&fixture("plonk/witness.wtns"), cache, &config, &mut OsRng).unwrap();
+
+        assert_eq!(proof, read_json("plonk/proof.json"));
+        assert_eq!(public, read_json("plonk/public.json"));
+    }
+
+    /// `proof_blinded.json` was made with random blinding, like every proof snarkjs makes, and
+    /// checked with a separate verifier; `proof_tampered.json` is a copy with `eval_a` changed.
+    #[test]
+    fn test_fixture_proof_verifies() {
+        let vk = verification_key();
+        let public: Vec<String> = serde_json::from_value(read_json("plonk/public.json")).unwrap();
+
+        let proof: PlonkProof = serde_json::from_value(read_json("plonk/proof_blinded.json")).unwrap();
+        assert!(plonk_verify_helper(&proof, &public, &vk).unwrap());
+
+        let tampered: PlonkProof = serde_json::from_value(read_json("plonk/proof_tampered.json")).unwrap();
+        assert!(!plonk_verify_helper(&tampered, &public, &vk).unwrap());
+        // and through the file API, which must not fail on a bad proof
+        let (public, vk) = (fixture("plonk/public.json"), fixture("plonk/verification_key.json"));
+        assert!(!crate::plonk_verify(&fixture("plonk/proof_tampered.json"), &public, &vk).unwrap());
+    }
+
+    #[test]
+    fn test_bad_witness_names_gate_signals() {
+        let mut manager = CacheManager::default();
+        let cache = plonk_cache(&mut manager);
+        let symbols = Symbols::parse("1,1,0,main.out\n2,2,0,main.a\n3,3,0,main.b\n4,4,0,main.ab\n").unwrap();
+        let config = ProverConfig {
+            symbols: Some(std::sync::Arc::new(symbols)),
+            ..cpu_config()
+        };
+
+        // main.ab = main.a * main.b no longer holds
+        let mut signals = crate::witness::read_wtns_helper(&fixture("plonk/witness.wtns")).unwrap();
+        signals[4] = signals[4] + F::one();
+        let witness = std::env::temp_dir().join("icicle_snark_bad_plonk.wtns");
+        let witness = witness.to_str().unwrap();
+        crate::witness::write_wtns_helper(&signals, witness, None).unwrap();
+
+        let error = plonk_prove_helper(witness, cache, &config, &mut OsRng).unwrap_err();
+        std::fs::remove_file(witness).unwrap();
+        assert_eq!(
+            error.to_string(),
+            format!("Witness {} does not satisfy gate 1 of the circuit: main.a = 3, main.b = 5, main.ab = 16", witness)
+        );
+    }
+
+    #[test]
+    fn test_blinded_proof_verifies() {
+        let mut manager = CacheManager::default();
+        let cache = plonk_cache(&mut manager);
+
+        let (proof, public) = plonk_prove_helper(&fixture("plonk/witness.wtns"), cache, &cpu_config(), &mut OsRng).unwrap();
+
+        assert_ne!(proof, read_json("plonk/proof.json"));
+        let proof: PlonkProof = serde_json::from_value(proof).unwrap();
+        let public: Vec<String> = serde_json::from_value(public).unwrap();
+        assert!(plonk_verify_helper(&proof, &public, &verification_key()).unwrap());
+    }
+}
@@ -0,0 +1,188 @@
+//! Poseidon over the BN254 scalar field, with the parameters of circomlib's `Poseidon(n)`:
+//! the x^5 S-box, 8 full rounds and width-dependent partial rounds, for 1 to 16 inputs.
+//!
+//! The round constants and MDS matrices are not embedded but regenerated, the first time a
+//! width is used, with the Grain LFSR of the Poseidon reference script that circomlib's
+//! constants come from.
+
+use icicle_core::traits::{Arithmetic, FieldImpl};
+use num_bigint::BigUint;
+use std::collections::VecDeque;
+use std::sync::OnceLock;
+
+use crate::F;
+
+const FULL_ROUNDS: usize = 8;
+/// Partial rounds for `1..=16` inputs, as in circomlib.
+const PARTIAL_ROUNDS: [usize; 16] = [56, 57, 56, 60, 60, 63, 64, 63, 60, 66, 60, 65, 70, 60, 64, 68];
+const FIELD_BITS: usize = 254;
+
+struct PoseidonParams {
+    round_constants: Vec<F>,
+    mds: Vec<Vec<F>>,
+}
+
+static PARAMS: [OnceLock<PoseidonParams>; 16] = [const { OnceLock::new() }; 16];
+
+/// The self-shrinking Grain LFSR, seeded with the permutation parameters.
+struct Grain {
+    state: VecDeque<u8>,
+}
+
+impl Grain {
+    fn new(width: usize, partial_rounds: usize) -> Self {
+        // prime field, x^5 S-box, then the field size, width and round counts
+        let fields = [(1, 2), (0, 4), (FIELD_BITS, 12), (width, 12), (FULL_ROUNDS, 10), (partial_rounds, 10)];
+        let mut state = VecDeque::with_capacity(80);
+        for (value, bits) in fields {
+            for i in (0..bits).rev() {
+                state.push_back(((value >> i) & 1) as u8);
+            }
+        }
+        state.extend([1u8; 30]);
+
+        let mut grain = Self { state };
+        for _ in 0..160 {
+            grain.step();
+        }
+        grain
+    }
+
+    fn step(&mut self) -> u8 {
+        let s = &self.state;
+        let bit = s[62] ^ s[51] ^ s[38] ^ s[23] ^ s[13] ^ s[0];
+        self.state.pop_front();
+        self.state.push_back(bit);
+        bit
+    }
+
+    /// Bits are taken in pairs, and the second one kept only when the first is set.
+    fn next_bit(&mut self) -> u8 {
+        loop {
+            if self.step() == 1 {
+                return self.step();
+            }
+            self.step();
+        }
+    }
+
+    fn next_int(&mut self) -> BigUint {
+        let mut value = BigUint::ZERO;
+        for _ in 0..FIELD_BITS {
+            value = (value << 1u32) + self.next_bit() as u32;
+        }
+        value
+    }
+}
+
+fn modulus() -> BigUint {
+    BigUint::from_bytes_le(&(F::zero() - F::one()).to_bytes_le()) + 1u32
+}
+
+fn to_field(value: &BigUint) -> F {
+    F::from_bytes_le(&value.to_bytes_le())
+}
+
+fn generate_params(width: usize, partial_rounds: usize) -> PoseidonParams {
+    let modulus = modulus();
+    let mut grain = Grain::new(width, partial_rounds);
+
+    let round_constants = (0..(FULL_ROUNDS + partial_rounds) * width)
+        .map(|_| loop {
+            let value = grain.next_int();
+            if value < modulus {
+                return to_field(&value);
+            }
+        })
+        .collect();
+
+    // Cauchy matrix 1 / (x_i + y_j)
+    let points: Vec<F> = (0..2 * width).map(|_| to_field(&(grain.next_int() % &modulus))).collect();
+    let (xs, ys) = points.split_at(width);
+    let mds = xs
+        .iter()
+        .map(|x| ys.iter().map(|y| (*x + *y).inv()).collect())
+        .collect();
+
+    PoseidonParams { round_constants, mds }
+}
+
+fn sbox(value: F) -> F {
+    let square = value.sqr();
+    square.sqr() * value
+}
+
+/// circomlib's `Poseidon(inputs.len())`: the first element of the permuted state
+/// `[0, inputs...]`. Takes 1 to 16 inputs.
+pub fn poseidon(inputs: &[F]) -> F {
+    assert!(
+        (1..=16).contains(&inputs.len()),
+        "Poseidon takes 1 to 16 inputs, got {}",
+        inputs.len()
+    );
+
+    let width = inputs.len() + 1;
+    let partial_rounds = PARTIAL_ROUNDS[inputs.len() - 1];
+    let params = PARAMS[inputs.len() - 1].get_or_init(|| generate_params(width, partial_rounds));
+
+    let mut state = Vec::with_capacity(width);
+    state.push(F::zero());
+    state.extend_from_slice(inputs);
+
+    for round in 0..FULL_ROUNDS + partial_rounds {
+        for (i, value) in state.iter_mut().enumerate() {
+            *value = *value + params.round_constants[round * width + i];
+        }
+
+        if round < FULL_ROUNDS / 2 || round >= FULL_ROUNDS / 2 + partial_rounds {
+            for value in state.iter_mut() {
+                *value = sbox(*value);
+            }
+        } else {
+            state[0] = sbox(state[0]);
+        }
+
+        state = params
+            .mds
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .zip(&state)
+                    .fold(F::zero(), |acc, (m, value)| acc + *m * *value)
+            })
+            .collect();
+    }
+
+    state[0]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scalar(value: &str) -> F {
+        F::from_bytes_le(&BigUint::parse_bytes(value.as_bytes(), 10).unwrap().to_bytes_le())
+    }
+
+    #[test]
+    fn test_poseidon_matches_circomlib() {
+        let inputs: Vec<F> = (1..=5).map(F::from_u32).collect();
+
+        assert_eq!(
+            poseidon(&inputs[..1]),
+            scalar("18586133768512220936620570745912940619677854269274689475585506675881198879027")
+        );
+        assert_eq!(
+            poseidon(&inputs[..2]),
+            scalar("7853200120776062878684798364095072458815029376092732009249414926327459813530")
+        );
+        assert_eq!(
+            poseidon(&inputs[..4]),
+            scalar("18821383157269793795438455681495246036402687001665670618754263018637548127333")
+        );
+        assert_eq!(
+            poseidon(&[F::from_u32(3), F::from_u32(4), F::zero(), F::zero(), F::zero()]),
+            scalar("5811595552068139067952687508729883632420015185677766880877743348592482390548")
+        );
+    }
+}
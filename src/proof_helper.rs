@@ -1,23 +1,25 @@
 use crate::{
-    cache::{VerificationKey, ZKeyCache}, conversions::{deserialize_g1_affine, deserialize_g2_affine, from_u8, serialize_g1_affine, serialize_g2_affine}, file_wrapper::FileWrapper, icicle_helper::{msm_helper, ntt_helper}, ProjectiveG1, ProjectiveG2, F
+    config::{Blinding, BlindingFactors, ProverConfig, QuotientStrategy}, cache::{BaseShard, Bases, BasesRef, Csr, DeviceShard, MatrixStorage, SparseMatrix, VerificationKey, ZKeyCache}, conversions::{deserialize_g1_affine, deserialize_g2_affine, from_u8, serialize_g1_affine, serialize_g2_affine}, file_wrapper::{FileWrapper, Section}, icicle_helper::{msm_bases_helper, ntt_helper, sum_partials}, zkey::ZKey, ProjectiveG1, ProjectiveG2, F
 };
-use icicle_bn254::curve::{G1Projective, ScalarField};
+use icicle_bn254::curve::ScalarField;
 use icicle_core::{
-    field::Field, pairing::pairing, traits::{FieldImpl, MontgomeryConvertible}, vec_ops::{mul_scalars, sub_scalars, VecOpsConfig}
+    field::Field, pairing::pairing, traits::{Arithmetic, FieldImpl}, vec_ops::{mul_scalars, sparse_matvec, sub_scalars, VecOpsConfig}
 };
 use icicle_runtime::{
     memory::{DeviceSlice, DeviceVec, HostOrDeviceSlice, HostSlice}, stream::IcicleStream
 };
 use num_bigint::BigUint;
+use rand_core::CryptoRngCore;
 use serde::{Serialize, Deserialize};
 use serde_json::Value;
+use std::fmt;
+use std::sync::mpsc;
+use zeroize::Zeroize;
 
 use rayon::prelude::*;
 
-#[cfg(not(feature = "no-randomness"))]
-use icicle_bn254::curve::ScalarCfg;
-#[cfg(not(feature = "no-randomness"))]
-use icicle_core::traits::GenerateRandom;
+/// Upper bound on the number of proofs whose MSMs are batched together.
+const MAX_PROOFS_PER_BATCH: usize = 8;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Proof {
@@ -28,75 +30,139 @@ pub struct Proof {
     pub curve: String,
 }
 
-pub fn construct_r1cs(witness: &[ScalarField], zkey_cache: &ZKeyCache) -> DeviceVec<ScalarField> {
-    let mut stream = IcicleStream::create().unwrap();
-    let mut cfg = VecOpsConfig::default();
-    cfg.is_async = true;
-    cfg.stream_handle = *stream;
+struct PreparedWitness {
+    path: String,
+    source: WitnessSource,
+    public_signals: Vec<String>,
+}
 
-    let n_coef = zkey_cache.c_values.len();
-    let nof_coef = zkey_cache.zkey.domain_size;
+enum WitnessSource {
+    File {
+        wtns_file: FileWrapper,
+        sections: Vec<Vec<Section>>,
+    },
+    Computed(Vec<ScalarField>),
+}
 
-    let mut d_second_slice = DeviceVec::device_malloc_async(n_coef, &stream).unwrap();
-    let mut d_vec = DeviceVec::device_malloc_async(nof_coef * 3, &stream).unwrap();
+impl PreparedWitness {
+    fn scalars(&self) -> &[ScalarField] {
+        match &self.source {
+            WitnessSource::File { wtns_file, sections } => from_u8(wtns_file.read_section(&sections[..], 2).unwrap()),
+            WitnessSource::Computed(scalars) => scalars,
+        }
+    }
+}
 
-    let mut out_buff_b_a = vec![ScalarField::zero(); nof_coef * 2];
+fn prepare_witness(witness: &str, zkey_cache: &ZKeyCache) -> Result<PreparedWitness, Box<dyn std::error::Error>> {
+    let (fd_wtns, sections_wtns) = FileWrapper::read_bin_file(witness, "wtns", 2)?;
 
-    let first_slice = &zkey_cache.first_slice;
-    let s_values = &zkey_cache.s_values;
-    let c_values = &zkey_cache.c_values;
-    let m_values = &zkey_cache.m_values;
+    let mut wtns_file = FileWrapper::new(fd_wtns)?;
 
-    let mut second_slice = Vec::with_capacity(n_coef);
-    unsafe {
-        second_slice.set_len(n_coef);
+    let wtns = wtns_file.read_wtns_header(&sections_wtns[..])?;
+
+    let zkey = &zkey_cache.zkey;
+
+    if !F::eq(&zkey.r, &wtns.q) {
+        return Err("Curve of the witness does not match the curve of the proving key".into());
     }
 
-    second_slice
-        .par_iter_mut()
-        .enumerate()
-        .for_each(|(i, slice_elem)| {
-            let s = s_values[i];
-            *slice_elem = witness[s];
-        });
+    if wtns.n_witness != zkey.n_vars {
+        return Err(format!(
+            "Invalid witness length. Circuit: {}, witness: {}",
+            zkey.n_vars, wtns.n_witness
+        )
+        .into());
+    }
 
-    let second_slice = HostSlice::from_mut_slice(&mut second_slice);
+    let buff_witness = wtns_file.read_section(&sections_wtns[..], 2)?;
 
-    let mut res = Vec::with_capacity(n_coef);
-    unsafe {
-        res.set_len(n_coef);
+    let mut public_signals = Vec::with_capacity(zkey.n_public);
+    let field_size = ScalarField::zero().to_bytes_le().len();
+
+    for i in 1..=zkey.n_public {
+        let start = i * field_size;
+        let end = start + field_size;
+        let b = &buff_witness[start..end];
+        let scalar_bytes: BigUint = BigUint::from_bytes_le(b);
+        public_signals.push(scalar_bytes.to_str_radix(10));
     }
-    let res = HostSlice::from_mut_slice(&mut res);
 
-    d_second_slice
-        .copy_from_host_async(second_slice, &stream)
+    Ok(PreparedWitness {
+        path: witness.to_string(),
+        source: WitnessSource::File {
+            wtns_file,
+            sections: sections_wtns,
+        },
+        public_signals,
+    })
+}
+
+/// Takes a witness computed in memory, named `source` in errors.
+fn prepare_computed_witness(
+    witness: Vec<ScalarField>,
+    source: &str,
+    zkey_cache: &ZKeyCache,
+) -> Result<PreparedWitness, Box<dyn std::error::Error>> {
+    let zkey = &zkey_cache.zkey;
+
+    if witness.len() != zkey.n_vars {
+        return Err(format!(
+            "Invalid witness length. Circuit: {}, witness: {}",
+            zkey.n_vars,
+            witness.len()
+        )
+        .into());
+    }
+
+    let public_signals = witness[1..=zkey.n_public]
+        .iter()
+        .map(|scalar| BigUint::from_bytes_le(&scalar.to_bytes_le()).to_str_radix(10))
+        .collect();
+
+    Ok(PreparedWitness {
+        path: source.to_string(),
+        source: WitnessSource::Computed(witness),
+        public_signals,
+    })
+}
+
+pub fn construct_r1cs(witness: &[ScalarField], zkey_cache: &ZKeyCache, quotient: QuotientStrategy) -> DeviceVec<ScalarField> {
+    let mut stream = IcicleStream::create().unwrap();
+
+    let nof_coef = zkey_cache.zkey.domain_size;
+
+    let mut d_witness = DeviceVec::device_malloc_async(witness.len(), &stream).unwrap();
+    let mut d_vec = DeviceVec::device_malloc_async(nof_coef * 3, &stream).unwrap();
+    d_witness
+        .copy_from_host_async(HostSlice::from_slice(witness), &stream)
         .unwrap();
-    ScalarField::from_mont(&mut d_second_slice, &stream);
-    mul_scalars(&first_slice[..], &d_second_slice, res, &cfg).unwrap();
+
+    construct_r1cs_async(witness, &d_witness, zkey_cache, quotient, &mut d_vec, &stream);
 
     stream.synchronize().unwrap();
+    stream.destroy().unwrap();
 
-    let zero_scalar = ScalarField::zero();
+    d_vec
+}
 
-    for i in 0..n_coef {
-        let c = c_values[i];
-        let m = m_values[i];
-        let idx = c + m * nof_coef;
-        let value = &mut out_buff_b_a[idx];
+/// Evaluates the R1CS into `d_vec` on `stream`, reusing the caller's device buffers.
+/// `d_witness` is the device copy of `witness`, which the host fallback reads instead.
+/// On return `d_vec[nof_coef..2 * nof_coef]` holds the H scalars once the stream is synchronized.
+fn construct_r1cs_async(
+    witness: &[ScalarField],
+    d_witness: &DeviceSlice<ScalarField>,
+    zkey_cache: &ZKeyCache,
+    quotient: QuotientStrategy,
+    d_vec: &mut DeviceSlice<ScalarField>,
+    stream: &IcicleStream,
+) {
+    let mut cfg = VecOpsConfig::default();
+    cfg.is_async = true;
+    cfg.stream_handle = stream.into();
 
-        if zero_scalar.eq(value) {
-            *value = res[i];
-        } else if !res[i].eq(&zero_scalar) {
-            *value = *value + res[i];
-        }
-    }
+    let nof_coef = zkey_cache.zkey.domain_size;
 
-    d_vec[0..nof_coef]
-        .copy_from_host_async(HostSlice::from_slice(&out_buff_b_a[nof_coef..]), &stream)
-        .unwrap();
-    d_vec[nof_coef..nof_coef * 2]
-        .copy_from_host_async(HostSlice::from_slice(&out_buff_b_a[..nof_coef]), &stream)
-        .unwrap();
+    evaluate_ab(witness, d_witness, &zkey_cache.matrix, &mut d_vec[..nof_coef * 2], stream);
 
     let d_vec_copy = unsafe {
         DeviceSlice::from_mut_slice(std::slice::from_raw_parts_mut(
@@ -113,44 +179,40 @@ pub fn construct_r1cs(witness: &[ScalarField], zkey_cache: &ZKeyCache) -> Device
     )
     .unwrap();
 
-    ntt_helper(&mut d_vec, true, None, &stream);
-
-    #[cfg(not(feature = "coset-gen"))] {
-        let keys = &zkey_cache.keys;
-
-        mul_scalars(
-            &d_vec[..nof_coef],
-            &keys[..],
-            &mut d_vec_copy[..nof_coef],
-            &cfg,
-        )
-        .unwrap();
-        mul_scalars(
-            &d_vec[nof_coef..nof_coef * 2],
-            &keys[..],
-            &mut d_vec_copy[nof_coef..2 * nof_coef],
-            &cfg,
-        )
-        .unwrap();
-        mul_scalars(
-            &d_vec[nof_coef * 2..],
-            &keys[..],
-            &mut d_vec_copy[2 * nof_coef..],
-            &cfg,
-        )
-        .unwrap();
+    ntt_helper(d_vec, true, None, stream);
+
+    match quotient {
+        QuotientStrategy::PrecomputedKeys => {
+            let keys = zkey_cache.keys.as_ref().expect("coset keys are computed by ZKeyCache::prepare");
+
+            mul_scalars(
+                &d_vec[..nof_coef],
+                &keys[..],
+                &mut d_vec_copy[..nof_coef],
+                &cfg,
+            )
+            .unwrap();
+            mul_scalars(
+                &d_vec[nof_coef..nof_coef * 2],
+                &keys[..],
+                &mut d_vec_copy[nof_coef..2 * nof_coef],
+                &cfg,
+            )
+            .unwrap();
+            mul_scalars(
+                &d_vec[nof_coef * 2..],
+                &keys[..],
+                &mut d_vec_copy[2 * nof_coef..],
+                &cfg,
+            )
+            .unwrap();
+
+            ntt_helper(d_vec, false, None, stream);
+        }
+        QuotientStrategy::CosetNtt => ntt_helper(d_vec, false, Some(&zkey_cache.inc), stream),
     }
 
-    #[cfg(not(feature = "coset-gen"))]
-    ntt_helper(&mut d_vec, false, None, &stream);
-    #[cfg(feature = "coset-gen")]
-    ntt_helper(&mut d_vec, false, Some(&zkey_cache.inc), &stream);
-
-    stream.synchronize().unwrap();
-    stream.destroy().unwrap();
-
     // L * R - O
-    let cfg: VecOpsConfig = VecOpsConfig::default();
     mul_scalars(
         &d_vec[0..nof_coef],
         &d_vec[nof_coef..nof_coef * 2],
@@ -165,14 +227,109 @@ pub fn construct_r1cs(witness: &[ScalarField], zkey_cache: &ZKeyCache) -> Device
         &cfg,
     )
     .unwrap();
+}
 
-    d_vec
+/// Writes the B and A evaluations of the witness into `d_ab`, B first.
+///
+/// Runs as a sparse matrix-vector product wherever the matrix is kept. If the backend cannot
+/// multiply it, the rows are evaluated on the host instead and copied over.
+fn evaluate_ab(
+    witness: &[ScalarField],
+    d_witness: &DeviceSlice<ScalarField>,
+    matrix: &SparseMatrix,
+    d_ab: &mut DeviceSlice<ScalarField>,
+    stream: &IcicleStream,
+) {
+    let mut cfg = VecOpsConfig::default();
+    cfg.is_async = true;
+    cfg.stream_handle = stream.into();
+
+    let on_cpu = icicle_runtime::get_active_device().unwrap().get_device_type() == "CPU";
+
+    let evaluated = match &matrix.storage {
+        MatrixStorage::Device(csr) => {
+            sparse_matvec(&csr.row_ptr[..], &csr.col_idx[..], &csr.values[..], d_witness, d_ab, &cfg).is_ok()
+        }
+        // the CPU backend works on host memory
+        MatrixStorage::Host(csr) if on_cpu => sparse_matvec(
+            HostSlice::from_slice(&csr.row_ptr),
+            HostSlice::from_slice(&csr.col_idx),
+            HostSlice::from_slice(&csr.values),
+            d_witness,
+            d_ab,
+            &cfg,
+        )
+        .is_ok(),
+        MatrixStorage::Host(_) => false,
+    };
+
+    if !evaluated {
+        let ab = match &matrix.storage {
+            MatrixStorage::Host(csr) => sparse_matvec_host(csr, matrix.nof_rows, witness),
+            MatrixStorage::Device(csr) => sparse_matvec_host(&csr.to_host(), matrix.nof_rows, witness),
+        };
+        d_ab.copy_from_host_async(HostSlice::from_slice(&ab), stream)
+            .unwrap();
+        stream.synchronize().unwrap();
+    }
+}
+
+fn sparse_matvec_host(csr: &Csr<Vec<u32>, Vec<ScalarField>>, nof_rows: usize, witness: &[ScalarField]) -> Vec<ScalarField> {
+    (0..nof_rows)
+        .into_par_iter()
+        .map(|row| {
+            let entries = csr.row_ptr[row] as usize..csr.row_ptr[row + 1] as usize;
+            csr.col_idx[entries.clone()]
+                .iter()
+                .zip(&csr.values[entries])
+                .fold(ScalarField::zero(), |sum, (&col, &value)| sum + value * witness[col as usize])
+        })
+        .collect()
+}
+
+/// Streams the commitment MSMs are spread over.
+struct CommitmentStreams {
+    streams: Vec<IcicleStream>,
+}
+
+impl CommitmentStreams {
+    /// Creates `count` streams, capped at one per MSM.
+    fn create(count: usize) -> Self {
+        let streams = (0..count.clamp(1, 5)).map(|_| IcicleStream::create().unwrap()).collect();
+        CommitmentStreams { streams }
+    }
+
+    /// Stream of the G2 MSM, which is the slowest.
+    fn g2(&self) -> &IcicleStream {
+        &self.streams[0]
+    }
+
+    /// Stream of the `i`-th G1 MSM, in the order A, B1, C, H.
+    fn g1(&self, i: usize) -> &IcicleStream {
+        match self.streams.len() {
+            1 => &self.streams[0],
+            n => &self.streams[1 + i % (n - 1)],
+        }
+    }
+
+    fn synchronize(&self) {
+        for stream in &self.streams {
+            stream.synchronize().unwrap();
+        }
+    }
+
+    fn destroy(mut self) {
+        for stream in &mut self.streams {
+            stream.destroy().unwrap();
+        }
+    }
 }
 
 pub fn groth16_commitments(
     d_vec: DeviceVec<F>,
     scalars: &[F],
     zkey_cache: &ZKeyCache,
+    streams: usize,
 ) -> (
     ProjectiveG1,
     ProjectiveG1,
@@ -180,6 +337,10 @@ pub fn groth16_commitments(
     ProjectiveG1,
     ProjectiveG1,
 ) {
+    if !zkey_cache.shards.is_empty() {
+        return groth16_sharded_commitments(&d_vec, scalars, zkey_cache, streams);
+    }
+
     let nof_coef = zkey_cache.zkey.domain_size;
     // A, B, C
     let points_a = &zkey_cache.points_a;
@@ -188,132 +349,582 @@ pub fn groth16_commitments(
     let points_c = &zkey_cache.points_c;
     let points_h = &zkey_cache.points_h;
 
-    let mut stream_g1 = IcicleStream::create().unwrap();
-    let mut stream_g2 = IcicleStream::create().unwrap();
+    let streams = CommitmentStreams::create(streams);
 
     let scalars = HostSlice::from_slice(scalars);
-    let mut d_scalars = DeviceVec::device_malloc_async(scalars.len(), &stream_g1).unwrap();
-    d_scalars.copy_from_host_async(scalars, &stream_g1).unwrap();
-
-    let commitment_a = msm_helper(&d_scalars[..], points_a, &stream_g1);
-    let commitment_b1 = msm_helper(&d_scalars[..], points_b1, &stream_g1);
-    let commitment_c = msm_helper(
+    let mut d_scalars = DeviceVec::device_malloc_async(scalars.len(), streams.g1(0)).unwrap();
+    d_scalars.copy_from_host_async(scalars, streams.g1(0)).unwrap();
+    // every stream reads the scalars
+    streams.g1(0).synchronize().unwrap();
+
+    let commitment_a = msm_bases_helper(&d_scalars[..], zkey_cache.bases(points_a), &zkey_cache.msm.a, 1, streams.g1(0));
+    let commitment_b1 = msm_bases_helper(&d_scalars[..], zkey_cache.bases(points_b1), &zkey_cache.msm.b1, 1, streams.g1(1));
+    let commitment_c = msm_bases_helper(
         &d_scalars[zkey_cache.zkey.n_public + 1..],
-        points_c,
-        &stream_g1,
+        zkey_cache.bases(points_c),
+        &zkey_cache.msm.c,
+        1,
+        streams.g1(2),
     );
-    let commitment_h = msm_helper(&d_vec[nof_coef..nof_coef * 2], points_h, &stream_g1);
-    let commitment_b = msm_helper(&d_scalars[..], points_b, &stream_g2);
+    let commitment_h = msm_bases_helper(&d_vec[nof_coef..nof_coef * 2], zkey_cache.bases(points_h), &zkey_cache.msm.h, 1, streams.g1(3));
+    let commitment_b = msm_bases_helper(&d_scalars[..], zkey_cache.bases(points_b), &zkey_cache.msm.b, 1, streams.g2());
 
-    let mut pi_a = [ProjectiveG1::zero(); 1];
-    let mut pi_b1 = [ProjectiveG1::zero(); 1];
-    let mut pi_b = [ProjectiveG2::zero(); 1];
-    let mut pi_c = [ProjectiveG1::zero(); 1];
-    let mut pi_h = [ProjectiveG1::zero(); 1];
+    let mut pi_a = vec![ProjectiveG1::zero(); commitment_a.len()];
+    let mut pi_b1 = vec![ProjectiveG1::zero(); commitment_b1.len()];
+    let mut pi_b = vec![ProjectiveG2::zero(); commitment_b.len()];
+    let mut pi_c = vec![ProjectiveG1::zero(); commitment_c.len()];
+    let mut pi_h = vec![ProjectiveG1::zero(); commitment_h.len()];
 
     commitment_a
-        .copy_to_host_async(HostSlice::from_mut_slice(&mut pi_a[..]), &stream_g1)
+        .copy_to_host_async(HostSlice::from_mut_slice(&mut pi_a[..]), streams.g1(0))
         .unwrap();
 
     commitment_b1
-        .copy_to_host_async(HostSlice::from_mut_slice(&mut pi_b1[..]), &stream_g1)
+        .copy_to_host_async(HostSlice::from_mut_slice(&mut pi_b1[..]), streams.g1(1))
         .unwrap();
 
     commitment_b
-        .copy_to_host_async(HostSlice::from_mut_slice(&mut pi_b[..]), &stream_g2)
+        .copy_to_host_async(HostSlice::from_mut_slice(&mut pi_b[..]), streams.g2())
         .unwrap();
 
     commitment_c
-        .copy_to_host_async(HostSlice::from_mut_slice(&mut pi_c[..]), &stream_g1)
+        .copy_to_host_async(HostSlice::from_mut_slice(&mut pi_c[..]), streams.g1(2))
         .unwrap();
 
     commitment_h
-        .copy_to_host_async(HostSlice::from_mut_slice(&mut pi_h[..]), &stream_g1)
+        .copy_to_host_async(HostSlice::from_mut_slice(&mut pi_h[..]), streams.g1(3))
+        .unwrap();
+
+    streams.synchronize();
+    streams.destroy();
+
+    (
+        sum_partials(&pi_a, 1)[0],
+        sum_partials(&pi_b1, 1)[0],
+        sum_partials(&pi_b, 1)[0],
+        sum_partials(&pi_c, 1)[0],
+        sum_partials(&pi_h, 1)[0],
+    )
+}
+
+/// Runs every device's share of the commitment MSMs on its own thread and sums the partial
+/// results on the host.
+fn groth16_sharded_commitments(
+    d_vec: &DeviceVec<F>,
+    scalars: &[F],
+    zkey_cache: &ZKeyCache,
+    streams: usize,
+) -> (
+    ProjectiveG1,
+    ProjectiveG1,
+    ProjectiveG2,
+    ProjectiveG1,
+    ProjectiveG1,
+) {
+    let nof_coef = zkey_cache.zkey.domain_size;
+    let mut scalars_h = vec![F::zero(); nof_coef];
+    d_vec[nof_coef..nof_coef * 2]
+        .copy_to_host(HostSlice::from_mut_slice(&mut scalars_h))
         .unwrap();
+    let scalars_h = &scalars_h[..];
+
+    let partials: Vec<_> = std::thread::scope(|scope| {
+        let workers: Vec<_> = zkey_cache
+            .shards
+            .iter()
+            .filter(|shard| !shard.primary)
+            .map(|shard| {
+                scope.spawn(move || {
+                    icicle_runtime::set_device(&shard.device).unwrap();
+                    shard_commitments(shard, scalars, scalars_h, zkey_cache, streams)
+                })
+            })
+            .collect();
+
+        let mut partials: Vec<_> = zkey_cache
+            .shards
+            .iter()
+            .filter(|shard| shard.primary)
+            .map(|shard| shard_commitments(shard, scalars, scalars_h, zkey_cache, streams))
+            .collect();
+        partials.extend(workers.into_iter().map(|worker| worker.join().unwrap()));
+        partials
+    });
+
+    partials.into_iter().fold(
+        (
+            ProjectiveG1::zero(),
+            ProjectiveG1::zero(),
+            ProjectiveG2::zero(),
+            ProjectiveG1::zero(),
+            ProjectiveG1::zero(),
+        ),
+        |acc, part| (acc.0 + part.0, acc.1 + part.1, acc.2 + part.2, acc.3 + part.3, acc.4 + part.4),
+    )
+}
+
+/// Computes the MSMs of one shard on the active device. Commitments the shard has no part
+/// of come back as zero.
+fn shard_commitments(
+    shard: &DeviceShard,
+    scalars: &[F],
+    scalars_h: &[F],
+    zkey_cache: &ZKeyCache,
+    streams: usize,
+) -> (
+    ProjectiveG1,
+    ProjectiveG1,
+    ProjectiveG2,
+    ProjectiveG1,
+    ProjectiveG1,
+) {
+    let streams = CommitmentStreams::create(streams);
+
+    let mut d_scalars = DeviceVec::device_malloc_async(scalars.len(), streams.g1(0)).unwrap();
+    d_scalars
+        .copy_from_host_async(HostSlice::from_slice(scalars), streams.g1(0))
+        .unwrap();
+    let mut d_scalars_h = DeviceVec::device_malloc_async(scalars_h.len(), streams.g1(0)).unwrap();
+    d_scalars_h
+        .copy_from_host_async(HostSlice::from_slice(scalars_h), streams.g1(0))
+        .unwrap();
+    streams.g1(0).synchronize().unwrap();
+
+    let d_scalars_c = &d_scalars[zkey_cache.zkey.n_public + 1..];
+
+    fn shard_bases<'a, P>(shard: &'a BaseShard<P>, bases: &'a Bases<P>, zkey_cache: &'a ZKeyCache) -> BasesRef<'a, P> {
+        match &shard.points {
+            Some(points) => BasesRef::Device(points),
+            None => zkey_cache.bases(bases).slice(shard.range.clone()),
+        }
+    }
+
+    let commitment_a = shard.a.as_ref().map(|shard| {
+        let points = shard_bases(shard, &zkey_cache.points_a, zkey_cache);
+        msm_bases_helper(&d_scalars[shard.range.clone()], points, &zkey_cache.msm.a, 1, streams.g1(0))
+    });
+    let commitment_b1 = shard.b1.as_ref().map(|shard| {
+        let points = shard_bases(shard, &zkey_cache.points_b1, zkey_cache);
+        msm_bases_helper(&d_scalars[shard.range.clone()], points, &zkey_cache.msm.b1, 1, streams.g1(1))
+    });
+    let commitment_c = shard.c.as_ref().map(|shard| {
+        let points = shard_bases(shard, &zkey_cache.points_c, zkey_cache);
+        msm_bases_helper(&d_scalars_c[shard.range.clone()], points, &zkey_cache.msm.c, 1, streams.g1(2))
+    });
+    let commitment_h = shard.h.as_ref().map(|shard| {
+        let points = shard_bases(shard, &zkey_cache.points_h, zkey_cache);
+        msm_bases_helper(&d_scalars_h[shard.range.clone()], points, &zkey_cache.msm.h, 1, streams.g1(3))
+    });
+    let commitment_b = shard.b.as_ref().map(|shard| {
+        let points = shard_bases(shard, &zkey_cache.points_b, zkey_cache);
+        msm_bases_helper(&d_scalars[shard.range.clone()], points, &zkey_cache.msm.b, 1, streams.g2())
+    });
+
+    let mut pi_a = vec![ProjectiveG1::zero(); commitment_a.as_ref().map_or(0, |c| c.len())];
+    let mut pi_b1 = vec![ProjectiveG1::zero(); commitment_b1.as_ref().map_or(0, |c| c.len())];
+    let mut pi_b = vec![ProjectiveG2::zero(); commitment_b.as_ref().map_or(0, |c| c.len())];
+    let mut pi_c = vec![ProjectiveG1::zero(); commitment_c.as_ref().map_or(0, |c| c.len())];
+    let mut pi_h = vec![ProjectiveG1::zero(); commitment_h.as_ref().map_or(0, |c| c.len())];
+
+    for (i, (commitment, pi)) in [
+        (&commitment_a, &mut pi_a),
+        (&commitment_b1, &mut pi_b1),
+        (&commitment_c, &mut pi_c),
+        (&commitment_h, &mut pi_h),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        if let Some(commitment) = commitment {
+            commitment
+                .copy_to_host_async(HostSlice::from_mut_slice(&mut pi[..]), streams.g1(i))
+                .unwrap();
+        }
+    }
+    if let Some(commitment) = &commitment_b {
+        commitment
+            .copy_to_host_async(HostSlice::from_mut_slice(&mut pi_b[..]), streams.g2())
+            .unwrap();
+    }
+
+    streams.synchronize();
+    streams.destroy();
+
+    (
+        sum_partials(&pi_a, 1)[0],
+        sum_partials(&pi_b1, 1)[0],
+        sum_partials(&pi_b, 1)[0],
+        sum_partials(&pi_c, 1)[0],
+        sum_partials(&pi_h, 1)[0],
+    )
+}
+
+impl BlindingFactors {
+    fn new(blinding: &Blinding, rng: &mut dyn CryptoRngCore) -> Self {
+        match blinding {
+            Blinding::Random => BlindingFactors {
+                r: random_scalar(rng),
+                s: random_scalar(rng),
+            },
+            Blinding::Fixed => BlindingFactors {
+                r: F::one(),
+                s: F::one(),
+            },
+            Blinding::Explicit(factors) => factors.clone(),
+        }
+    }
+}
 
-    stream_g1.synchronize().unwrap();
-    stream_g2.synchronize().unwrap();
+/// Samples a uniform scalar by rejection.
+pub(crate) fn random_scalar(rng: &mut dyn CryptoRngCore) -> F {
+    // largest scalar, little-endian
+    let max = (F::zero() - F::one()).to_bytes_le();
+    let top = max.len() - 1;
+    let mask = u8::MAX >> max[top].leading_zeros();
+
+    let mut bytes = vec![0u8; max.len()];
+    loop {
+        rng.fill_bytes(&mut bytes);
+        bytes[top] &= mask;
+        if bytes.iter().rev().le(max.iter().rev()) {
+            break;
+        }
+    }
 
-    stream_g1.destroy().unwrap();
-    stream_g2.destroy().unwrap();
+    let scalar = F::from_bytes_le(&bytes);
+    bytes.zeroize();
+    scalar
+}
 
-    (pi_a[0], pi_b1[0], pi_b[0], pi_c[0], pi_h[0])
+fn finalize_proof(
+    zkey: &ZKey,
+    pi_a: ProjectiveG1,
+    pi_b1: ProjectiveG1,
+    pi_b: ProjectiveG2,
+    pi_c: ProjectiveG1,
+    pi_h: ProjectiveG1,
+    factors: &BlindingFactors,
+) -> Proof {
+    let (r, s) = (factors.r, factors.s);
+
+    let pi_a = pi_a + zkey.vk_alpha_1 + zkey.vk_delta_1 * r;
+    let pi_b = pi_b + zkey.vk_beta_2 + zkey.vk_delta_2 * s;
+    let pi_b1 = pi_b1 + zkey.vk_beta_1 + zkey.vk_delta_1 * s;
+    let pi_c = pi_c + pi_h + pi_a * s + pi_b1 * r - zkey.vk_delta_1 * r * s;
+
+    Proof {
+        pi_a: serialize_g1_affine(pi_a.into()),
+        pi_b: serialize_g2_affine(pi_b.into()),
+        pi_c: serialize_g1_affine(pi_c.into()),
+        protocol: "groth16".to_string(),
+        curve: "bn128".to_string(),
+    }
 }
 
 pub fn groth16_prove_helper(
     witness: &str,
     zkey_cache: &ZKeyCache,
+    config: &ProverConfig,
+    rng: &mut dyn CryptoRngCore,
 ) -> Result<(Value, Value), Box<dyn std::error::Error>> {
-    let (fd_wtns, sections_wtns) = FileWrapper::read_bin_file(witness, "wtns", 2).unwrap();
+    let prepared = prepare_witness(witness, zkey_cache)?;
 
-    let mut wtns_file = FileWrapper::new(fd_wtns).unwrap();
+    groth16_prove_prepared(&prepared, zkey_cache, config, rng)
+}
 
-    let wtns = wtns_file.read_wtns_header(&sections_wtns[..]).unwrap();
+/// Like `groth16_prove_helper`, for a witness computed in memory. `source` names the witness
+/// in errors.
+pub fn groth16_prove_computed_helper(
+    witness: Vec<F>,
+    source: &str,
+    zkey_cache: &ZKeyCache,
+    config: &ProverConfig,
+    rng: &mut dyn CryptoRngCore,
+) -> Result<(Value, Value), Box<dyn std::error::Error>> {
+    let prepared = prepare_computed_witness(witness, source, zkey_cache)?;
 
-    let zkey = &zkey_cache.zkey;
+    groth16_prove_prepared(&prepared, zkey_cache, config, rng)
+}
 
-    if !F::eq(&zkey.r, &wtns.q) {
-        panic!("Curve of the witness does not match the curve of the proving key");
+fn groth16_prove_prepared(
+    prepared: &PreparedWitness,
+    zkey_cache: &ZKeyCache,
+    config: &ProverConfig,
+    rng: &mut dyn CryptoRngCore,
+) -> Result<(Value, Value), Box<dyn std::error::Error>> {
+    let d_vec = construct_r1cs(prepared.scalars(), zkey_cache, config.quotient);
+
+    let (pi_a, pi_b1, pi_b, pi_c, pi_h) = groth16_commitments(d_vec, prepared.scalars(), zkey_cache, config.streams);
+
+    let factors = BlindingFactors::new(&config.blinding, rng);
+    let proof = finalize_proof(&zkey_cache.zkey, pi_a, pi_b1, pi_b, pi_c, pi_h, &factors);
+
+    if config.verify {
+        check_proof(&proof, &prepared.public_signals, &prepared.path, &zkey_cache.zkey.verification_key())?;
     }
 
-    if wtns.n_witness != zkey.n_vars {
-        panic!(
-            "Invalid witness length. Circuit: {}, witness: {}",
-            zkey.n_vars, wtns.n_witness
+    Ok((serde_json::json!(proof), serde_json::json!(prepared.public_signals)))
+}
+
+/// Returned instead of a proof that fails the verify-after-prove check, which points at a
+/// zkey/witness mismatch, a corrupted cache or a device fault rather than bad input files.
+#[derive(Debug)]
+pub struct InvalidProof {
+    /// Witness the proof was computed from.
+    pub witness: String,
+}
+
+impl fmt::Display for InvalidProof {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Proof of {} does not verify against the verification key of the zkey",
+            self.witness
+        )
+    }
+}
+
+impl std::error::Error for InvalidProof {}
+
+/// Fails with `InvalidProof` if `proof` of `witness` does not verify against `vk`.
+fn check_proof(
+    proof: &Proof,
+    public_signals: &[String],
+    witness: &str,
+    vk: &VerificationKey,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if !groth16_verify_helper(proof, public_signals, vk)? {
+        return Err(InvalidProof {
+            witness: witness.to_string(),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// Proves several witnesses of the same circuit.
+///
+/// Witness loading and gathering run on a separate host thread so that proof k+1 is
+/// prepared while the device works on proof k. Up to `MAX_PROOFS_PER_BATCH` proofs share
+/// one batched MSM per commitment, since all of them use the same bases. A cache whose MSMs
+/// are sharded over several devices proves the witnesses one at a time on the shards instead.
+pub fn groth16_prove_batch_helper(
+    witnesses: &[&str],
+    zkey_cache: &ZKeyCache,
+    config: &ProverConfig,
+    rng: &mut dyn CryptoRngCore,
+) -> Result<Vec<(Value, Value)>, Box<dyn std::error::Error>> {
+    // errors cross the thread as strings, since boxed errors are not `Send`
+    let (sender, receiver) = mpsc::sync_channel::<Result<PreparedWitness, String>>(1);
+
+    std::thread::scope(|scope| {
+        scope.spawn(move || {
+            for witness in witnesses {
+                let prepared = prepare_witness(witness, zkey_cache).map_err(|e| e.to_string());
+                let failed = prepared.is_err();
+                if sender.send(prepared).is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        // dropped on an early return, which stops the producer
+        let receiver = receiver;
+
+        let mut results = Vec::with_capacity(witnesses.len());
+
+        if !zkey_cache.shards.is_empty() {
+            for _ in witnesses {
+                let prepared = receiver.recv()??;
+                results.push(groth16_prove_prepared(&prepared, zkey_cache, config, rng)?);
+            }
+            return Ok(results);
+        }
+
+        let vk = config.verify.then(|| zkey_cache.zkey.verification_key());
+
+        for chunk in witnesses.chunks(MAX_PROOFS_PER_BATCH) {
+            results.extend(groth16_prove_chunk(&receiver, chunk.len(), zkey_cache, config, vk.as_ref(), rng)?);
+        }
+
+        Ok(results)
+    })
+}
+
+/// Buffers of one witness in flight. The witness is kept until its stream is synchronized,
+/// since the stream may still be copying from its mmap.
+struct WitnessSlot {
+    stream: IcicleStream,
+    d_vec: DeviceVec<F>,
+    witness: Option<PreparedWitness>,
+}
+
+fn groth16_prove_chunk(
+    receiver: &mpsc::Receiver<Result<PreparedWitness, String>>,
+    batch_size: usize,
+    zkey_cache: &ZKeyCache,
+    config: &ProverConfig,
+    vk: Option<&VerificationKey>,
+    rng: &mut dyn CryptoRngCore,
+) -> Result<Vec<(Value, Value)>, Box<dyn std::error::Error>> {
+    let zkey = &zkey_cache.zkey;
+    let nof_coef = zkey.domain_size;
+    let n_vars = zkey.n_vars;
+    let n_private = n_vars - zkey.n_public - 1;
+
+    let mut d_scalars = DeviceVec::device_malloc(n_vars * batch_size).unwrap();
+    let mut d_scalars_c = DeviceVec::device_malloc(n_private * batch_size).unwrap();
+    let mut d_scalars_h = DeviceVec::device_malloc(nof_coef * batch_size).unwrap();
+
+    // two slots, so that witness k + 1 is uploaded and evaluated while witness k still is
+    let mut slots: Vec<WitnessSlot> = (0..batch_size.min(2))
+        .map(|_| WitnessSlot {
+            stream: IcicleStream::create().unwrap(),
+            d_vec: DeviceVec::device_malloc(nof_coef * 3).unwrap(),
+            witness: None,
+        })
+        .collect();
+    let n_slots = slots.len();
+
+    let mut public_signals = Vec::with_capacity(batch_size);
+
+    for i in 0..batch_size {
+        let mut prepared = receiver.recv()??;
+        let slot = &mut slots[i % n_slots];
+
+        // the previous witness of the slot has to be done with its buffers
+        slot.stream.synchronize().unwrap();
+        slot.witness = None;
+
+        public_signals.push((prepared.path.clone(), std::mem::take(&mut prepared.public_signals)));
+
+        let scalars = prepared.scalars();
+        d_scalars[i * n_vars..(i + 1) * n_vars]
+            .copy_from_host_async(HostSlice::from_slice(scalars), &slot.stream)
+            .unwrap();
+        d_scalars_c[i * n_private..(i + 1) * n_private]
+            .copy_from_host_async(HostSlice::from_slice(&scalars[zkey.n_public + 1..]), &slot.stream)
+            .unwrap();
+
+        construct_r1cs_async(
+            scalars,
+            &d_scalars[i * n_vars..(i + 1) * n_vars],
+            zkey_cache,
+            config.quotient,
+            &mut slot.d_vec,
+            &slot.stream,
         );
+
+        d_scalars_h[i * nof_coef..(i + 1) * nof_coef]
+            .copy_async(&slot.d_vec[nof_coef..nof_coef * 2], &slot.stream)
+            .unwrap();
+
+        slot.witness = Some(prepared);
+    }
+
+    for mut slot in slots {
+        slot.stream.synchronize().unwrap();
+        slot.stream.destroy().unwrap();
     }
 
-    let buff_witness = wtns_file.read_section(&sections_wtns[..], 2).unwrap();
+    let streams = CommitmentStreams::create(config.streams);
+
+    let commitment_a = msm_bases_helper(&d_scalars[..], zkey_cache.bases(&zkey_cache.points_a), &zkey_cache.msm.a, batch_size, streams.g1(0));
+    let commitment_b1 = msm_bases_helper(&d_scalars[..], zkey_cache.bases(&zkey_cache.points_b1), &zkey_cache.msm.b1, batch_size, streams.g1(1));
+    let commitment_c = msm_bases_helper(&d_scalars_c[..], zkey_cache.bases(&zkey_cache.points_c), &zkey_cache.msm.c, batch_size, streams.g1(2));
+    let commitment_h = msm_bases_helper(&d_scalars_h[..], zkey_cache.bases(&zkey_cache.points_h), &zkey_cache.msm.h, batch_size, streams.g1(3));
+    let commitment_b = msm_bases_helper(&d_scalars[..], zkey_cache.bases(&zkey_cache.points_b), &zkey_cache.msm.b, batch_size, streams.g2());
+
+    let mut pi_a = vec![ProjectiveG1::zero(); commitment_a.len()];
+    let mut pi_b1 = vec![ProjectiveG1::zero(); commitment_b1.len()];
+    let mut pi_b = vec![ProjectiveG2::zero(); commitment_b.len()];
+    let mut pi_c = vec![ProjectiveG1::zero(); commitment_c.len()];
+    let mut pi_h = vec![ProjectiveG1::zero(); commitment_h.len()];
+
+    commitment_a
+        .copy_to_host_async(HostSlice::from_mut_slice(&mut pi_a[..]), streams.g1(0))
+        .unwrap();
+    commitment_b1
+        .copy_to_host_async(HostSlice::from_mut_slice(&mut pi_b1[..]), streams.g1(1))
+        .unwrap();
+    commitment_b
+        .copy_to_host_async(HostSlice::from_mut_slice(&mut pi_b[..]), streams.g2())
+        .unwrap();
+    commitment_c
+        .copy_to_host_async(HostSlice::from_mut_slice(&mut pi_c[..]), streams.g1(2))
+        .unwrap();
+    commitment_h
+        .copy_to_host_async(HostSlice::from_mut_slice(&mut pi_h[..]), streams.g1(3))
+        .unwrap();
 
-    let scalars = from_u8(buff_witness);
+    streams.synchronize();
 
-    let d_vec = construct_r1cs(scalars, zkey_cache);
+    let pi_a = sum_partials(&pi_a, batch_size);
+    let pi_b1 = sum_partials(&pi_b1, batch_size);
+    let pi_b = sum_partials(&pi_b, batch_size);
+    let pi_c = sum_partials(&pi_c, batch_size);
+    let pi_h = sum_partials(&pi_h, batch_size);
 
-    let (pi_a, pi_b1, pi_b, pi_c, pi_h) = groth16_commitments(d_vec, scalars, zkey_cache);
+    streams.destroy();
 
-    #[cfg(not(feature = "no-randomness"))]
-    let (pi_a, pi_b, pi_c) = {
-        let rs = ScalarCfg::generate_random(2);
-        let r = rs[0];
-        let s = rs[1];
+    let mut results = Vec::with_capacity(batch_size);
+    for (i, (witness, public_signals)) in public_signals.into_iter().enumerate() {
+        let factors = BlindingFactors::new(&config.blinding, rng);
+        let proof = finalize_proof(zkey, pi_a[i], pi_b1[i], pi_b[i], pi_c[i], pi_h[i], &factors);
+        if let Some(vk) = vk {
+            check_proof(&proof, &public_signals, &witness, vk)?;
+        }
+        results.push((serde_json::json!(proof), serde_json::json!(public_signals)));
+    }
 
-        let pi_a = pi_a + zkey.vk_alpha_1 + zkey.vk_delta_1 * r;
-        let pi_b = pi_b + zkey.vk_beta_2 + zkey.vk_delta_2 * s;
-        let pi_b1 = pi_b1 + zkey.vk_beta_1 + zkey.vk_delta_1 * s;
-        let pi_c = pi_c + pi_h + pi_a * s + pi_b1 * r - zkey.vk_delta_1 * r * s;
+    Ok(results)
+}
 
-        (pi_a, pi_b, pi_c)
+/// Re-randomizes `proof` for the same public inputs, without the witness.
+///
+/// For fresh `t != 0` and `u`, `(A / t, t * B + t * u * delta_2, C + u * A)` satisfies the
+/// same verification equation and is distributed like a freshly blinded proof, so copies
+/// shown to different verifiers cannot be linked to each other.
+pub fn rerandomize_helper(proof: &Proof, vk: &VerificationKey, rng: &mut dyn CryptoRngCore) -> Proof {
+    let pi_a = deserialize_g1_affine(&proof.pi_a).to_projective();
+    let pi_b = deserialize_g2_affine(&proof.pi_b).to_projective();
+    let pi_c = deserialize_g1_affine(&proof.pi_c).to_projective();
+
+    // (t, u), then (1 / t, t * u)
+    let mut factors = BlindingFactors {
+        r: random_scalar(rng),
+        s: random_scalar(rng),
     };
-    #[cfg(feature = "no-randomness")]
-    let (pi_a, pi_b, pi_c) = {
-        let pi_a = pi_a + zkey.vk_alpha_1 + zkey.vk_delta_1;
-        let pi_b = pi_b + zkey.vk_beta_2 + zkey.vk_delta_2;
-        let pi_b1 = pi_b1 + zkey.vk_beta_1 + zkey.vk_delta_1;
-        let pi_c = pi_c + pi_h + pi_a + pi_b1 - zkey.vk_delta_1;
-
-        (pi_a, pi_b, pi_c)
+    while factors.r == F::zero() {
+        factors.r = random_scalar(rng);
+    }
+    let derived = BlindingFactors {
+        r: factors.r.inv(),
+        s: factors.r * factors.s,
     };
 
-    let mut public_signals = Vec::with_capacity(zkey.n_public);
-    let field_size = ScalarField::zero().to_bytes_le().len();
-
-    for i in 1..=zkey.n_public {
-        let start = i * field_size;
-        let end = start + field_size;
-        let b = &buff_witness[start..end];
-        let scalar_bytes: BigUint = BigUint::from_bytes_le(b);
-        public_signals.push(scalar_bytes.to_str_radix(10));
-    }
+    let pi_a_new = pi_a * derived.r;
+    let pi_b = pi_b * factors.r + vk.vk_delta_2.to_projective() * derived.s;
+    let pi_c = pi_c + pi_a * factors.s;
 
-    let proof = Proof {
-        pi_a: serialize_g1_affine(pi_a.into()),
+    Proof {
+        pi_a: serialize_g1_affine(pi_a_new.into()),
         pi_b: serialize_g2_affine(pi_b.into()),
         pi_c: serialize_g1_affine(pi_c.into()),
-        protocol: "groth16".to_string(),
-        curve: "bn128".to_string(),
-    };
+        protocol: proof.protocol.clone(),
+        curve: proof.curve.clone(),
+    }
+}
 
-    Ok((serde_json::json!(proof), serde_json::json!(public_signals)))
+/// Parses the decimal public signals of a `public.json`.
+pub fn parse_public_signals(public: &[String]) -> Vec<F> {
+    public
+        .iter()
+        .map(|s| {
+            let hex = BigUint::parse_bytes(s.as_bytes(), 10).unwrap();
+            ScalarField::from_bytes_le(&hex.to_bytes_le())
+        })
+        .collect()
 }
 
 pub fn groth16_verify_helper(
@@ -328,12 +939,7 @@ pub fn groth16_verify_helper(
     let n_public = verification_key.n_public;
     let ic = verification_key.ic.clone();
 
-    let mut public_scalars = Vec::with_capacity(n_public);
-    for s in public.iter().take(n_public) {
-        let hex = BigUint::parse_bytes(s.as_bytes(), 10).unwrap();
-        let scalar = ScalarField::from_bytes_le(&hex.to_bytes_le());
-        public_scalars.push(scalar);
-    }
+    let public_scalars = parse_public_signals(&public[..n_public.min(public.len())]);
 
     let mut cpub = ic[0].to_projective();
     for i in 0..public_scalars.len() {
@@ -369,4 +975,238 @@ pub fn groth16_verify_helper(
     let result = Field::one() == first * second * third * fourth;
 
     Ok(result)
-}
\ No newline at end of file
+}
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{CacheManager, MsmSplit, C1, C2};
+    use icicle_core::curve::Curve;
+    use icicle_runtime::Device;
+    use rand_core::OsRng;
+
+    /// Path of a file in the `fixtures` directory.
+    pub(crate) fn fixture(path: &str) -> String {
+        format!("{}/fixtures/{}", env!("CARGO_MANIFEST_DIR"), path)
+    }
+
+    /// Proves on the CPU, without writing precomputed keys to the working directory.
+    pub(crate) fn cpu_config() -> ProverConfig {
+        ProverConfig {
+            device: Device::new("CPU", 0),
+            quotient: QuotientStrategy::CosetNtt,
+            ..Default::default()
+        }
+    }
+
+    /// The cache of the fixture `zkey` in `manager`, computed on the CPU on first use.
+    pub(crate) fn fixture_cache<'a>(manager: &'a mut CacheManager, zkey: &str) -> &'a mut ZKeyCache {
+        icicle_runtime::set_device(&Device::new("CPU", 0)).unwrap();
+        if !manager.contains(zkey) {
+            let cache = manager.compute(&fixture(zkey)).unwrap();
+            manager.insert_cache(zkey, cache);
+        }
+        let cache = manager.get_cache(zkey);
+        cache.prepare(&cpu_config()).unwrap();
+        cache
+    }
+
+    /// Asserts that every proof in `proofs` verifies against `vk`.
+    pub(crate) fn assert_proofs_verify(proofs: &[(Value, Value)], vk: &VerificationKey) {
+        for (proof, public) in proofs {
+            let proof: Proof = serde_json::from_value(proof.clone()).unwrap();
+            let public: Vec<String> = serde_json::from_value(public.clone()).unwrap();
+            assert!(groth16_verify_helper(&proof, &public, vk).unwrap());
+        }
+    }
+
+    /// A verification key with known discrete logs and a valid proof for each of `publics`,
+    /// built without a circuit:
+    /// `a * b = alpha * beta + (ic_0 + sum(x_i * ic_i)) * gamma + c * delta`.
+    pub(crate) fn toy_proofs(publics: &[&[F]]) -> (Vec<Proof>, VerificationKey) {
+        let g1 = C1::get_generator();
+        let g2 = C2::get_generator();
+        let mut rng = OsRng;
+        let n_public = publics[0].len();
+        let [alpha, beta, gamma, delta] = [(); 4].map(|_| random_scalar(&mut rng));
+        let ic: Vec<F> = (0..=n_public).map(|_| random_scalar(&mut rng)).collect();
+
+        let vk = VerificationKey {
+            vk_alpha_1: (g1 * alpha).into(),
+            vk_beta_2: (g2 * beta).into(),
+            vk_gamma_2: (g2 * gamma).into(),
+            vk_delta_2: (g2 * delta).into(),
+            ic: ic.iter().map(|&ic| (g1 * ic).into()).collect(),
+            n_public,
+        };
+
+        let proofs = publics
+            .iter()
+            .map(|public| {
+                let [a, b] = [(); 2].map(|_| random_scalar(&mut rng));
+                let cpub = public
+                    .iter()
+                    .zip(&ic[1..])
+                    .fold(ic[0], |sum, (&x, &ic)| sum + x * ic);
+                let c = (a * b - alpha * beta - cpub * gamma) * delta.inv();
+
+                Proof {
+                    pi_a: serialize_g1_affine((g1 * a).into()),
+                    pi_b: serialize_g2_affine((g2 * b).into()),
+                    pi_c: serialize_g1_affine((g1 * c).into()),
+                    protocol: "groth16".to_string(),
+                    curve: "bn128".to_string(),
+                }
+            })
+            .collect();
+
+        (proofs, vk)
+    }
+
+    fn toy_proof(public: &[F]) -> (Proof, VerificationKey) {
+        let (mut proofs, vk) = toy_proofs(&[public]);
+        (proofs.remove(0), vk)
+    }
+
+    pub(crate) fn signals(public: &[F]) -> Vec<String> {
+        public
+            .iter()
+            .map(|x| BigUint::from_bytes_le(&x.to_bytes_le()).to_str_radix(10))
+            .collect()
+    }
+
+    #[test]
+    fn test_rerandomized_proof_verifies() {
+        let public = [F::from_u32(3), F::from_u32(11)];
+        let (proof, vk) = toy_proof(&public);
+        assert!(groth16_verify_helper(&proof, &signals(&public), &vk).unwrap());
+
+        let rerandomized = rerandomize_helper(&proof, &vk, &mut OsRng);
+
+        assert!(groth16_verify_helper(&rerandomized, &signals(&public), &vk).unwrap());
+        assert_ne!(rerandomized.pi_a, proof.pi_a);
+        assert_ne!(rerandomized.pi_b, proof.pi_b);
+        assert_ne!(rerandomized.pi_c, proof.pi_c);
+    }
+
+    #[test]
+    fn test_rerandomized_proofs_are_unlinked() {
+        let public = [F::from_u32(5)];
+        let (proof, vk) = toy_proof(&public);
+
+        let first = rerandomize_helper(&proof, &vk, &mut OsRng);
+        let second = rerandomize_helper(&first, &vk, &mut OsRng);
+
+        assert!(groth16_verify_helper(&second, &signals(&public), &vk).unwrap());
+        assert_ne!(first.pi_a, second.pi_a);
+        assert_ne!(first.pi_c, second.pi_c);
+    }
+
+    #[test]
+    fn test_rerandomized_proof_keeps_statement() {
+        let public = [F::from_u32(7)];
+        let (proof, vk) = toy_proof(&public);
+
+        let rerandomized = rerandomize_helper(&proof, &vk, &mut OsRng);
+
+        assert!(!groth16_verify_helper(&rerandomized, &signals(&[F::from_u32(8)]), &vk).unwrap());
+    }
+
+    #[test]
+    fn test_batched_proofs_verify() {
+        let mut manager = CacheManager::default();
+        let zkey_cache = fixture_cache(&mut manager, "groth16/small/circuit_final.zkey");
+        let witnesses: Vec<String> = (1..=3)
+            .map(|i| fixture(&format!("groth16/small/witness_{}.wtns", i)))
+            .collect();
+        let witnesses: Vec<&str> = witnesses.iter().map(String::as_str).collect();
+
+        let proofs = groth16_prove_batch_helper(&witnesses, zkey_cache, &cpu_config(), &mut OsRng).unwrap();
+
+        assert_eq!(proofs.len(), witnesses.len());
+        assert_proofs_verify(&proofs, &zkey_cache.zkey.verification_key());
+    }
+
+    #[test]
+    fn test_batch_reports_unreadable_witness() {
+        let mut manager = CacheManager::default();
+        let zkey_cache = fixture_cache(&mut manager, "groth16/small/circuit_final.zkey");
+        let witness = fixture("groth16/small/witness_1.wtns");
+        let missing = fixture("groth16/small/missing.wtns");
+
+        let result = groth16_prove_batch_helper(&[&witness, &missing], zkey_cache, &cpu_config(), &mut OsRng);
+
+        assert!(result.unwrap_err().to_string().contains("missing.wtns"));
+    }
+
+    #[test]
+    fn test_mismatched_witness_is_invalid_proof() {
+        // a private signal that no longer satisfies its constraint
+        let mut signals = crate::witness::read_wtns_helper(&fixture("groth16/small/witness_1.wtns")).unwrap();
+        let last = signals.len() - 1;
+        signals[last] = signals[last] + F::one();
+
+        let dir = std::env::temp_dir();
+        let [witness, proof, public] = ["bad.wtns", "bad_proof.json", "bad_public.json"]
+            .map(|name| dir.join(format!("icicle_snark_{}", name)).to_str().unwrap().to_string());
+        crate::witness::write_wtns_helper(&signals, &witness, None).unwrap();
+        let config = ProverConfig {
+            verify: true,
+            ..cpu_config()
+        };
+
+        let mut manager = CacheManager::default();
+        let zkey = fixture("groth16/small/circuit_final.zkey");
+        let error =
+            crate::groth16_prove_with_config(&witness, &zkey, &proof, &public, &config, &mut manager).unwrap_err();
+        std::fs::remove_file(&witness).unwrap();
+
+        let invalid = error.downcast_ref::<InvalidProof>().unwrap();
+        assert_eq!(invalid.witness, witness);
+        assert!(!std::path::Path::new(&proof).exists());
+        assert!(!std::path::Path::new(&public).exists());
+    }
+
+    #[test]
+    fn test_explicit_blinding_matches_fixed() {
+        let mut manager = CacheManager::default();
+        let zkey_cache = fixture_cache(&mut manager, "groth16/small/circuit_final.zkey");
+        let witness = fixture("groth16/small/witness_1.wtns");
+        let prove = |blinding| {
+            let config = ProverConfig { blinding, verify: true, ..cpu_config() };
+            groth16_prove_helper(&witness, zkey_cache, &config, &mut OsRng).unwrap().0
+        };
+
+        let explicit = prove(Blinding::Explicit(BlindingFactors { r: F::one(), s: F::one() }));
+
+        assert_eq!(explicit, prove(Blinding::Fixed));
+        assert_ne!(explicit, prove(Blinding::Random));
+    }
+
+    /// The commitments of a fixture witness of the mid circuit.
+    fn mid_commitments(zkey_cache: &ZKeyCache) -> (ProjectiveG1, ProjectiveG1, ProjectiveG2, ProjectiveG1, ProjectiveG1) {
+        let witness = prepare_witness(&fixture("groth16/mid/witness_1.wtns"), zkey_cache).unwrap();
+        let d_vec = construct_r1cs(witness.scalars(), zkey_cache, QuotientStrategy::CosetNtt);
+
+        groth16_commitments(d_vec, witness.scalars(), zkey_cache, 2)
+    }
+
+    #[test]
+    fn test_sharded_commitments_match_single_device() {
+        let zkey = "groth16/mid/circuit_final.zkey";
+        let mut manager = CacheManager::default();
+        let expected = mid_commitments(fixture_cache(&mut manager, zkey));
+
+        let cpu = Device::new("CPU", 0);
+        for split in [MsmSplit::Partition, MsmSplit::PerCommitment] {
+            let mut manager = CacheManager::default().with_msm_devices(vec![cpu.clone(), cpu.clone()], split);
+            let zkey_cache = fixture_cache(&mut manager, zkey);
+
+            assert_eq!(zkey_cache.shards.len(), 2);
+            assert_eq!(mid_commitments(zkey_cache), expected);
+
+            let witness = fixture("groth16/mid/witness_2.wtns");
+            let proofs = groth16_prove_batch_helper(&[&witness], zkey_cache, &cpu_config(), &mut OsRng).unwrap();
+            assert_proofs_verify(&proofs, &zkey_cache.zkey.verification_key());
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use std::io;
+
+use crate::conversions::{from_affine_mont, from_u8};
+use crate::file_wrapper::FileWrapper;
+use crate::{C1, C2, G1, G2};
+
+/// Leading powers of tau of a snarkjs `.ptau` file.
+#[derive(Clone, Debug)]
+pub struct PowersOfTau {
+    /// The file holds `2^power` G2 and `2^(power + 1) - 1` G1 powers.
+    pub power: usize,
+    /// `tau^i * g1`
+    pub tau_g1: Vec<G1>,
+    /// `tau^i * g2`
+    pub tau_g2: Vec<G2>,
+}
+
+impl PowersOfTau {
+    /// Reads the first `n_g1` G1 and `n_g2` G2 powers of the `.ptau` file at `path`.
+    pub fn read(path: &str, n_g1: usize, n_g2: usize) -> io::Result<Self> {
+        let (file, sections) = FileWrapper::read_bin_file(path, "ptau", 1)?;
+        let mut fd = FileWrapper::new(file)?;
+
+        fd.start_read_unique_section(&sections, 1)?;
+        let n8 = fd.read_u32_le()? as usize;
+        fd.read_big_int(n8, None)?;
+        let power = fd.read_u32_le()? as usize;
+        fd.end_read_section(true)?;
+
+        if n8 != 32 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: not a bn254 ptau", path),
+            ));
+        }
+
+        if n_g1 > (2 << power) - 1 || n_g2 > 1 << power {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "{}: {} G1 and {} G2 powers requested, the file holds 2^{}",
+                    path, n_g1, n_g2, power
+                ),
+            ));
+        }
+
+        let mut tau_g1 = from_u8::<G1>(fd.read_section(&sections, 2)?)[..n_g1].to_vec();
+        let mut tau_g2 = from_u8::<G2>(fd.read_section(&sections, 3)?)[..n_g2].to_vec();
+        from_affine_mont::<C1>(&mut tau_g1);
+        from_affine_mont::<C2>(&mut tau_g2);
+
+        Ok(Self {
+            power,
+            tau_g1,
+            tau_g2,
+        })
+    }
+}
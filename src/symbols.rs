@@ -0,0 +1,74 @@
+//! circom's `.sym` files, which name the signals of a circuit: one `label,witness,component,name`
+//! line per signal, with witness index -1 for the signals the optimizer removed.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::Path;
+
+#[derive(Clone, Debug, Default)]
+pub struct Symbols {
+    names: HashMap<usize, String>,
+}
+
+impl Symbols {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Several names can share a witness index once the optimizer merges equal signals; the
+    /// first one listed, the outermost, is kept.
+    pub fn parse(sym: &str) -> Result<Self, Box<dyn Error>> {
+        let mut names = HashMap::new();
+
+        for (i, line) in sym.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.splitn(4, ',').collect();
+            let [_, witness, _, name] = fields[..] else {
+                return Err(format!("Invalid symbol on line {}: {}", i + 1, line).into());
+            };
+            let witness: i64 = witness
+                .parse()
+                .map_err(|_| format!("Invalid witness index on line {}: {}", i + 1, line))?;
+
+            if witness >= 0 {
+                names.entry(witness as usize).or_insert_with(|| name.to_string());
+            }
+        }
+
+        Ok(Self { names })
+    }
+
+    pub fn name(&self, index: usize) -> Option<&str> {
+        self.names.get(&index).map(String::as_str)
+    }
+
+    /// The name of the signal at witness `index`, or `signal <index>` for unnamed ones like the
+    /// constant 1.
+    pub fn label(&self, index: usize) -> String {
+        match self.name(index) {
+            Some(name) => name.to_string(),
+            None => format!("signal {}", index),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_symbols() {
+        let sym = "1,1,1,main.out\n2,2,1,main.a\n3,-1,0,main.hasher.in[0]\n4,2,0,main.hasher.out[3]\n";
+        let symbols = Symbols::parse(sym).unwrap();
+
+        assert_eq!(symbols.name(1), Some("main.out"));
+        assert_eq!(symbols.name(2), Some("main.a"));
+        assert_eq!(symbols.label(0), "signal 0");
+        assert!(Symbols::parse("1,x,0,main.a").is_err());
+        assert!(Symbols::parse("1,1").is_err());
+    }
+}
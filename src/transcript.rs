@@ -0,0 +1,147 @@
+//! Fiat-Shamir transcripts in snarkjs' layout, hashed with Keccak-256 or Poseidon.
+//!
+//! As in snarkjs, a challenge only covers what was added since the previous one; protocols
+//! chain challenges by adding the previous challenge back as a scalar.
+
+use icicle_core::traits::FieldImpl;
+use num_bigint::BigUint;
+use sha3::{Digest, Keccak256};
+
+use crate::{poseidon::poseidon, F, G1};
+
+/// The hash a `Transcript` squeezes its challenges with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TranscriptHash {
+    /// snarkjs' transcript, as the Solidity verifiers it exports recompute it. Points are
+    /// hashed as their uncompressed big-endian coordinates and scalars as 32 big-endian bytes,
+    /// and the digest is reduced modulo r.
+    #[default]
+    Keccak256,
+    /// circomlib's Poseidon, for transcripts recomputed inside a circuit. Scalars are absorbed
+    /// as they are, and each point coordinate as its low and high 128 bits, the point at
+    /// infinity as zero coordinates. A challenge hashes up to 16 elements at once, and chains
+    /// longer inputs as `poseidon([previous, next 15 elements])`.
+    Poseidon,
+}
+
+pub struct Transcript {
+    hash: TranscriptHash,
+    bytes: Vec<u8>,
+    elements: Vec<F>,
+}
+
+impl Transcript {
+    pub fn new(hash: TranscriptHash) -> Self {
+        Self {
+            hash,
+            bytes: Vec::new(),
+            elements: Vec::new(),
+        }
+    }
+
+    pub fn add_scalar(&mut self, scalar: &F) {
+        match self.hash {
+            TranscriptHash::Keccak256 => {
+                let mut bytes = scalar.to_bytes_le();
+                bytes.reverse();
+                self.bytes.extend_from_slice(&bytes);
+            }
+            TranscriptHash::Poseidon => self.elements.push(*scalar),
+        }
+    }
+
+    pub fn add_point(&mut self, point: &G1) {
+        match self.hash {
+            TranscriptHash::Keccak256 => {
+                if *point == G1::zero() {
+                    let mut bytes = [0u8; 64];
+                    bytes[0] = 0x40;
+                    self.bytes.extend_from_slice(&bytes);
+                    return;
+                }
+                for coordinate in [point.x.to_bytes_le(), point.y.to_bytes_le()] {
+                    self.bytes.extend(coordinate.iter().rev());
+                }
+            }
+            TranscriptHash::Poseidon => {
+                for coordinate in [point.x.to_bytes_le(), point.y.to_bytes_le()] {
+                    let (low, high) = coordinate.split_at(16);
+                    self.elements.push(F::from_bytes_le(low));
+                    self.elements.push(F::from_bytes_le(high));
+                }
+            }
+        }
+    }
+
+    /// Hashes everything added since the previous challenge into a scalar.
+    pub fn challenge(&mut self) -> F {
+        match self.hash {
+            TranscriptHash::Keccak256 => {
+                let modulus = BigUint::from_bytes_le(&(F::zero() - F::one()).to_bytes_le()) + 1u32;
+                let digest = Keccak256::digest(&self.bytes);
+                self.bytes.clear();
+                let challenge = BigUint::from_bytes_be(&digest) % modulus;
+                F::from_bytes_le(&challenge.to_bytes_le())
+            }
+            TranscriptHash::Poseidon => {
+                let elements = std::mem::take(&mut self.elements);
+                if elements.is_empty() {
+                    return poseidon(&[F::zero()]);
+                }
+
+                let first = elements.len().min(16);
+                let mut challenge = poseidon(&elements[..first]);
+                for chunk in elements[first..].chunks(15) {
+                    let mut inputs = Vec::with_capacity(16);
+                    inputs.push(challenge);
+                    inputs.extend_from_slice(chunk);
+                    challenge = poseidon(&inputs);
+                }
+                challenge
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::C1;
+    use icicle_core::curve::Curve;
+
+    #[test]
+    fn test_poseidon_transcript_chains_long_inputs() {
+        let elements: Vec<F> = (1..=20).map(F::from_u32).collect();
+        let mut transcript = Transcript::new(TranscriptHash::Poseidon);
+        for element in &elements {
+            transcript.add_scalar(element);
+        }
+
+        let first = poseidon(&elements[..16]);
+        let mut inputs = vec![first];
+        inputs.extend_from_slice(&elements[16..]);
+
+        assert_eq!(transcript.challenge(), poseidon(&inputs));
+        // a challenge only covers what was added since the previous one
+        assert_eq!(transcript.challenge(), poseidon(&[F::zero()]));
+    }
+
+    #[test]
+    fn test_point_layouts() {
+        let generator: G1 = C1::get_generator().into();
+
+        let mut keccak = Transcript::new(TranscriptHash::Keccak256);
+        keccak.add_point(&generator);
+        let mut expected = [0u8; 64];
+        expected[31] = 1;
+        expected[63] = 2;
+        assert_eq!(keccak.bytes, expected);
+
+        let mut poseidon = Transcript::new(TranscriptHash::Poseidon);
+        poseidon.add_point(&generator);
+        assert_eq!(
+            poseidon.elements,
+            [F::one(), F::zero(), F::from_u32(2), F::zero()]
+        );
+    }
+}
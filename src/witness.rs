@@ -0,0 +1,194 @@
+//! Witnesses in `.wtns` files and in the JSON of `snarkjs wtns export json`, an array with one
+//! decimal string per signal, the constant 1 first.
+
+use icicle_core::traits::FieldImpl;
+use num_bigint::BigInt;
+use serde_json::Value;
+use std::error::Error;
+
+use crate::{
+    conversions::from_u8,
+    file_wrapper::{FileWrapper, FFLONK_PROTOCOL_ID, GROTH16_PROTOCOL_ID, PLONK_PROTOCOL_ID},
+    plonk_helper::scalar_to_string,
+    symbols::Symbols,
+    witness_calculator::{modulus, parse_integer},
+    F,
+};
+
+/// Bytes per witness value.
+const N8: usize = 32;
+
+fn modulus_bytes() -> Vec<u8> {
+    let mut bytes = modulus().to_bytes_le();
+    bytes.resize(N8, 0);
+    bytes
+}
+
+pub fn read_wtns_helper(wtns: &str) -> Result<Vec<F>, Box<dyn Error>> {
+    let (fd_wtns, sections_wtns) = FileWrapper::read_bin_file(wtns, "wtns", 2)?;
+    let mut wtns_file = FileWrapper::new(fd_wtns)?;
+    let header = wtns_file.read_wtns_header(&sections_wtns)?;
+
+    if header.n8 != N8 || !F::eq(&header.q, &F::from_bytes_le(&modulus_bytes())) {
+        return Err(format!("{} is not a witness over the BN254 scalar field", wtns).into());
+    }
+
+    let values = wtns_file.read_section(&sections_wtns, 2)?;
+    if values.len() != header.n_witness * N8 {
+        return Err(format!(
+            "{} holds {} bytes of values for {} signals",
+            wtns,
+            values.len(),
+            header.n_witness
+        )
+        .into());
+    }
+
+    Ok(from_u8::<F>(values).to_vec())
+}
+
+pub fn write_wtns_helper(witness: &[F], wtns: &str, zkey: Option<&str>) -> Result<(), Box<dyn Error>> {
+    if let Some(zkey) = zkey {
+        check_witness(witness, zkey)?;
+    }
+
+    FileWrapper::save_wtns_file(wtns, &modulus_bytes(), witness)?;
+    Ok(())
+}
+
+/// Checks that `zkey` takes witnesses of `witness.len()` values of `N8` bytes modulo r, and that
+/// the witness starts with the constant signal.
+fn check_witness(witness: &[F], zkey: &str) -> Result<(), Box<dyn Error>> {
+    let (fd_zkey, sections_zkey) = FileWrapper::read_bin_file(zkey, "zkey", 2)?;
+    let mut zkey_file = FileWrapper::new(fd_zkey)?;
+
+    // PLONK and FFLONK zkeys count the internal signals of their additions as variables
+    let (n8r, r, n_signals) = match zkey_file.read_protocol_id(&sections_zkey)? {
+        GROTH16_PROTOCOL_ID => {
+            let header = zkey_file.read_zkey_header(&sections_zkey)?;
+            (header.n8r, header.r, header.n_vars)
+        }
+        PLONK_PROTOCOL_ID => {
+            let header = zkey_file.read_plonk_zkey_header(&sections_zkey)?;
+            (header.n8r, header.r, header.n_vars - header.n_additions)
+        }
+        FFLONK_PROTOCOL_ID => {
+            let header = zkey_file.read_fflonk_zkey_header(&sections_zkey)?;
+            (header.n8r, header.r, header.n_vars - header.n_additions)
+        }
+        protocol_id => return Err(format!("Unsupported zkey protocol {}", protocol_id).into()),
+    };
+
+    if n8r != N8 {
+        return Err(format!("The zkey takes {}-byte scalars, not {}-byte ones", n8r, N8).into());
+    }
+    if !F::eq(&r, &F::from_bytes_le(&modulus_bytes())) {
+        return Err("Curve of the witness does not match the curve of the proving key".into());
+    }
+    if witness.len() != n_signals {
+        return Err(format!(
+            "Invalid witness length. Circuit: {}, witness: {}",
+            n_signals,
+            witness.len()
+        )
+        .into());
+    }
+    if witness[0] != F::one() {
+        return Err("The first witness signal must be the constant 1".into());
+    }
+
+    Ok(())
+}
+
+pub fn wtns_to_json_helper(witness: &[F]) -> Value {
+    Value::from(witness.iter().map(scalar_to_string).collect::<Vec<_>>())
+}
+
+/// Reads a JSON array of values: numbers, decimal or `0x` strings. Negative values are taken
+/// modulo r, but values of r or more in magnitude are rejected rather than reduced.
+pub fn json_to_wtns_helper(json: &Value) -> Result<Vec<F>, Box<dyn Error>> {
+    let values = json
+        .as_array()
+        .ok_or("A witness JSON must be an array of values")?;
+    let modulus = BigInt::from(modulus());
+
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| {
+            let integer =
+                parse_integer(value).ok_or_else(|| format!("Invalid value {} for witness signal {}", value, i))?;
+            if integer >= modulus || integer <= -&modulus {
+                return Err(format!("Witness signal {} is out of the scalar field", i).into());
+            }
+
+            let (_, bytes) = ((integer + &modulus) % &modulus).to_bytes_le();
+            Ok(F::from_bytes_le(&bytes))
+        })
+        .collect()
+}
+
+/// One `name = value` line per signal, unnamed signals labelled by their witness index.
+pub fn describe_witness_helper(witness: &[F], symbols: Option<&Symbols>) -> Vec<String> {
+    witness
+        .iter()
+        .enumerate()
+        .map(|(i, value)| {
+            let label = match symbols {
+                Some(symbols) => symbols.label(i),
+                None => format!("signal {}", i),
+            };
+            format!("{} = {}", label, scalar_to_string(value))
+        })
+        .collect()
+}
+
+/// Public signal `i` is the signal at witness index `i + 1`, after the constant 1.
+pub fn describe_public_helper(public: &[String], symbols: &Symbols) -> Vec<String> {
+    public
+        .iter()
+        .enumerate()
+        .map(|(i, value)| format!("{} = {}", symbols.label(i + 1), value))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_wtns_round_trip() {
+        let witness = [F::one(), F::from_u32(42), F::zero() - F::one()];
+        let path = std::env::temp_dir().join(format!("icicle_snark_{}.wtns", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        write_wtns_helper(&witness, path, None).unwrap();
+        let read = read_wtns_helper(path);
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(read.unwrap(), witness);
+    }
+
+    #[test]
+    fn test_json_conversion() {
+        let json = json!(["1", 42, "0x10", "-1"]);
+        let witness = json_to_wtns_helper(&json).unwrap();
+
+        assert_eq!(witness, [F::one(), F::from_u32(42), F::from_u32(16), F::zero() - F::one()]);
+        assert_eq!(
+            wtns_to_json_helper(&witness[..3]),
+            json!(["1", "42", "16"])
+        );
+
+        let symbols = Symbols::parse("1,1,0,main.out").unwrap();
+        assert_eq!(
+            describe_witness_helper(&witness[..2], Some(&symbols)),
+            ["signal 0 = 1", "main.out = 42"]
+        );
+
+        let r = modulus().to_str_radix(10);
+        assert!(json_to_wtns_helper(&json!(["1", r])).is_err());
+        assert!(json_to_wtns_helper(&json!({ "a": 1 })).is_err());
+    }
+}
@@ -0,0 +1,441 @@
+//! Witness computation with the WebAssembly calculator `circom --wasm` emits, in place of
+//! `snarkjs wtns calculate`.
+//!
+//! The calculator is compiled by an embedded wasmtime runtime, with the host functions and the
+//! shared memory interface of snarkjs' `witness_calculator.js`: field elements cross the
+//! boundary as `getFieldNumLen32()` little-endian 32-bit words, and input signals are addressed
+//! by the 64-bit FNV-1a hash of their name. Only circom 2 calculators are supported.
+
+use icicle_core::traits::FieldImpl;
+use num_bigint::{BigInt, BigUint};
+use serde_json::Value;
+use std::error::Error;
+use wasmtime::{Caller, Config, Engine, Linker, Module, Store, TypedFunc, WasmParams, WasmResults};
+
+use crate::F;
+
+/// Messages assembled by the host functions.
+#[derive(Default)]
+struct Runtime {
+    /// Error messages printed before an exception, reported with it.
+    errors: String,
+    /// The line circom `log` calls are building.
+    line: String,
+}
+
+fn export<Params: WasmParams, Results: WasmResults>(
+    caller: &mut Caller<'_, Runtime>,
+    name: &str,
+) -> wasmtime::Result<TypedFunc<Params, Results>> {
+    caller
+        .get_export(name)
+        .and_then(|export| export.into_func())
+        .ok_or_else(|| wasmtime::Error::msg(format!("The witness calculator does not export {}", name)))?
+        .typed(&*caller)
+}
+
+fn message(caller: &mut Caller<'_, Runtime>) -> wasmtime::Result<String> {
+    let get_message_char = export::<(), i32>(caller, "getMessageChar")?;
+    let mut message = String::new();
+    loop {
+        let c = get_message_char.call(&mut *caller, ())?;
+        if c == 0 {
+            return Ok(message);
+        }
+        message.push(char::from_u32(c as u32).unwrap_or(char::REPLACEMENT_CHARACTER));
+    }
+}
+
+fn push_to_line(runtime: &mut Runtime, message: &str) {
+    if !runtime.line.is_empty() {
+        runtime.line.push(' ');
+    }
+    runtime.line.push_str(message);
+}
+
+fn exception_handler(mut caller: Caller<'_, Runtime>, code: i32) -> wasmtime::Result<()> {
+    let exception = match code {
+        1 => "Signal not found.",
+        2 => "Too many signals set.",
+        3 => "Signal already set.",
+        4 => "Assert Failed.",
+        5 => "Not enough memory.",
+        6 => "Input signal array access exceeds the size.",
+        _ => "Unknown error.",
+    };
+    let errors = std::mem::take(&mut caller.data_mut().errors);
+
+    Err(wasmtime::Error::msg(format!("{}\n{}", exception, errors).trim_end().to_string()))
+}
+
+fn print_error_message(mut caller: Caller<'_, Runtime>) -> wasmtime::Result<()> {
+    let message = message(&mut caller)?;
+    let errors = &mut caller.data_mut().errors;
+    errors.push_str(&message);
+    errors.push('\n');
+    Ok(())
+}
+
+/// Prints the circuit's `log()` output to stderr, since the CLI worker answers on stdout.
+fn write_buffer_message(mut caller: Caller<'_, Runtime>) -> wasmtime::Result<()> {
+    let message = message(&mut caller)?;
+    if message == "\n" {
+        eprintln!("{}", std::mem::take(&mut caller.data_mut().line));
+    } else {
+        push_to_line(caller.data_mut(), &message);
+    }
+    Ok(())
+}
+
+fn show_shared_rw_memory(mut caller: Caller<'_, Runtime>) -> wasmtime::Result<()> {
+    let n32 = export::<(), i32>(&mut caller, "getFieldNumLen32")?.call(&mut caller, ())?;
+    let read = export::<i32, i32>(&mut caller, "readSharedRWMemory")?;
+    let words = (0..n32)
+        .map(|j| read.call(&mut caller, j).map(|word| word as u32))
+        .collect::<wasmtime::Result<Vec<u32>>>()?;
+
+    push_to_line(caller.data_mut(), &BigUint::from_slice(&words).to_str_radix(10));
+    Ok(())
+}
+
+/// A compiled circom witness calculator. One calculator computes any number of witnesses of
+/// its circuit, one at a time.
+pub struct WitnessCalculator {
+    store: Store<Runtime>,
+    n32: usize,
+    read_shared_rw_memory: TypedFunc<i32, i32>,
+    write_shared_rw_memory: TypedFunc<(i32, i32), ()>,
+    init: TypedFunc<i32, ()>,
+    get_input_signal_size: TypedFunc<(i32, i32), i32>,
+    get_input_size: TypedFunc<(), i32>,
+    set_input_signal: TypedFunc<(i32, i32, i32), ()>,
+    get_witness_size: TypedFunc<(), i32>,
+    get_witness: TypedFunc<i32, ()>,
+}
+
+impl WitnessCalculator {
+    /// Compiles `circuit_js/circuit.wasm`, as written by `circom --wasm`.
+    pub fn from_file(path: &str) -> Result<Self, Box<dyn Error>> {
+        let wasm = std::fs::read(path)?;
+        Self::from_bytes(&wasm)
+    }
+
+    pub fn from_bytes(wasm: &[u8]) -> Result<Self, Box<dyn Error>> {
+        // circom reports failures through the exception handler, which already says where
+        let mut config = Config::new();
+        config.wasm_backtrace(false);
+        let engine = Engine::new(&config)?;
+        let module = Module::new(&engine, wasm)?;
+
+        let mut linker = Linker::new(&engine);
+        linker.func_wrap("runtime", "exceptionHandler", exception_handler)?;
+        linker.func_wrap("runtime", "printErrorMessage", print_error_message)?;
+        linker.func_wrap("runtime", "writeBufferMessage", write_buffer_message)?;
+        linker.func_wrap("runtime", "showSharedRWMemory", show_shared_rw_memory)?;
+
+        let mut store = Store::new(&engine, Runtime::default());
+        let instance = linker.instantiate(&mut store, &module)?;
+
+        let version = instance
+            .get_typed_func::<(), i32>(&mut store, "getVersion")
+            .map_err(|_| "Only circom 2 witness calculators are supported")?
+            .call(&mut store, ())?;
+        if version < 2 {
+            return Err(format!("Unsupported witness calculator version {}, circom 2 is required", version).into());
+        }
+
+        let n32 = instance.get_typed_func::<(), i32>(&mut store, "getFieldNumLen32")?.call(&mut store, ())? as usize;
+        let get_raw_prime = instance.get_typed_func::<(), ()>(&mut store, "getRawPrime")?;
+
+        let mut calculator = Self {
+            n32,
+            read_shared_rw_memory: instance.get_typed_func(&mut store, "readSharedRWMemory")?,
+            write_shared_rw_memory: instance.get_typed_func(&mut store, "writeSharedRWMemory")?,
+            init: instance.get_typed_func(&mut store, "init")?,
+            get_input_signal_size: instance.get_typed_func(&mut store, "getInputSignalSize")?,
+            get_input_size: instance.get_typed_func(&mut store, "getInputSize")?,
+            set_input_signal: instance.get_typed_func(&mut store, "setInputSignal")?,
+            get_witness_size: instance.get_typed_func(&mut store, "getWitnessSize")?,
+            get_witness: instance.get_typed_func(&mut store, "getWitness")?,
+            store,
+        };
+
+        get_raw_prime.call(&mut calculator.store, ())?;
+        let prime = BigUint::from_bytes_le(&calculator.read_shared()?);
+        if prime != modulus() {
+            return Err(format!(
+                "The witness calculator works modulo {}, not the BN254 scalar field. Compile the circuit with `-p bn128`.",
+                prime
+            )
+            .into());
+        }
+
+        Ok(calculator)
+    }
+
+    /// Computes the witness of the circuit inputs in `input`, a JSON object in the format
+    /// `snarkjs wtns calculate` takes. Values may be numbers, decimal or `0x` strings, and
+    /// nested arrays of them; negative values are taken modulo r.
+    pub fn calculate(&mut self, input: &Value) -> Result<Vec<F>, Box<dyn Error>> {
+        if !input.is_object() {
+            return Err("The circuit input must be a JSON object".into());
+        }
+
+        self.init.call(&mut self.store, 0)?;
+
+        let mut signals = Vec::new();
+        qualify_input("", input, &mut signals)?;
+
+        let modulus = BigInt::from(modulus());
+        let mut input_counter = 0;
+        for (name, values) in signals {
+            let hash = fnv1a(&name);
+            let (msb, lsb) = ((hash >> 32) as i32, hash as i32);
+
+            let size = self.get_input_signal_size.call(&mut self.store, (msb, lsb))?;
+            if size < 0 {
+                return Err(format!("Signal {} not found", name).into());
+            }
+            if values.len() < size as usize {
+                return Err(format!("Not enough values for input signal {}", name).into());
+            }
+            if values.len() > size as usize {
+                return Err(format!("Too many values for input signal {}", name).into());
+            }
+
+            for (i, value) in values.into_iter().enumerate() {
+                let value = parse_integer(value)
+                    .ok_or_else(|| format!("Invalid value {} for input signal {}", value, name))?;
+                let value = ((value % &modulus) + &modulus) % &modulus;
+                self.write_shared(&value.to_biguint().unwrap())?;
+                self.set_input_signal.call(&mut self.store, (msb, lsb, i as i32))?;
+                input_counter += 1;
+            }
+        }
+
+        let input_size = self.get_input_size.call(&mut self.store, ())?;
+        if input_counter < input_size {
+            return Err(format!(
+                "Not all inputs have been set. Only {} out of {}",
+                input_counter, input_size
+            )
+            .into());
+        }
+
+        let witness_size = self.get_witness_size.call(&mut self.store, ())?;
+        let mut witness = Vec::with_capacity(witness_size as usize);
+        for i in 0..witness_size {
+            self.get_witness.call(&mut self.store, i)?;
+            witness.push(F::from_bytes_le(&self.read_shared()?));
+        }
+
+        Ok(witness)
+    }
+
+    /// Like `calculate`, reading the input from a JSON file.
+    pub fn calculate_from_file(&mut self, input: &str) -> Result<Vec<F>, Box<dyn Error>> {
+        let input_str = std::fs::read_to_string(input)?;
+        let input: Value = serde_json::from_str(&input_str)?;
+        self.calculate(&input)
+    }
+
+    /// The shared memory as little-endian bytes.
+    fn read_shared(&mut self) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut bytes = Vec::with_capacity(4 * self.n32);
+        for j in 0..self.n32 {
+            let word = self.read_shared_rw_memory.call(&mut self.store, j as i32)?;
+            bytes.extend_from_slice(&word.to_le_bytes());
+        }
+        Ok(bytes)
+    }
+
+    fn write_shared(&mut self, value: &BigUint) -> Result<(), Box<dyn Error>> {
+        let mut words = value.to_u32_digits();
+        words.resize(self.n32, 0);
+        for (j, word) in words.into_iter().enumerate() {
+            self.write_shared_rw_memory.call(&mut self.store, (j as i32, word as i32))?;
+        }
+        Ok(())
+    }
+}
+
+/// The order r of the BN254 scalar field.
+pub(crate) fn modulus() -> BigUint {
+    BigUint::from_bytes_le(&(F::zero() - F::one()).to_bytes_le()) + 1u32
+}
+
+fn fnv1a(name: &str) -> u64 {
+    name.bytes()
+        .fold(0xCBF2_9CE4_8422_2325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100_0000_01B3))
+}
+
+/// Flattens `input` into signal names and their values as `witness_calculator.js` does: arrays
+/// of values become one signal, and objects, including arrays of objects, are qualified with
+/// `.field` and `[i]` into the signals of circom buses.
+fn qualify_input<'a>(
+    prefix: &str,
+    input: &'a Value,
+    signals: &mut Vec<(String, Vec<&'a Value>)>,
+) -> Result<(), Box<dyn Error>> {
+    match input {
+        Value::Object(fields) => {
+            for (key, value) in fields {
+                let name = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                qualify_input(&name, value, signals)?;
+            }
+        }
+        Value::Array(_) => {
+            let mut values = Vec::new();
+            flatten(input, &mut values);
+
+            let objects = values.iter().filter(|value| value.is_object()).count();
+            if objects == 0 {
+                signals.push((prefix.to_string(), values));
+            } else if objects == values.len() {
+                for (i, value) in values.into_iter().enumerate() {
+                    qualify_input(&format!("{}[{}]", prefix, i), value, signals)?;
+                }
+            } else {
+                return Err(format!("Types are not the same in the key {}", prefix).into());
+            }
+        }
+        _ => signals.push((prefix.to_string(), vec![input])),
+    }
+    Ok(())
+}
+
+fn flatten<'a>(value: &'a Value, values: &mut Vec<&'a Value>) {
+    match value {
+        Value::Array(items) => items.iter().for_each(|item| flatten(item, values)),
+        _ => values.push(value),
+    }
+}
+
+/// Reads a JSON number, decimal or `0x` string, or boolean as an integer.
+pub(crate) fn parse_integer(value: &Value) -> Option<BigInt> {
+    match value {
+        Value::Number(number) => number
+            .as_i64()
+            .map(BigInt::from)
+            .or_else(|| number.as_u64().map(BigInt::from)),
+        Value::String(string) => {
+            let (negative, digits) = match string.trim().strip_prefix('-') {
+                Some(digits) => (true, digits),
+                None => (false, string.trim()),
+            };
+            let parsed = match digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+                Some(hex) => BigInt::parse_bytes(hex.as_bytes(), 16),
+                None => BigInt::parse_bytes(digits.as_bytes(), 10),
+            };
+            parsed.map(|parsed| if negative { -parsed } else { parsed })
+        }
+        Value::Bool(flag) => Some(BigInt::from(*flag as u8)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// A calculator with the circom 2 interface for the witness `[1, a, b[0], b[1]]`, working
+    /// modulo `prime`. It fails an assertion when `a` is zero.
+    fn fake_circuit(prime: &BigUint) -> String {
+        let mut prime_bytes = prime.to_bytes_le();
+        prime_bytes.resize(32, 0);
+        let prime_data: String = prime_bytes.iter().map(|byte| format!("\\{:02x}", byte)).collect();
+
+        format!(
+            r#"(module
+  (import "runtime" "exceptionHandler" (func $exceptionHandler (param i32)))
+  (import "runtime" "printErrorMessage" (func $printErrorMessage))
+  (import "runtime" "writeBufferMessage" (func $writeBufferMessage))
+  (import "runtime" "showSharedRWMemory" (func $showSharedRWMemory))
+  (memory 1)
+  (data (i32.const 1024) "{prime_data}")
+  ;; shared memory at 0, witness element i at 64 + 32 i
+  (func $copy (param $from i32) (param $to i32)
+    (local $j i32)
+    (loop $words
+      (i32.store (i32.add (local.get $to) (local.get $j)) (i32.load (i32.add (local.get $from) (local.get $j))))
+      (local.set $j (i32.add (local.get $j) (i32.const 4)))
+      (br_if $words (i32.lt_u (local.get $j) (i32.const 32)))))
+  (func (export "getVersion") (result i32) (i32.const 2))
+  (func (export "getFieldNumLen32") (result i32) (i32.const 8))
+  (func (export "getMessageChar") (result i32) (i32.const 0))
+  (func (export "getRawPrime") (call $copy (i32.const 1024) (i32.const 0)))
+  (func (export "readSharedRWMemory") (param i32) (result i32)
+    (i32.load (i32.mul (local.get 0) (i32.const 4))))
+  (func (export "writeSharedRWMemory") (param i32 i32)
+    (i32.store (i32.mul (local.get 0) (i32.const 4)) (local.get 1)))
+  (func (export "init") (param i32)
+    (local $j i32)
+    (loop $clear
+      (i32.store (i32.add (i32.const 64) (local.get $j)) (i32.const 0))
+      (local.set $j (i32.add (local.get $j) (i32.const 4)))
+      (br_if $clear (i32.lt_u (local.get $j) (i32.const 128))))
+    (i32.store (i32.const 64) (i32.const 1)))
+  (func $isA (param i32 i32) (result i32)
+    (i32.and (i32.eq (local.get 0) (i32.const -1352410036)) (i32.eq (local.get 1) (i32.const -2046694260))))
+  (func $isB (param i32 i32) (result i32)
+    (i32.and (i32.eq (local.get 0) (i32.const -1352409268)) (i32.eq (local.get 1) (i32.const -2046692955))))
+  (func (export "getInputSignalSize") (param i32 i32) (result i32)
+    (if (result i32) (call $isA (local.get 0) (local.get 1))
+      (then (i32.const 1))
+      (else (if (result i32) (call $isB (local.get 0) (local.get 1))
+        (then (i32.const 2))
+        (else (i32.const -1))))))
+  (func (export "getInputSize") (result i32) (i32.const 3))
+  (func (export "getWitnessSize") (result i32) (i32.const 4))
+  (func (export "setInputSignal") (param i32 i32 i32)
+    (if (call $isA (local.get 0) (local.get 1))
+      (then
+        (if (i32.eqz (i32.or (i32.load (i32.const 0)) (i32.load (i32.const 28))))
+          (then (call $printErrorMessage) (call $exceptionHandler (i32.const 4))))
+        (call $copy (i32.const 0) (i32.const 96)))
+      (else (call $copy (i32.const 0) (i32.add (i32.const 128) (i32.mul (local.get 2) (i32.const 32)))))))
+  (func (export "getWitness") (param i32)
+    (call $copy (i32.add (i32.const 64) (i32.mul (local.get 0) (i32.const 32))) (i32.const 0))))"#
+        )
+    }
+
+    fn calculator() -> WitnessCalculator {
+        WitnessCalculator::from_bytes(fake_circuit(&modulus()).as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_calculate_witness() {
+        let mut calculator = calculator();
+
+        let witness = calculator.calculate(&json!({ "a": "-1", "b": [2, "0x10"] })).unwrap();
+        assert_eq!(witness, [F::one(), F::zero() - F::one(), F::from_u32(2), F::from_u32(16)]);
+
+        // the calculator is reset between witnesses
+        let witness = calculator.calculate(&json!({ "a": 5, "b": [[7], ["8"]] })).unwrap();
+        assert_eq!(witness, [F::one(), F::from_u32(5), F::from_u32(7), F::from_u32(8)]);
+    }
+
+    #[test]
+    fn test_invalid_inputs() {
+        let mut calculator = calculator();
+
+        let error = |input: Value, calculator: &mut WitnessCalculator| calculator.calculate(&input).unwrap_err().to_string();
+
+        assert_eq!(error(json!({ "a": 1 }), &mut calculator), "Not all inputs have been set. Only 1 out of 3");
+        assert_eq!(error(json!({ "c": 1 }), &mut calculator), "Signal c not found");
+        assert_eq!(error(json!({ "a": 1, "b": [1, 2, 3] }), &mut calculator), "Too many values for input signal b");
+        assert_eq!(error(json!({ "a": 1, "b": [1] }), &mut calculator), "Not enough values for input signal b");
+        assert!(error(json!({ "a": 0, "b": [1, 2] }), &mut calculator).contains("Assert Failed."));
+    }
+
+    #[test]
+    fn test_rejects_other_primes() {
+        let wasm = fake_circuit(&BigUint::from(65_537u32));
+        assert!(WitnessCalculator::from_bytes(wasm.as_bytes()).is_err());
+    }
+}
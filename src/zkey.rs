@@ -1,7 +1,8 @@
 use crate::{
-    conversions::from_affine_mont,
+    cache::VerificationKey,
+    conversions::{from_affine_mont, from_scalars_mont, from_u8},
     file_wrapper::{FileWrapper, Section},
-    ProjectiveG1, ProjectiveG2, C1, C2, F,
+    ProjectiveG1, ProjectiveG2, C1, C2, F, G1, G2,
 };
 use icicle_core::traits::FieldImpl;
 use std::io::{self};
@@ -22,6 +23,8 @@ pub struct ZKey {
     pub vk_gamma_2: ProjectiveG2,
     pub vk_delta_1: ProjectiveG1,
     pub vk_delta_2: ProjectiveG2,
+    /// Bases of the public inputs in the verifier's linear combination, constant term first.
+    pub ic: Vec<G1>,
 }
 
 impl ZKey {
@@ -41,6 +44,19 @@ impl ZKey {
             vk_gamma_2: ProjectiveG2::zero(),
             vk_delta_1: ProjectiveG1::zero(),
             vk_delta_2: ProjectiveG2::zero(),
+            ic: Vec::new(),
+        }
+    }
+
+    /// Verification key of the circuit, as snarkjs exports it.
+    pub fn verification_key(&self) -> VerificationKey {
+        VerificationKey {
+            vk_alpha_1: self.vk_alpha_1.into(),
+            vk_beta_2: self.vk_beta_2.into(),
+            vk_gamma_2: self.vk_gamma_2.into(),
+            vk_delta_2: self.vk_delta_2.into(),
+            ic: self.ic.clone(),
+            n_public: self.n_public,
         }
     }
 
@@ -81,6 +97,9 @@ impl ZKey {
         zkey.vk_delta_1 = mont_points_g1[2].to_projective();
         zkey.vk_delta_2 = mont_points_g2[2].to_projective();
 
+        zkey.ic = from_u8::<G1>(fd.read_section(sections, 3)?).to_vec();
+        from_affine_mont::<C1>(&mut zkey.ic);
+
         Ok(zkey)
     }
 }
@@ -90,3 +109,161 @@ impl Default for ZKey {
         Self::new()
     }
 }
+
+/// Header of a snarkjs PLONK zkey.
+#[derive(Clone, Debug)]
+pub struct PlonkZKey {
+    pub n8q: usize,
+    pub q: F,
+    pub n8r: usize,
+    pub r: F,
+    /// Signals of the circuit, including the internal ones computed from the additions section.
+    pub n_vars: usize,
+    pub n_public: usize,
+    pub domain_size: usize,
+    pub power: usize,
+    pub n_additions: usize,
+    pub n_constraints: usize,
+    /// Coset shifts of the B and C wires in the permutation argument.
+    pub k1: F,
+    pub k2: F,
+    pub qm: G1,
+    pub ql: G1,
+    pub qr: G1,
+    pub qo: G1,
+    pub qc: G1,
+    pub s1: G1,
+    pub s2: G1,
+    pub s3: G1,
+    /// `tau * g2`
+    pub x_2: G2,
+}
+
+impl PlonkZKey {
+    pub fn read_header_plonk(fd: &mut FileWrapper, sections: &[Vec<Section>]) -> io::Result<Self> {
+        fd.start_read_unique_section(sections, 2)?;
+        let n8q = fd.read_u32_le()? as usize;
+        let q = fd.read_big_int(n8q, None)?;
+
+        let n8r = fd.read_u32_le()? as usize;
+        let r = fd.read_big_int(n8r, None)?;
+        let n_vars = fd.read_u32_le()? as usize;
+        let n_public = fd.read_u32_le()? as usize;
+        let domain_size = fd.read_u32_le()? as usize;
+        let n_additions = fd.read_u32_le()? as usize;
+        let n_constraints = fd.read_u32_le()? as usize;
+
+        let mut k = [fd.read_big_int(n8r, None)?, fd.read_big_int(n8r, None)?];
+        from_scalars_mont(&mut k);
+
+        let mut points_g1 = [(); 8].map(|_| fd.read_g1());
+        let mut x_2 = [fd.read_g2()];
+        fd.end_read_section(false)?;
+
+        from_affine_mont::<C1>(&mut points_g1);
+        from_affine_mont::<C2>(&mut x_2);
+
+        let [qm, ql, qr, qo, qc, s1, s2, s3] = points_g1;
+
+        Ok(Self {
+            n8q,
+            q,
+            n8r,
+            r,
+            n_vars,
+            n_public,
+            domain_size,
+            power: domain_size.trailing_zeros() as usize,
+            n_additions,
+            n_constraints,
+            k1: k[0],
+            k2: k[1],
+            qm,
+            ql,
+            qr,
+            qo,
+            qc,
+            s1,
+            s2,
+            s3,
+            x_2: x_2[0],
+        })
+    }
+}
+
+/// Header of an FFLONK zkey.
+#[derive(Clone, Debug)]
+pub struct FflonkZKey {
+    pub n8q: usize,
+    pub q: F,
+    pub n8r: usize,
+    pub r: F,
+    /// Signals of the circuit, including the internal ones computed from the additions section.
+    pub n_vars: usize,
+    pub n_public: usize,
+    pub domain_size: usize,
+    pub power: usize,
+    pub n_additions: usize,
+    pub n_constraints: usize,
+    /// Coset shifts of the B and C wires in the permutation argument.
+    pub k1: F,
+    pub k2: F,
+    /// Primitive third, fourth and eighth roots of unity, the opening points are multiples of.
+    pub w3: F,
+    pub w4: F,
+    pub w8: F,
+    /// Cube root of the generator of the domain.
+    pub wr: F,
+    /// `tau * g2`
+    pub x_2: G2,
+    /// Commitment to the circuit polynomial `C0`, which interleaves the selectors and sigmas.
+    pub c0: G1,
+}
+
+impl FflonkZKey {
+    pub fn read_header_fflonk(fd: &mut FileWrapper, sections: &[Vec<Section>]) -> io::Result<Self> {
+        fd.start_read_unique_section(sections, 2)?;
+        let n8q = fd.read_u32_le()? as usize;
+        let q = fd.read_big_int(n8q, None)?;
+
+        let n8r = fd.read_u32_le()? as usize;
+        let r = fd.read_big_int(n8r, None)?;
+        let n_vars = fd.read_u32_le()? as usize;
+        let n_public = fd.read_u32_le()? as usize;
+        let domain_size = fd.read_u32_le()? as usize;
+        let n_additions = fd.read_u32_le()? as usize;
+        let n_constraints = fd.read_u32_le()? as usize;
+
+        let mut scalars = [(); 6].map(|_| fd.read_big_int(n8r, None).unwrap());
+        let mut x_2 = [fd.read_g2()];
+        let mut c0 = [fd.read_g1()];
+        fd.end_read_section(false)?;
+
+        from_scalars_mont(&mut scalars);
+        from_affine_mont::<C2>(&mut x_2);
+        from_affine_mont::<C1>(&mut c0);
+
+        let [k1, k2, w3, w4, w8, wr] = scalars;
+
+        Ok(Self {
+            n8q,
+            q,
+            n8r,
+            r,
+            n_vars,
+            n_public,
+            domain_size,
+            power: domain_size.trailing_zeros() as usize,
+            n_additions,
+            n_constraints,
+            k1,
+            k2,
+            w3,
+            w4,
+            w8,
+            wr,
+            x_2: x_2[0],
+            c0: c0[0],
+        })
+    }
+}
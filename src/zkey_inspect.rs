@@ -0,0 +1,245 @@
+//! Statistics of a zkey, read from its header and sections table without loading the key, and
+//! the device memory its cache will take.
+
+use std::error::Error;
+use std::fmt;
+use std::mem::size_of;
+
+use icicle_core::traits::FieldImpl;
+
+use crate::{
+    file_wrapper::{FileWrapper, FFLONK_PROTOCOL_ID, GROTH16_PROTOCOL_ID, PLONK_PROTOCOL_ID},
+    witness_calculator::modulus,
+    F,
+};
+
+/// Device memory, in bytes, of a `ZKeyCache` computed by a default `CacheManager`.
+///
+/// Only the default cache is covered: streamed bases (`with_streamed_bases`), precomputed bases
+/// (`with_msm_settings`), shards on other devices (`with_msm_devices`) and a host matrix
+/// (`with_host_matrix`) all change what the cache keeps on the device.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheMemory {
+    /// The A and B coefficient matrix as a CSR matrix.
+    pub matrix: usize,
+    /// The MSM bases of sections 5 to 9, uploaded as they are stored.
+    pub bases: usize,
+    /// Powers of the coset generator, for `QuotientStrategy::PrecomputedKeys`.
+    pub keys: usize,
+}
+
+impl CacheMemory {
+    pub fn total(&self) -> usize {
+        self.matrix + self.bases + self.keys
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ZKeyInfo {
+    pub protocol: String,
+    pub curve: String,
+    pub n_vars: usize,
+    pub n_public: usize,
+    pub domain_size: usize,
+    pub power: usize,
+    /// Entries of the A and B matrices in section 4. Groth16 only.
+    pub n_coefficients: Option<usize>,
+    /// Size in bytes of every section present, by section id.
+    pub sections: Vec<(usize, u64)>,
+    /// Groth16 only.
+    pub cache_memory: Option<CacheMemory>,
+    /// Total and free memory of the device the zkey is inspected for, if it reports them.
+    pub available_memory: Option<(usize, usize)>,
+}
+
+impl ZKeyInfo {
+    /// Whether the cache fits in the free memory of the device; `None` when either is unknown.
+    pub fn fits(&self) -> Option<bool> {
+        let (_, free) = self.available_memory?;
+        Some(self.cache_memory?.total() <= free)
+    }
+}
+
+fn format_bytes(bytes: usize) -> String {
+    match bytes {
+        b if b >= 1 << 30 => format!("{:.1} GiB", b as f64 / (1u64 << 30) as f64),
+        b if b >= 1 << 20 => format!("{:.1} MiB", b as f64 / (1u64 << 20) as f64),
+        b => format!("{} bytes", b),
+    }
+}
+
+impl fmt::Display for ZKeyInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "protocol: {}", self.protocol)?;
+        writeln!(f, "curve: {}", self.curve)?;
+        writeln!(f, "n_vars: {}", self.n_vars)?;
+        writeln!(f, "n_public: {}", self.n_public)?;
+        writeln!(f, "domain_size: {} (2^{})", self.domain_size, self.power)?;
+        if let Some(n_coefficients) = self.n_coefficients {
+            writeln!(f, "coefficients: {}", n_coefficients)?;
+        }
+        for (id, size) in &self.sections {
+            writeln!(f, "section {}: {} bytes", id, size)?;
+        }
+        if let Some(memory) = &self.cache_memory {
+            writeln!(
+                f,
+                "default cache device memory: {} (matrix {}, bases {}, keys {})",
+                format_bytes(memory.total()),
+                format_bytes(memory.matrix),
+                format_bytes(memory.bases),
+                format_bytes(memory.keys)
+            )?;
+        }
+        if let Some((total, free)) = self.available_memory {
+            write!(f, "device memory: {} free of {}", format_bytes(free), format_bytes(total))?;
+            if let Some(fits) = self.fits() {
+                write!(f, ", the cache {}", if fits { "fits" } else { "does not fit" })?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn inspect_zkey_helper(zkey: &str) -> Result<ZKeyInfo, Box<dyn Error>> {
+    let (fd_zkey, sections_zkey) = FileWrapper::read_bin_file(zkey, "zkey", 2)?;
+    let mut zkey_file = FileWrapper::new(fd_zkey)?;
+
+    let sections: Vec<(usize, u64)> = sections_zkey
+        .iter()
+        .enumerate()
+        .filter(|(_, section)| !section.is_empty())
+        .map(|(id, section)| (id, section.iter().map(|s| s.size).sum()))
+        .collect();
+    let section_size = |id: usize| sections.iter().find(|(i, _)| *i == id).map_or(0, |(_, size)| *size as usize);
+
+    let (protocol, n8r, r, n_vars, n_public, domain_size, power) = match zkey_file.read_protocol_id(&sections_zkey)? {
+        GROTH16_PROTOCOL_ID => {
+            let h = zkey_file.read_zkey_header(&sections_zkey)?;
+            ("groth16", h.n8r, h.r, h.n_vars, h.n_public, h.domain_size, h.power)
+        }
+        PLONK_PROTOCOL_ID => {
+            let h = zkey_file.read_plonk_zkey_header(&sections_zkey)?;
+            ("plonk", h.n8r, h.r, h.n_vars, h.n_public, h.domain_size, h.power)
+        }
+        FFLONK_PROTOCOL_ID => {
+            let h = zkey_file.read_fflonk_zkey_header(&sections_zkey)?;
+            ("fflonk", h.n8r, h.r, h.n_vars, h.n_public, h.domain_size, h.power)
+        }
+        protocol_id => return Err(format!("Unsupported zkey protocol {}", protocol_id).into()),
+    };
+
+    let curve = if n8r == 32 && F::eq(&r, &F::from_bytes_le(&modulus().to_bytes_le())) {
+        "bn128"
+    } else {
+        "unknown"
+    };
+
+    let (n_coefficients, cache_memory) = if protocol == "groth16" {
+        let buff_coeffs = zkey_file.read_section(&sections_zkey, 4)?;
+        let n_coefficients = buff_coeffs
+            .get(..4)
+            .ok_or("Truncated coefficients section")?;
+        let n_coefficients = u32::from_le_bytes(n_coefficients.try_into()?) as usize;
+        let memory = CacheMemory {
+            matrix: n_coefficients * (size_of::<u32>() + size_of::<F>()) + (2 * domain_size + 1) * size_of::<u32>(),
+            bases: (5..=9).map(section_size).sum(),
+            keys: domain_size * size_of::<F>(),
+        };
+        (Some(n_coefficients), Some(memory))
+    } else {
+        (None, None)
+    };
+
+    Ok(ZKeyInfo {
+        protocol: protocol.to_string(),
+        curve: curve.to_string(),
+        n_vars,
+        n_public,
+        domain_size,
+        power,
+        n_coefficients,
+        sections,
+        cache_memory,
+        available_memory: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proof_helper::tests::fixture;
+
+    /// Every section, its 12 byte header and the 12 byte file header.
+    fn file_size(info: &ZKeyInfo) -> u64 {
+        info.sections.iter().map(|(_, size)| size + 12).sum::<u64>() + 12
+    }
+
+    #[test]
+    fn test_inspect_groth16_zkey() {
+        let zkey = fixture("groth16/small/circuit_final.zkey");
+        let info = inspect_zkey_helper(&zkey).unwrap();
+
+        assert_eq!(info.protocol, "groth16");
+        assert_eq!(info.curve, "bn128");
+        assert_eq!((info.n_vars, info.n_public), (11, 2));
+        assert_eq!((info.domain_size, info.power), (16, 4));
+        assert_eq!(info.n_coefficients, Some(28));
+        assert_eq!(file_size(&info), std::fs::metadata(&zkey).unwrap().len());
+
+        let g1 = 64;
+        let sizes: Vec<u64> = info.sections.iter().map(|(_, size)| *size).collect();
+        assert_eq!(info.sections.iter().map(|(id, _)| *id).collect::<Vec<_>>(), (1..=10).collect::<Vec<_>>());
+        assert_eq!(sizes[2], 3 * g1); // IC, one point per public signal and one for the constant
+        assert_eq!(sizes[3], 4 + 28 * (12 + 32));
+        assert_eq!(sizes[4..9], [11 * g1, 11 * g1, 11 * 2 * g1, 8 * g1, 16 * g1]);
+
+        let memory = info.cache_memory.unwrap();
+        assert_eq!(memory.matrix, 28 * (4 + 32) + 33 * 4);
+        assert_eq!(memory.bases, sizes[4..9].iter().sum::<u64>() as usize);
+        assert_eq!(memory.keys, 16 * 32);
+    }
+
+    #[test]
+    fn test_inspect_rejects_bad_zkeys() {
+        assert!(inspect_zkey_helper(&fixture("groth16/missing.zkey")).is_err());
+        assert!(inspect_zkey_helper(&fixture("groth16/small/witness_1.wtns")).is_err());
+
+        // cut section 4 down to two bytes, too short for its entry count
+        let zkey = std::fs::read(fixture("groth16/small/circuit_final.zkey")).unwrap();
+        let mut truncated = zkey[..12].to_vec();
+        let mut offset = 12;
+        while offset < zkey.len() {
+            let id = u32::from_le_bytes(zkey[offset..offset + 4].try_into().unwrap());
+            let size = u64::from_le_bytes(zkey[offset + 4..offset + 12].try_into().unwrap()) as usize;
+            let data = &zkey[offset + 12..offset + 12 + size];
+            let data = if id == 4 { &data[..2] } else { data };
+            truncated.extend_from_slice(&id.to_le_bytes());
+            truncated.extend_from_slice(&(data.len() as u64).to_le_bytes());
+            truncated.extend_from_slice(data);
+            offset += 12 + size;
+        }
+        let path = std::env::temp_dir().join("icicle_snark_truncated.zkey");
+        std::fs::write(&path, truncated).unwrap();
+
+        let error = inspect_zkey_helper(path.to_str().unwrap()).unwrap_err();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(error.to_string(), "Truncated coefficients section");
+    }
+
+    #[test]
+    fn test_inspect_plonk_zkey() {
+        let zkey = fixture("plonk/circuit.zkey");
+        let info = inspect_zkey_helper(&zkey).unwrap();
+
+        assert_eq!(info.protocol, "plonk");
+        assert_eq!(info.curve, "bn128");
+        assert_eq!((info.n_vars, info.n_public), (6, 1));
+        assert_eq!((info.domain_size, info.power), (8, 3));
+        assert_eq!(info.sections.len(), 14);
+        assert_eq!(file_size(&info), std::fs::metadata(&zkey).unwrap().len());
+        assert_eq!(info.n_coefficients, None);
+        assert_eq!(info.cache_memory, None);
+    }
+}
@@ -3,6 +3,7 @@ pub mod field;
 pub mod msm;
 pub mod ntt;
 pub mod pairing;
+pub mod polynomials;
 pub mod vec_ops;
 
 #[doc(hidden)]
@@ -0,0 +1,439 @@
+use crate::ntt::{get_root_of_unity, ntt, ntt_inplace, NTTConfig, NTTDir, NTT};
+use crate::traits::{Arithmetic, FieldImpl};
+use crate::vec_ops::{add_scalars, div_scalars, mul_scalars, scalar_mul, sub_scalars, sum_scalars, VecOps, VecOpsConfig};
+use icicle_runtime::{
+    errors::eIcicleError,
+    memory::{DeviceSlice, DeviceVec, HostOrDeviceSlice, HostSlice},
+};
+
+#[doc(hidden)]
+pub mod tests;
+
+/// A polynomial in coefficient form, stored on the active device.
+///
+/// All operations that need an NTT (`mul`, the divisions, `interpolate` and the domain and coset evaluations)
+/// use the NTT domain, so it must be initialized with a root of unity of large
+/// enough order first.
+///
+/// The degree is tracked through the operations rather than recomputed from the coefficients, so it is an
+/// upper bound: adding two polynomials can cancel their leading terms. The zero polynomial has degree 0.
+pub struct Polynomial<F: FieldImpl> {
+    coeffs: DeviceVec<F>,
+    degree: usize,
+}
+
+impl<F> Polynomial<F>
+where
+    F: FieldImpl + Arithmetic,
+    <F as FieldImpl>::Config: NTT<F, F> + VecOps<F>,
+{
+    /// Uploads `coeffs`, lowest degree first. Trailing zero coefficients do not count towards the degree, and
+    /// no coefficients at all make the zero polynomial.
+    pub fn from_coeffs(coeffs: &HostSlice<F>) -> Result<Self, eIcicleError> {
+        if coeffs.is_empty() {
+            return Self::zero();
+        }
+
+        let degree = coeffs
+            .as_slice()
+            .iter()
+            .rposition(|c| *c != F::zero())
+            .unwrap_or(0);
+
+        let mut device_coeffs = DeviceVec::device_malloc(degree + 1)?;
+        device_coeffs.copy_from_host(&coeffs[..=degree])?;
+
+        Ok(Self {
+            coeffs: device_coeffs,
+            degree,
+        })
+    }
+
+    /// Takes ownership of coefficients that are already on the device. The degree is taken to be
+    /// `coeffs.len() - 1`; an empty vector makes the zero polynomial.
+    pub fn from_device(coeffs: DeviceVec<F>) -> Result<Self, eIcicleError> {
+        if coeffs.is_empty() {
+            return Self::zero();
+        }
+
+        let degree = coeffs.len() - 1;
+        Ok(Self { coeffs, degree })
+    }
+
+    /// The zero polynomial, of degree 0.
+    pub fn zero() -> Result<Self, eIcicleError> {
+        let mut coeffs = DeviceVec::device_malloc(1)?;
+        coeffs.memset(0, 1)?;
+
+        Ok(Self { coeffs, degree: 0 })
+    }
+
+    /// Interpolates the polynomial of degree below `evals.len()` that takes `evals` on the subgroup of that
+    /// size. The size must be a power of two, or `InvalidArgument` is returned.
+    pub fn interpolate(evals: &(impl HostOrDeviceSlice<F> + ?Sized)) -> Result<Self, eIcicleError> {
+        Self::interpolate_on_coset(evals, &F::one())
+    }
+
+    /// Like `interpolate`, for evaluations on the coset `coset_gen * H`.
+    pub fn interpolate_on_coset(
+        evals: &(impl HostOrDeviceSlice<F> + ?Sized),
+        coset_gen: &F,
+    ) -> Result<Self, eIcicleError> {
+        if !evals
+            .len()
+            .is_power_of_two()
+        {
+            return Err(eIcicleError::InvalidArgument);
+        }
+
+        let mut cfg = NTTConfig::<F>::default();
+        cfg.coset_gen = *coset_gen;
+
+        let mut coeffs = DeviceVec::device_malloc(evals.len())?;
+        ntt(evals, NTTDir::kInverse, &cfg, &mut coeffs[..])?;
+
+        Self::from_device(coeffs)
+    }
+
+    pub fn degree(&self) -> usize {
+        self.degree
+    }
+
+    /// The `degree + 1` coefficients, lowest degree first.
+    pub fn coefficients(&self) -> &DeviceSlice<F> {
+        &self.coeffs[..=self.degree]
+    }
+
+    pub fn evaluate(&self, x: &F) -> Result<F, eIcicleError> {
+        let mut powers = Vec::with_capacity(self.degree + 1);
+        let mut power = F::one();
+        for _ in 0..=self.degree {
+            powers.push(power);
+            power = power * *x;
+        }
+
+        let cfg = VecOpsConfig::default();
+        let mut terms = DeviceVec::device_malloc(self.degree + 1)?;
+        mul_scalars(
+            self.coefficients(),
+            HostSlice::from_slice(&powers),
+            &mut terms[..],
+            &cfg,
+        )?;
+
+        let mut result = [F::zero()];
+        sum_scalars(&terms[..], HostSlice::from_mut_slice(&mut result), &cfg)?;
+
+        Ok(result[0])
+    }
+
+    /// Evaluates on the subgroup of order `size`, in natural order. `size` must be a power of two greater
+    /// than the degree, or `InvalidArgument` is returned.
+    pub fn evaluate_on_domain(&self, size: usize) -> Result<DeviceVec<F>, eIcicleError> {
+        self.evaluate_on_coset(size, &F::one())
+    }
+
+    /// Evaluates on the coset `coset_gen * H` of the subgroup `H` of order `size`.
+    pub fn evaluate_on_coset(&self, size: usize, coset_gen: &F) -> Result<DeviceVec<F>, eIcicleError> {
+        if !size.is_power_of_two() {
+            return Err(eIcicleError::InvalidArgument);
+        }
+
+        let mut cfg = NTTConfig::<F>::default();
+        cfg.coset_gen = *coset_gen;
+
+        let mut evals = self.padded(size)?;
+        ntt_inplace(&mut evals[..], NTTDir::kForward, &cfg)?;
+
+        Ok(evals)
+    }
+
+    pub fn add(&self, other: &Self) -> Result<Self, eIcicleError> {
+        let degree = self
+            .degree
+            .max(other.degree);
+        let a = self.padded(degree + 1)?;
+        let b = other.padded(degree + 1)?;
+
+        let mut coeffs = DeviceVec::device_malloc(degree + 1)?;
+        add_scalars(&a[..], &b[..], &mut coeffs[..], &VecOpsConfig::default())?;
+
+        Ok(Self { coeffs, degree })
+    }
+
+    pub fn sub(&self, other: &Self) -> Result<Self, eIcicleError> {
+        let degree = self
+            .degree
+            .max(other.degree);
+        let a = self.padded(degree + 1)?;
+        let b = other.padded(degree + 1)?;
+
+        let mut coeffs = DeviceVec::device_malloc(degree + 1)?;
+        sub_scalars(&a[..], &b[..], &mut coeffs[..], &VecOpsConfig::default())?;
+
+        Ok(Self { coeffs, degree })
+    }
+
+    /// Multiplies through an NTT of the next power of two above the product's degree.
+    pub fn mul(&self, other: &Self) -> Result<Self, eIcicleError> {
+        let degree = self.degree + other.degree;
+        let size = (degree + 1).next_power_of_two();
+
+        let a = self.evaluate_on_domain(size)?;
+        let b = other.evaluate_on_domain(size)?;
+
+        let mut coeffs = DeviceVec::device_malloc(size)?;
+        mul_scalars(&a[..], &b[..], &mut coeffs[..], &VecOpsConfig::default())?;
+        ntt_inplace(&mut coeffs[..], NTTDir::kInverse, &NTTConfig::<F>::default())?;
+
+        Ok(Self { coeffs, degree })
+    }
+
+    pub fn scale(&self, factor: &F) -> Result<Self, eIcicleError> {
+        let mut coeffs = DeviceVec::device_malloc(self.degree + 1)?;
+        scalar_mul(
+            HostSlice::from_slice(&[*factor]),
+            self.coefficients(),
+            &mut coeffs[..],
+            &VecOpsConfig::default(),
+        )?;
+
+        Ok(Self {
+            coeffs,
+            degree: self.degree,
+        })
+    }
+
+    /// Adds `coeff * X^degree`.
+    pub fn add_monomial(&self, coeff: &F, degree: usize) -> Result<Self, eIcicleError> {
+        let degree_sum = self
+            .degree
+            .max(degree);
+        let mut coeffs = self.padded(degree_sum + 1)?;
+
+        let mut term = [F::zero()];
+        coeffs[degree..=degree].copy_to_host(HostSlice::from_mut_slice(&mut term))?;
+        term[0] = term[0] + *coeff;
+        coeffs[degree..=degree].copy_from_host(HostSlice::from_slice(&term))?;
+
+        Ok(Self {
+            coeffs,
+            degree: degree_sum,
+        })
+    }
+
+    /// Multiplies by the vanishing polynomial `X^n - 1` of the subgroup of order `n`.
+    pub fn mul_by_vanishing(&self, n: usize) -> Result<Self, eIcicleError> {
+        let degree = self.degree + n;
+        let mut shifted = DeviceVec::device_malloc(degree + 1)?;
+        shifted.memset(0, degree + 1)?;
+        shifted[n..].copy(self.coefficients())?;
+        let unshifted = self.padded(degree + 1)?;
+
+        let mut coeffs = DeviceVec::device_malloc(degree + 1)?;
+        sub_scalars(&shifted[..], &unshifted[..], &mut coeffs[..], &VecOpsConfig::default())?;
+
+        Ok(Self { coeffs, degree })
+    }
+
+    /// Divides by `X - x`, for an `x` that is not a root of unity. The division must be exact; the remainder is
+    /// not checked.
+    pub fn divide_by_linear(&self, x: &F) -> Result<Self, eIcicleError> {
+        let linear = Self::from_coeffs(HostSlice::from_slice(&[F::zero() - *x, F::one()]))?;
+        self.divide_exactly(&linear)
+    }
+
+    /// Quotient and remainder of the division by `X^k - c`, for a `c` that is not a root of unity and a
+    /// positive `k`.
+    pub fn divide_by_binomial(&self, k: usize, c: &F) -> Result<(Self, Self), eIcicleError> {
+        if k == 0 {
+            return Err(eIcicleError::InvalidArgument);
+        }
+
+        // the remainder is sum(c^j P_j) over the blocks P_j of k coefficients
+        let blocks = self.degree / k + 1;
+        let coeffs = self.padded(blocks * k)?;
+        let mut powers = Vec::with_capacity(blocks * k);
+        let mut power = F::one();
+        for _ in 0..blocks {
+            powers.extend(std::iter::repeat(power).take(k));
+            power = power * *c;
+        }
+
+        let mut terms = DeviceVec::device_malloc(blocks * k)?;
+        mul_scalars(
+            &coeffs[..],
+            HostSlice::from_slice(&powers),
+            &mut terms[..],
+            &VecOpsConfig::default(),
+        )?;
+        let mut cfg = VecOpsConfig::default();
+        cfg.columns_batch = true;
+        let mut remainder = DeviceVec::device_malloc(k)?;
+        sum_scalars(&terms[..], &mut remainder[..], &cfg)?;
+        let remainder = Self::from_device(remainder)?;
+
+        let mut binomial = vec![F::zero(); k + 1];
+        binomial[0] = F::zero() - *c;
+        binomial[k] = F::one();
+        let quotient = self
+            .sub(&remainder)?
+            .divide_exactly(&Self::from_coeffs(HostSlice::from_slice(&binomial))?)?;
+
+        Ok((quotient, remainder))
+    }
+
+    /// Divides by the vanishing polynomial `X^n - 1` of the subgroup of order `n`, which must be a power of
+    /// two, or `InvalidArgument` is returned. The division must be exact; the remainder is not checked.
+    ///
+    /// The quotient is computed on the coset of a root of unity of twice the evaluation size, where the
+    /// vanishing polynomial has no zeros.
+    pub fn divide_by_vanishing(&self, n: usize) -> Result<Self, eIcicleError> {
+        if !n.is_power_of_two() {
+            return Err(eIcicleError::InvalidArgument);
+        }
+        if self.degree < n {
+            return Self::zero();
+        }
+
+        let size = (self.degree + 1).next_power_of_two();
+        let coset_gen = get_root_of_unity::<F>((2 * size) as u64);
+        let evals = self.evaluate_on_coset(size, &coset_gen)?;
+
+        // Z_H(g w^i) = g^n w^(n i) - 1 only depends on i modulo size / n
+        let period = size / n;
+        let g_n = coset_gen.pow(n);
+        let w_n = get_root_of_unity::<F>(size as u64).pow(n);
+        let mut inverses = Vec::with_capacity(period);
+        let mut point = g_n;
+        for _ in 0..period {
+            inverses.push((point - F::one()).inv());
+            point = point * w_n;
+        }
+        let inverses: Vec<F> = inverses
+            .iter()
+            .cycle()
+            .take(size)
+            .copied()
+            .collect();
+
+        let mut coeffs = DeviceVec::device_malloc(size)?;
+        mul_scalars(
+            &evals[..],
+            HostSlice::from_slice(&inverses),
+            &mut coeffs[..],
+            &VecOpsConfig::default(),
+        )?;
+
+        let mut cfg = NTTConfig::<F>::default();
+        cfg.coset_gen = coset_gen;
+        ntt_inplace(&mut coeffs[..], NTTDir::kInverse, &cfg)?;
+
+        Ok(Self {
+            coeffs,
+            degree: self.degree - n,
+        })
+    }
+
+    /// Divides by `divisor` through their evaluations on the coset of a root of unity of twice the
+    /// evaluation size, where `divisor` must have no zeros. The division must be exact.
+    fn divide_exactly(&self, divisor: &Self) -> Result<Self, eIcicleError> {
+        if self.degree < divisor.degree {
+            return Self::zero();
+        }
+
+        let size = (self.degree + 1).next_power_of_two();
+        let coset_gen = get_root_of_unity::<F>((2 * size) as u64);
+        let evals = self.evaluate_on_coset(size, &coset_gen)?;
+        let divisor_evals = divisor.evaluate_on_coset(size, &coset_gen)?;
+
+        let mut coeffs = DeviceVec::device_malloc(size)?;
+        div_scalars(
+            &evals[..],
+            &divisor_evals[..],
+            &mut coeffs[..],
+            &VecOpsConfig::default(),
+        )?;
+
+        let mut cfg = NTTConfig::<F>::default();
+        cfg.coset_gen = coset_gen;
+        ntt_inplace(&mut coeffs[..], NTTDir::kInverse, &cfg)?;
+
+        Ok(Self {
+            coeffs,
+            degree: self.degree - divisor.degree,
+        })
+    }
+
+    /// Copies the coefficients into a zeroed buffer of `size` elements, which must hold them all.
+    fn padded(&self, size: usize) -> Result<DeviceVec<F>, eIcicleError> {
+        if size <= self.degree {
+            return Err(eIcicleError::InvalidArgument);
+        }
+
+        let mut padded = DeviceVec::device_malloc(size)?;
+        padded.memset(0, size)?;
+        padded[..=self.degree].copy(self.coefficients())?;
+
+        Ok(padded)
+    }
+}
+
+#[macro_export]
+macro_rules! impl_polynomial_tests {
+    (
+      $field:ident
+    ) => {
+        use icicle_core::ntt::tests::init_domain;
+        use icicle_runtime::test_utilities;
+        use std::sync::Once;
+
+        const MAX_SIZE: u64 = 1 << 18;
+        static INIT: Once = Once::new();
+
+        pub fn initialize() {
+            INIT.call_once(move || {
+                test_utilities::test_load_and_init_devices();
+                test_utilities::test_set_main_device();
+                init_domain::<$field>(MAX_SIZE, false);
+            });
+            test_utilities::test_set_main_device();
+        }
+
+        #[test]
+        fn test_polynomial_arithmetic() {
+            initialize();
+            check_polynomial_arithmetic::<$field>()
+        }
+
+        #[test]
+        fn test_polynomial_invalid_arguments() {
+            initialize();
+            check_polynomial_invalid_arguments::<$field>()
+        }
+
+        #[test]
+        fn test_polynomial_division_by_vanishing() {
+            initialize();
+            check_polynomial_division_by_vanishing::<$field>()
+        }
+
+        #[test]
+        fn test_polynomial_division_by_linear() {
+            initialize();
+            check_polynomial_division_by_linear::<$field>()
+        }
+
+        #[test]
+        fn test_polynomial_division_by_binomial() {
+            initialize();
+            check_polynomial_division_by_binomial::<$field>()
+        }
+
+        #[test]
+        fn test_polynomial_evaluation() {
+            initialize();
+            check_polynomial_evaluation::<$field>()
+        }
+    };
+}
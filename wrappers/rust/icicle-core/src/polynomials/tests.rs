@@ -0,0 +1,278 @@
+use icicle_runtime::{errors::eIcicleError, memory::HostSlice};
+
+use crate::{
+    ntt::{get_root_of_unity, NTT},
+    polynomials::Polynomial,
+    traits::{Arithmetic, FieldImpl, GenerateRandom},
+    vec_ops::VecOps,
+};
+
+fn to_host<F>(poly: &Polynomial<F>) -> Vec<F>
+where
+    F: FieldImpl + Arithmetic,
+    <F as FieldImpl>::Config: NTT<F, F> + VecOps<F>,
+{
+    let mut coeffs = vec![F::zero(); poly.degree() + 1];
+    poly.coefficients()
+        .copy_to_host(HostSlice::from_mut_slice(&mut coeffs))
+        .unwrap();
+    coeffs
+}
+
+fn evaluate_on_host<F: FieldImpl + Arithmetic>(coeffs: &[F], x: F) -> F {
+    coeffs
+        .iter()
+        .rev()
+        .fold(F::zero(), |acc, c| acc * x + *c)
+}
+
+fn mul_on_host<F: FieldImpl + Arithmetic>(a: &[F], b: &[F]) -> Vec<F> {
+    let mut result = vec![F::zero(); a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            result[i + j] = result[i + j] + *x * *y;
+        }
+    }
+    result
+}
+
+pub fn check_polynomial_arithmetic<F>()
+where
+    F: FieldImpl + Arithmetic,
+    <F as FieldImpl>::Config: NTT<F, F> + VecOps<F> + GenerateRandom<F>,
+{
+    let a_coeffs = F::Config::generate_random(37);
+    let b_coeffs = F::Config::generate_random(20);
+    let a = Polynomial::from_coeffs(HostSlice::from_slice(&a_coeffs)).unwrap();
+    let b = Polynomial::from_coeffs(HostSlice::from_slice(&b_coeffs)).unwrap();
+    assert_eq!(a.degree(), 36);
+    assert_eq!(b.degree(), 19);
+
+    let sum = a
+        .add(&b)
+        .unwrap();
+    let difference = b
+        .sub(&a)
+        .unwrap();
+    assert_eq!(sum.degree(), 36);
+    assert_eq!(difference.degree(), 36);
+    let sum = to_host(&sum);
+    let difference = to_host(&difference);
+    for i in 0..37 {
+        let b_i = if i < 20 { b_coeffs[i] } else { F::zero() };
+        assert_eq!(sum[i], a_coeffs[i] + b_i);
+        assert_eq!(difference[i], b_i - a_coeffs[i]);
+    }
+
+    let product = a
+        .mul(&b)
+        .unwrap();
+    assert_eq!(product.degree(), 55);
+    assert_eq!(to_host(&product), mul_on_host(&a_coeffs, &b_coeffs));
+
+    // trailing zeros do not count towards the degree
+    let mut padded = b_coeffs.clone();
+    padded.extend([F::zero(); 5]);
+    let padded = Polynomial::from_coeffs(HostSlice::from_slice(&padded)).unwrap();
+    assert_eq!(padded.degree(), 19);
+}
+
+pub fn check_polynomial_invalid_arguments<F>()
+where
+    F: FieldImpl + Arithmetic,
+    <F as FieldImpl>::Config: NTT<F, F> + VecOps<F> + GenerateRandom<F>,
+{
+    // empty input is the zero polynomial
+    let empty = Polynomial::<F>::from_coeffs(HostSlice::from_slice(&[])).unwrap();
+    assert_eq!(empty.degree(), 0);
+    assert_eq!(to_host(&empty), [F::zero()]);
+
+    let coeffs = F::Config::generate_random(20);
+    let poly = Polynomial::from_coeffs(HostSlice::from_slice(&coeffs)).unwrap();
+    assert_eq!(
+        to_host(
+            &poly
+                .add(&empty)
+                .unwrap()
+        ),
+        coeffs
+    );
+
+    let evals = F::Config::generate_random(24);
+    assert!(matches!(
+        Polynomial::interpolate(HostSlice::from_slice(&evals)),
+        Err(eIcicleError::InvalidArgument)
+    ));
+    assert!(matches!(poly.evaluate_on_domain(24), Err(eIcicleError::InvalidArgument)));
+    // a power of two, but below the size of the polynomial
+    assert!(matches!(
+        poly.evaluate_on_coset(16, &F::one()),
+        Err(eIcicleError::InvalidArgument)
+    ));
+    assert!(matches!(poly.divide_by_vanishing(12), Err(eIcicleError::InvalidArgument)));
+    assert!(matches!(
+        poly.divide_by_binomial(0, &F::one()),
+        Err(eIcicleError::InvalidArgument)
+    ));
+}
+
+pub fn check_polynomial_division_by_vanishing<F>()
+where
+    F: FieldImpl + Arithmetic,
+    <F as FieldImpl>::Config: NTT<F, F> + VecOps<F> + GenerateRandom<F>,
+{
+    let n = 16;
+    let quotient_coeffs = F::Config::generate_random(50);
+
+    // (X^n - 1) q(X)
+    let mut coeffs = vec![F::zero(); quotient_coeffs.len() + n];
+    for (i, q) in quotient_coeffs
+        .iter()
+        .enumerate()
+    {
+        coeffs[i] = coeffs[i] - *q;
+        coeffs[i + n] = coeffs[i + n] + *q;
+    }
+
+    let poly = Polynomial::from_coeffs(HostSlice::from_slice(&coeffs)).unwrap();
+    let quotient = poly
+        .divide_by_vanishing(n)
+        .unwrap();
+    assert_eq!(quotient.degree(), 49);
+    assert_eq!(to_host(&quotient), quotient_coeffs);
+}
+
+pub fn check_polynomial_division_by_linear<F>()
+where
+    F: FieldImpl + Arithmetic,
+    <F as FieldImpl>::Config: NTT<F, F> + VecOps<F> + GenerateRandom<F>,
+{
+    let n = 16;
+    let coeffs = F::Config::generate_random(40);
+    let poly = Polynomial::from_coeffs(HostSlice::from_slice(&coeffs)).unwrap();
+    let [factor, x] = <[F; 2]>::try_from(F::Config::generate_random(2)).unwrap();
+
+    let scaled = poly
+        .scale(&factor)
+        .unwrap();
+    let expected: Vec<F> = coeffs
+        .iter()
+        .map(|c| *c * factor)
+        .collect();
+    assert_eq!(to_host(&scaled), expected);
+
+    let with_monomial = poly
+        .add_monomial(&factor, 45)
+        .unwrap();
+    let mut expected = coeffs.clone();
+    expected.resize(46, F::zero());
+    expected[45] = factor;
+    assert_eq!(with_monomial.degree(), 45);
+    assert_eq!(to_host(&with_monomial), expected);
+
+    // (X^n - 1) p(X), and back
+    let multiple = poly
+        .mul_by_vanishing(n)
+        .unwrap();
+    let mut vanishing = vec![F::zero(); n + 1];
+    vanishing[0] = F::zero() - F::one();
+    vanishing[n] = F::one();
+    assert_eq!(multiple.degree(), 39 + n);
+    assert_eq!(to_host(&multiple), mul_on_host(&coeffs, &vanishing));
+    assert_eq!(
+        to_host(
+            &multiple
+                .divide_by_vanishing(n)
+                .unwrap()
+        ),
+        coeffs
+    );
+
+    // p(X) - p(x) is divisible by X - x
+    let shifted = poly
+        .add_monomial(&(F::zero() - evaluate_on_host(&coeffs, x)), 0)
+        .unwrap();
+    let quotient = shifted
+        .divide_by_linear(&x)
+        .unwrap();
+    assert_eq!(quotient.degree(), 38);
+    assert_eq!(
+        mul_on_host(&to_host(&quotient), &[F::zero() - x, F::one()]),
+        to_host(&shifted)
+    );
+}
+
+pub fn check_polynomial_division_by_binomial<F>()
+where
+    F: FieldImpl + Arithmetic,
+    <F as FieldImpl>::Config: NTT<F, F> + VecOps<F> + GenerateRandom<F>,
+{
+    let coeffs = F::Config::generate_random(45);
+    let poly = Polynomial::from_coeffs(HostSlice::from_slice(&coeffs)).unwrap();
+    let [c, x] = <[F; 2]>::try_from(F::Config::generate_random(2)).unwrap();
+
+    for k in [1, 3, 8, 50] {
+        let (quotient, remainder) = poly
+            .divide_by_binomial(k, &c)
+            .unwrap();
+        assert_eq!(remainder.degree(), k - 1);
+        assert_eq!(quotient.degree(), 44usize.saturating_sub(k));
+
+        // p(x) = (x^k - c) q(x) + r(x)
+        let x_k = (0..k).fold(F::one(), |acc, _| acc * x);
+        assert_eq!(
+            (x_k - c) * evaluate_on_host(&to_host(&quotient), x) + evaluate_on_host(&to_host(&remainder), x),
+            evaluate_on_host(&coeffs, x)
+        );
+    }
+}
+
+pub fn check_polynomial_evaluation<F>()
+where
+    F: FieldImpl + Arithmetic,
+    <F as FieldImpl>::Config: NTT<F, F> + VecOps<F> + GenerateRandom<F>,
+{
+    let size = 64;
+    let coeffs = F::Config::generate_random(size);
+    let poly = Polynomial::from_coeffs(HostSlice::from_slice(&coeffs)).unwrap();
+
+    let x = F::Config::generate_random(1)[0];
+    assert_eq!(
+        poly.evaluate(&x)
+            .unwrap(),
+        evaluate_on_host(&coeffs, x)
+    );
+
+    // domain and coset evaluations against direct evaluation
+    let coset_gen = F::Config::generate_random(1)[0];
+    let omega = get_root_of_unity::<F>((2 * size) as u64);
+    let domain_evals = poly
+        .evaluate_on_domain(2 * size)
+        .unwrap();
+    let coset_evals = poly
+        .evaluate_on_coset(2 * size, &coset_gen)
+        .unwrap();
+    let mut domain_host = vec![F::zero(); 2 * size];
+    let mut coset_host = vec![F::zero(); 2 * size];
+    domain_evals
+        .copy_to_host(HostSlice::from_mut_slice(&mut domain_host))
+        .unwrap();
+    coset_evals
+        .copy_to_host(HostSlice::from_mut_slice(&mut coset_host))
+        .unwrap();
+
+    let mut point = F::one();
+    for i in 0..2 * size {
+        assert_eq!(domain_host[i], evaluate_on_host(&coeffs, point));
+        assert_eq!(coset_host[i], evaluate_on_host(&coeffs, coset_gen * point));
+        point = point * omega;
+    }
+
+    // interpolation inverts both
+    let interpolated = Polynomial::interpolate(&domain_evals[..]).unwrap();
+    let coset_interpolated = Polynomial::interpolate_on_coset(&coset_evals[..], &coset_gen).unwrap();
+    let mut expected = coeffs.clone();
+    expected.resize(2 * size, F::zero());
+    assert_eq!(to_host(&interpolated), expected);
+    assert_eq!(to_host(&coset_interpolated), expected);
+}
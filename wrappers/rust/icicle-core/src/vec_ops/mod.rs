@@ -99,6 +99,15 @@ pub trait VecOps<F> {
         result: &mut (impl HostOrDeviceSlice<F> + ?Sized),
         cfg: &VecOpsConfig,
     ) -> Result<(), eIcicleError>;
+
+    fn sparse_matvec(
+        row_ptr: &(impl HostOrDeviceSlice<u32> + ?Sized),
+        col_idx: &(impl HostOrDeviceSlice<u32> + ?Sized),
+        values: &(impl HostOrDeviceSlice<F> + ?Sized),
+        x: &(impl HostOrDeviceSlice<F> + ?Sized),
+        result: &mut (impl HostOrDeviceSlice<F> + ?Sized),
+        cfg: &VecOpsConfig,
+    ) -> Result<(), eIcicleError>;
 }
 
 #[doc(hidden)]
@@ -340,6 +349,49 @@ where
     <<F as FieldImpl>::Config as VecOps<F>>::scalar_mul(a, b, result, &cfg)
 }
 
+/// Multiplies the CSR matrix (`row_ptr`, `col_idx`, `values`) by `x`, writing one element per row
+/// to `result`.
+///
+/// The three matrix slices must be in the same memory, host or device.
+pub fn sparse_matvec<F>(
+    row_ptr: &(impl HostOrDeviceSlice<u32> + ?Sized),
+    col_idx: &(impl HostOrDeviceSlice<u32> + ?Sized),
+    values: &(impl HostOrDeviceSlice<F> + ?Sized),
+    x: &(impl HostOrDeviceSlice<F> + ?Sized),
+    result: &mut (impl HostOrDeviceSlice<F> + ?Sized),
+    cfg: &VecOpsConfig,
+) -> Result<(), eIcicleError>
+where
+    F: FieldImpl,
+    <F as FieldImpl>::Config: VecOps<F>,
+{
+    if row_ptr.len() != result.len() + 1 {
+        panic!(
+            "row_ptr length {} does not match output length {} + 1",
+            row_ptr.len(),
+            result.len()
+        );
+    }
+    if col_idx.len() != values.len() {
+        panic!(
+            "col_idx and values lengths {}; {} do not match",
+            col_idx.len(),
+            values.len()
+        );
+    }
+    if row_ptr.is_on_device() != values.is_on_device() || col_idx.is_on_device() != values.is_on_device() {
+        panic!("row_ptr, col_idx and values must all be on host or all on device");
+    }
+    if row_ptr.is_on_device() && !row_ptr.is_on_active_device() {
+        panic!("row_ptr is allocated on an inactive device");
+    }
+    if col_idx.is_on_device() && !col_idx.is_on_active_device() {
+        panic!("col_idx is allocated on an inactive device");
+    }
+    let cfg = setup_config(values, x, result, cfg, 1);
+    <<F as FieldImpl>::Config as VecOps<F>>::sparse_matvec(row_ptr, col_idx, values, x, result, &cfg)
+}
+
 #[macro_export]
 macro_rules! impl_vec_ops_field {
     (
@@ -440,6 +492,17 @@ macro_rules! impl_vec_ops_field {
                     cfg: *const VecOpsConfig,
                     result: *mut $field,
                 ) -> eIcicleError;
+
+                #[link_name = concat!($field_prefix, "_sparse_matvec")]
+                pub(crate) fn sparse_matvec_ffi(
+                    row_ptr: *const u32,
+                    col_idx: *const u32,
+                    values: *const $field,
+                    nof_rows: u64,
+                    x: *const $field,
+                    cfg: *const VecOpsConfig,
+                    result: *mut $field,
+                ) -> eIcicleError;
             }
         }
 
@@ -617,6 +680,28 @@ macro_rules! impl_vec_ops_field {
                     .wrap()
                 }
             }
+
+            fn sparse_matvec(
+                row_ptr: &(impl HostOrDeviceSlice<u32> + ?Sized),
+                col_idx: &(impl HostOrDeviceSlice<u32> + ?Sized),
+                values: &(impl HostOrDeviceSlice<$field> + ?Sized),
+                x: &(impl HostOrDeviceSlice<$field> + ?Sized),
+                result: &mut (impl HostOrDeviceSlice<$field> + ?Sized),
+                cfg: &VecOpsConfig,
+            ) -> Result<(), eIcicleError> {
+                unsafe {
+                    $field_prefix_ident::sparse_matvec_ffi(
+                        row_ptr.as_ptr(),
+                        col_idx.as_ptr(),
+                        values.as_ptr(),
+                        result.len() as u64,
+                        x.as_ptr(),
+                        cfg as *const VecOpsConfig,
+                        result.as_mut_ptr(),
+                    )
+                    .wrap()
+                }
+            }
         }
     };
 }
@@ -3,7 +3,7 @@ use crate::field::FieldArithmetic;
 use crate::traits::GenerateRandom;
 use crate::vec_ops::{
     accumulate_scalars, add_scalars, div_scalars, mixed_mul_scalars,
-    mul_scalars, product_scalars, scalar_add, scalar_mul, scalar_sub, sub_scalars, sum_scalars, FieldImpl, MixedVecOps, VecOps, VecOpsConfig,
+    mul_scalars, product_scalars, scalar_add, scalar_mul, scalar_sub, sparse_matvec, sub_scalars, sum_scalars, FieldImpl, MixedVecOps, VecOps, VecOpsConfig,
 };
 use icicle_runtime::device::Device;
 use icicle_runtime::memory::{DeviceVec, HostOrDeviceSlice, HostSlice};
@@ -49,6 +49,7 @@ where
     check_vec_ops_scalars_sub_scalar::<F>(test_size);
     check_vec_ops_scalars_mul_scalar::<F>(test_size);
     check_vec_ops_scalars_accumulate::<F>(test_size);
+    check_vec_ops_sparse_matvec::<F>(test_size);
 }
 
 pub fn check_mixed_vec_ops_scalars<F: FieldImpl, T: FieldImpl>()
@@ -312,6 +313,65 @@ where
     assert_eq!(a_clone_slice.as_slice(), a_main_slice.as_slice());
 }
 
+pub fn check_vec_ops_sparse_matvec<F: FieldImpl>(test_size: usize)
+where
+    <F as FieldImpl>::Config: VecOps<F> + GenerateRandom<F>,
+{
+    let nof_rows = test_size / 4;
+
+    // row i has i % 5 entries, so some rows are empty
+    let mut row_ptr = vec![0u32];
+    let mut col_idx = Vec::new();
+    for row in 0..nof_rows {
+        for k in 0..row % 5 {
+            col_idx.push(((row * 7 + k * 13) % test_size) as u32);
+        }
+        row_ptr.push(col_idx.len() as u32);
+    }
+    let values = F::Config::generate_random(col_idx.len());
+    let x = F::Config::generate_random(test_size);
+    let mut result_main = vec![F::zero(); nof_rows];
+    let mut result_ref = vec![F::zero(); nof_rows];
+
+    let run = |result: &mut [F]| {
+        let mut d_row_ptr = DeviceVec::<u32>::device_malloc(row_ptr.len()).unwrap();
+        let mut d_col_idx = DeviceVec::<u32>::device_malloc(col_idx.len()).unwrap();
+        let mut d_values = DeviceVec::<F>::device_malloc(values.len()).unwrap();
+        let mut d_x = DeviceVec::<F>::device_malloc(x.len()).unwrap();
+        d_row_ptr
+            .copy_from_host(HostSlice::from_slice(&row_ptr))
+            .unwrap();
+        d_col_idx
+            .copy_from_host(HostSlice::from_slice(&col_idx))
+            .unwrap();
+        d_values
+            .copy_from_host(HostSlice::from_slice(&values))
+            .unwrap();
+        d_x.copy_from_host(HostSlice::from_slice(&x))
+            .unwrap();
+
+        let cfg = VecOpsConfig::default();
+        sparse_matvec(
+            &d_row_ptr[..],
+            &d_col_idx[..],
+            &d_values[..],
+            &d_x[..],
+            HostSlice::from_mut_slice(result),
+            &cfg,
+        )
+        .unwrap();
+    };
+
+    test_utilities::test_set_main_device();
+    run(&mut result_main);
+
+    test_utilities::test_set_ref_device();
+    run(&mut result_ref);
+
+    assert_eq!(result_main, result_ref);
+    assert_eq!(result_ref[0], F::zero());
+}
+
 pub fn check_slice<F: FieldImpl>()
 where
     <F as FieldImpl>::Config: VecOps<F> + GenerateRandom<F>,
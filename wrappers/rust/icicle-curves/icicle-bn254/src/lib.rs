@@ -1,6 +1,7 @@
 pub mod curve;
 pub mod msm;
 pub mod ntt;
+pub mod polynomials;
 pub mod vec_ops;
 #[cfg(not(feature = "no_g2"))]
 pub mod pairing;
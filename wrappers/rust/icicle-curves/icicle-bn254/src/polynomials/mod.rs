@@ -0,0 +1,8 @@
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::curve::ScalarField;
+    use icicle_core::impl_polynomial_tests;
+    use icicle_core::polynomials::tests::*;
+
+    impl_polynomial_tests!(ScalarField);
+}